@@ -0,0 +1,326 @@
+use std::{collections::BTreeMap, convert::TryFrom, fmt, fs, io::ErrorKind};
+
+use raylib::consts::GamepadButton::*;
+use raylib::consts::KeyboardKey::*;
+use raylib::consts::MouseButton::*;
+use raylib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{error::EngineError, input::InputState};
+
+/// Names keys are written with in binding files, without raylib's `KEY_` prefix
+const KEY_NAMES: [(&str, KeyboardKey); 108] = [
+    ("APOSTROPHE", KEY_APOSTROPHE), ("COMMA", KEY_COMMA), ("MINUS", KEY_MINUS), ("PERIOD", KEY_PERIOD),
+    ("SLASH", KEY_SLASH), ("ZERO", KEY_ZERO), ("ONE", KEY_ONE), ("TWO", KEY_TWO), ("THREE", KEY_THREE),
+    ("FOUR", KEY_FOUR), ("FIVE", KEY_FIVE), ("SIX", KEY_SIX), ("SEVEN", KEY_SEVEN), ("EIGHT", KEY_EIGHT),
+    ("NINE", KEY_NINE), ("SEMICOLON", KEY_SEMICOLON), ("EQUAL", KEY_EQUAL), ("A", KEY_A), ("B", KEY_B),
+    ("C", KEY_C), ("D", KEY_D), ("E", KEY_E), ("F", KEY_F), ("G", KEY_G), ("H", KEY_H), ("I", KEY_I),
+    ("J", KEY_J), ("K", KEY_K), ("L", KEY_L), ("M", KEY_M), ("N", KEY_N), ("O", KEY_O), ("P", KEY_P),
+    ("Q", KEY_Q), ("R", KEY_R), ("S", KEY_S), ("T", KEY_T), ("U", KEY_U), ("V", KEY_V), ("W", KEY_W),
+    ("X", KEY_X), ("Y", KEY_Y), ("Z", KEY_Z), ("SPACE", KEY_SPACE), ("ESCAPE", KEY_ESCAPE),
+    ("ENTER", KEY_ENTER), ("TAB", KEY_TAB), ("BACKSPACE", KEY_BACKSPACE), ("INSERT", KEY_INSERT),
+    ("DELETE", KEY_DELETE), ("RIGHT", KEY_RIGHT), ("LEFT", KEY_LEFT), ("DOWN", KEY_DOWN), ("UP", KEY_UP),
+    ("PAGE_UP", KEY_PAGE_UP), ("PAGE_DOWN", KEY_PAGE_DOWN), ("HOME", KEY_HOME), ("END", KEY_END),
+    ("CAPS_LOCK", KEY_CAPS_LOCK), ("SCROLL_LOCK", KEY_SCROLL_LOCK), ("NUM_LOCK", KEY_NUM_LOCK),
+    ("PRINT_SCREEN", KEY_PRINT_SCREEN), ("PAUSE", KEY_PAUSE), ("F1", KEY_F1), ("F2", KEY_F2), ("F3", KEY_F3),
+    ("F4", KEY_F4), ("F5", KEY_F5), ("F6", KEY_F6), ("F7", KEY_F7), ("F8", KEY_F8), ("F9", KEY_F9),
+    ("F10", KEY_F10), ("F11", KEY_F11), ("F12", KEY_F12), ("LEFT_SHIFT", KEY_LEFT_SHIFT),
+    ("LEFT_CONTROL", KEY_LEFT_CONTROL), ("LEFT_ALT", KEY_LEFT_ALT), ("LEFT_SUPER", KEY_LEFT_SUPER),
+    ("RIGHT_SHIFT", KEY_RIGHT_SHIFT), ("RIGHT_CONTROL", KEY_RIGHT_CONTROL), ("RIGHT_ALT", KEY_RIGHT_ALT),
+    ("RIGHT_SUPER", KEY_RIGHT_SUPER), ("KB_MENU", KEY_KB_MENU), ("LEFT_BRACKET", KEY_LEFT_BRACKET),
+    ("BACKSLASH", KEY_BACKSLASH), ("RIGHT_BRACKET", KEY_RIGHT_BRACKET), ("GRAVE", KEY_GRAVE),
+    ("KP_0", KEY_KP_0), ("KP_1", KEY_KP_1), ("KP_2", KEY_KP_2), ("KP_3", KEY_KP_3), ("KP_4", KEY_KP_4),
+    ("KP_5", KEY_KP_5), ("KP_6", KEY_KP_6), ("KP_7", KEY_KP_7), ("KP_8", KEY_KP_8), ("KP_9", KEY_KP_9),
+    ("KP_DECIMAL", KEY_KP_DECIMAL), ("KP_DIVIDE", KEY_KP_DIVIDE), ("KP_MULTIPLY", KEY_KP_MULTIPLY),
+    ("KP_SUBTRACT", KEY_KP_SUBTRACT), ("KP_ADD", KEY_KP_ADD), ("KP_ENTER", KEY_KP_ENTER),
+    ("KP_EQUAL", KEY_KP_EQUAL), ("BACK", KEY_BACK), ("VOLUME_UP", KEY_VOLUME_UP),
+    ("VOLUME_DOWN", KEY_VOLUME_DOWN),
+];
+
+const MOUSE_BUTTON_NAMES: [(&str, MouseButton); 3] = [
+    ("LEFT", MOUSE_LEFT_BUTTON),
+    ("RIGHT", MOUSE_RIGHT_BUTTON),
+    ("MIDDLE", MOUSE_MIDDLE_BUTTON),
+];
+
+const GAMEPAD_BUTTON_NAMES: [(&str, GamepadButton); 17] = [
+    ("LEFT_FACE_UP", GAMEPAD_BUTTON_LEFT_FACE_UP),
+    ("LEFT_FACE_RIGHT", GAMEPAD_BUTTON_LEFT_FACE_RIGHT),
+    ("LEFT_FACE_DOWN", GAMEPAD_BUTTON_LEFT_FACE_DOWN),
+    ("LEFT_FACE_LEFT", GAMEPAD_BUTTON_LEFT_FACE_LEFT),
+    ("RIGHT_FACE_UP", GAMEPAD_BUTTON_RIGHT_FACE_UP),
+    ("RIGHT_FACE_RIGHT", GAMEPAD_BUTTON_RIGHT_FACE_RIGHT),
+    ("RIGHT_FACE_DOWN", GAMEPAD_BUTTON_RIGHT_FACE_DOWN),
+    ("RIGHT_FACE_LEFT", GAMEPAD_BUTTON_RIGHT_FACE_LEFT),
+    ("LEFT_TRIGGER_1", GAMEPAD_BUTTON_LEFT_TRIGGER_1),
+    ("LEFT_TRIGGER_2", GAMEPAD_BUTTON_LEFT_TRIGGER_2),
+    ("RIGHT_TRIGGER_1", GAMEPAD_BUTTON_RIGHT_TRIGGER_1),
+    ("RIGHT_TRIGGER_2", GAMEPAD_BUTTON_RIGHT_TRIGGER_2),
+    ("MIDDLE_LEFT", GAMEPAD_BUTTON_MIDDLE_LEFT),
+    ("MIDDLE", GAMEPAD_BUTTON_MIDDLE),
+    ("MIDDLE_RIGHT", GAMEPAD_BUTTON_MIDDLE_RIGHT),
+    ("LEFT_THUMB", GAMEPAD_BUTTON_LEFT_THUMB),
+    ("RIGHT_THUMB", GAMEPAD_BUTTON_RIGHT_THUMB),
+];
+
+// STRUCTS ------
+/// A physical input an action can be bound to. Gamepad buttons are read from the first
+/// gamepad.
+///
+/// Written in binding files as `key:NAME`, `mouse:NAME` or `pad:NAME`, e.g. `key:W`,
+/// `mouse:LEFT` or `pad:RIGHT_FACE_DOWN`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum InputBinding {
+    Key(KeyboardKey),
+    MouseButton(MouseButton),
+    GamepadButton(GamepadButton),
+}
+
+impl InputBinding {
+    pub fn is_down(&self, input: &InputState) -> bool {
+        match *self {
+            InputBinding::Key(key) => input.is_key_down(key),
+            InputBinding::MouseButton(button) => input.is_mouse_button_down(button),
+            InputBinding::GamepadButton(button) => input.is_gamepad_button_down(button),
+        }
+    }
+
+    pub fn is_pressed(&self, input: &InputState) -> bool {
+        match *self {
+            InputBinding::Key(key) => input.is_key_pressed(key),
+            InputBinding::MouseButton(button) => input.is_mouse_button_pressed(button),
+            InputBinding::GamepadButton(button) => input.is_gamepad_button_pressed(button),
+        }
+    }
+
+    pub fn is_released(&self, input: &InputState) -> bool {
+        match *self {
+            InputBinding::Key(key) => input.is_key_released(key),
+            InputBinding::MouseButton(button) => input.is_mouse_button_released(button),
+            InputBinding::GamepadButton(button) => input.is_gamepad_button_released(button),
+        }
+    }
+}
+
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            InputBinding::Key(key) => write!(f, "key:{}", name_of(&KEY_NAMES, key)),
+            InputBinding::MouseButton(button) => write!(f, "mouse:{}", name_of(&MOUSE_BUTTON_NAMES, button)),
+            InputBinding::GamepadButton(button) => write!(f, "pad:{}", name_of(&GAMEPAD_BUTTON_NAMES, button)),
+        }
+    }
+}
+
+impl TryFrom<String> for InputBinding {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<InputBinding, String> {
+        let (device, name) = text
+            .split_once(':')
+            .ok_or_else(|| format!("binding {:?} should look like key:NAME, mouse:NAME or pad:NAME", text))?;
+        let m_binding = match device {
+            "key" => lookup(&KEY_NAMES, name).map(InputBinding::Key),
+            "mouse" => lookup(&MOUSE_BUTTON_NAMES, name).map(InputBinding::MouseButton),
+            "pad" => lookup(&GAMEPAD_BUTTON_NAMES, name).map(InputBinding::GamepadButton),
+            _ => return Err(format!("unknown input device {:?} in binding {:?}", device, text)),
+        };
+
+        m_binding.ok_or_else(|| format!("unknown {} input {:?}", device, name))
+    }
+}
+
+impl From<InputBinding> for String {
+    fn from(binding: InputBinding) -> String {
+        binding.to_string()
+    }
+}
+
+/// Bindings from gameplay actions `A` to the inputs that trigger them, stored as a resource.
+/// Systems ask whether an action is down, pressed or released this frame instead of checking
+/// keys, so players can rebind them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ActionMap<A: Ord> {
+    bindings: BTreeMap<A, Vec<InputBinding>>,
+}
+
+impl<A: Ord + Copy> ActionMap<A> {
+    pub fn new() -> ActionMap<A> {
+        ActionMap {
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    /// Adds `binding` to the inputs triggering `action`
+    pub fn bind(&mut self, action: A, binding: InputBinding) {
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes every binding of `action`, leaving it impossible to trigger
+    pub fn unbind(&mut self, action: A) {
+        self.bindings.insert(action, vec![]);
+    }
+
+    pub fn bindings_of(&self, action: A) -> &[InputBinding] {
+        self.bindings.get(&action).map_or(&[], |bindings| bindings.as_slice())
+    }
+
+    /// Every input bound to any action, which is what `InputState::capture` needs to read
+    pub fn all_bindings(&self) -> Vec<InputBinding> {
+        let mut all: Vec<InputBinding> = vec![];
+        for binding in self.bindings.values().flatten() {
+            if !all.contains(binding) {
+                all.push(*binding);
+            }
+        }
+
+        all
+    }
+
+    pub fn is_down(&self, input: &InputState, action: A) -> bool {
+        self.bindings_of(action).iter().any(|binding| binding.is_down(input))
+    }
+
+    pub fn is_pressed(&self, input: &InputState, action: A) -> bool {
+        self.bindings_of(action).iter().any(|binding| binding.is_pressed(input))
+    }
+
+    pub fn is_released(&self, input: &InputState, action: A) -> bool {
+        self.bindings_of(action).iter().any(|binding| binding.is_released(input))
+    }
+}
+
+impl<A> ActionMap<A>
+where
+    A: Ord + Copy + Serialize + for<'de> Deserialize<'de>,
+{
+    /// Reads the bindings at `path` over `defaults`. Actions the file lists replace their
+    /// default bindings, the others keep them. A missing file leaves the defaults untouched.
+    pub fn load(path: &str, defaults: ActionMap<A>) -> Result<ActionMap<A>, EngineError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                info!("No input bindings at {}, using defaults", path);
+                return Ok(defaults);
+            }
+            Err(error) => return Err(bindings_error(path, error.to_string())),
+        };
+
+        let loaded: ActionMap<A> =
+            serde_json::from_str(&text).map_err(|error| bindings_error(path, error.to_string()))?;
+        let mut action_map = defaults;
+        action_map.bindings.extend(loaded.bindings);
+        info!("Loaded input bindings from {}", path);
+
+        Ok(action_map)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), EngineError> {
+        let text = serde_json::to_string_pretty(self).map_err(|error| bindings_error(path, error.to_string()))?;
+        fs::write(path, text).map_err(|error| bindings_error(path, error.to_string()))?;
+        info!("Saved input bindings to {}", path);
+
+        Ok(())
+    }
+}
+
+impl<A: Ord + Copy> Default for ActionMap<A> {
+    fn default() -> ActionMap<A> {
+        ActionMap::new()
+    }
+}
+
+// FUNCTIONS ------
+fn name_of<T: PartialEq + Copy>(names: &[(&'static str, T)], value: T) -> &'static str {
+    names
+        .iter()
+        .find(|(_, named)| *named == value)
+        .map_or("UNKNOWN", |(name, _)| name)
+}
+
+fn lookup<T: Copy>(names: &[(&str, T)], name: &str) -> Option<T> {
+    names
+        .iter()
+        .find(|(named, _)| named.eq_ignore_ascii_case(name))
+        .map(|(_, value)| *value)
+}
+
+fn bindings_error(path: &str, reason: String) -> EngineError {
+    EngineError::data("Input bindings", path, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+    enum TestAction {
+        Jump,
+        Fire,
+    }
+
+    fn parse(text: &str) -> Result<InputBinding, String> {
+        InputBinding::try_from(text.to_string())
+    }
+
+    #[test]
+    fn bindings_parse_every_device() {
+        assert_eq!(parse("key:W"), Ok(InputBinding::Key(KEY_W)));
+        assert_eq!(parse("key:KP_ENTER"), Ok(InputBinding::Key(KEY_KP_ENTER)));
+        assert_eq!(parse("mouse:RIGHT"), Ok(InputBinding::MouseButton(MOUSE_RIGHT_BUTTON)));
+        assert_eq!(
+            parse("pad:RIGHT_FACE_DOWN"),
+            Ok(InputBinding::GamepadButton(GAMEPAD_BUTTON_RIGHT_FACE_DOWN))
+        );
+    }
+
+    #[test]
+    fn bindings_write_back_the_text_they_were_parsed_from() {
+        for text in ["key:F9", "key:LEFT_SHIFT", "mouse:MIDDLE", "pad:LEFT_THUMB"] {
+            assert_eq!(String::from(parse(text).unwrap()), text);
+        }
+    }
+
+    #[test]
+    fn bad_bindings_are_refused() {
+        assert!(parse("W").unwrap_err().contains("should look like"));
+        assert!(parse("joystick:W").unwrap_err().contains("unknown input device"));
+        assert!(parse("key:KEY_W").unwrap_err().contains("unknown key input"));
+        assert!(parse("mouse:W").is_err());
+    }
+
+    #[test]
+    fn action_maps_read_from_json() {
+        let action_map: ActionMap<TestAction> =
+            serde_json::from_str(r#"{ "Jump": ["key:SPACE", "pad:RIGHT_FACE_DOWN"] }"#).unwrap();
+
+        assert_eq!(
+            action_map.bindings_of(TestAction::Jump),
+            &[InputBinding::Key(KEY_SPACE), InputBinding::GamepadButton(GAMEPAD_BUTTON_RIGHT_FACE_DOWN)]
+        );
+        assert!(action_map.bindings_of(TestAction::Fire).is_empty());
+        assert!(serde_json::from_str::<ActionMap<TestAction>>(r#"{ "Jump": ["key:NOPE"] }"#).is_err());
+    }
+
+    #[test]
+    fn load_replaces_only_the_listed_actions() {
+        let path = std::env::temp_dir().join(format!("bindings_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, r#"{ "Jump": ["key:UP"] }"#).unwrap();
+
+        let mut defaults: ActionMap<TestAction> = ActionMap::new();
+        defaults.bind(TestAction::Jump, InputBinding::Key(KEY_SPACE));
+        defaults.bind(TestAction::Fire, InputBinding::MouseButton(MOUSE_LEFT_BUTTON));
+        let loaded = ActionMap::load(path, defaults.clone());
+        fs::remove_file(path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.bindings_of(TestAction::Jump), &[InputBinding::Key(KEY_UP)]);
+        assert_eq!(loaded.bindings_of(TestAction::Fire), defaults.bindings_of(TestAction::Fire));
+    }
+}
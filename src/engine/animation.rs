@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use hecs::World;
+use raylib::prelude::*;
+
+use super::{datatypes::Sprite, error::EngineError, schedule::SystemContext};
+
+// STRUCTS ------
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationFrame {
+    /// Atlas rectangle shown during the frame
+    pub rect: Rectangle,
+    /// Seconds of simulation time the frame lasts
+    pub duration: f32,
+    /// Drawn this far from the sprite position, for hops and bobs on single tile sprites
+    pub offset: Vector2,
+}
+
+impl AnimationFrame {
+    pub fn new(rect: Rectangle, duration: f32) -> AnimationFrame {
+        AnimationFrame {
+            rect,
+            duration,
+            offset: Vector2::zero(),
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vector2) -> AnimationFrame {
+        self.offset = offset;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationClip {
+    pub frames: Vec<AnimationFrame>,
+    /// One-shot clips stay on their last frame once played
+    pub looping: bool,
+}
+
+impl AnimationClip {
+    pub fn looping(frames: Vec<AnimationFrame>) -> AnimationClip {
+        AnimationClip { frames, looping: true }
+    }
+
+    pub fn one_shot(frames: Vec<AnimationFrame>) -> AnimationClip {
+        AnimationClip { frames, looping: false }
+    }
+
+    /// A single frame shown forever
+    pub fn still(rect: Rectangle) -> AnimationClip {
+        AnimationClip::one_shot(vec![AnimationFrame::new(rect, 0.0)])
+    }
+}
+
+/// Plays one of its clips on the entity's `Sprite`. Game systems pick the clip with `play`,
+/// `advance_animations` moves it forward with the simulation delta.
+pub struct Animator {
+    clips: HashMap<&'static str, AnimationClip>,
+    current: &'static str,
+    frame: usize,
+    /// Seconds spent on the current frame
+    elapsed: f32,
+}
+
+impl Animator {
+    /// Starts out playing `clip`. Clips can't be empty.
+    pub fn new(name: &'static str, clip: AnimationClip) -> Animator {
+        assert!(!clip.frames.is_empty(), "animation clip {} has no frames", name);
+        Animator {
+            clips: HashMap::from([(name, clip)]),
+            current: name,
+            frame: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn with_clip(mut self, name: &'static str, clip: AnimationClip) -> Animator {
+        assert!(!clip.frames.is_empty(), "animation clip {} has no frames", name);
+        self.clips.insert(name, clip);
+        self
+    }
+
+    pub fn current_clip(&self) -> &'static str {
+        self.current
+    }
+
+    /// Switches to clip `name` from its first frame. Playing the clip already playing keeps it
+    /// where it is.
+    pub fn play(&mut self, name: &'static str) -> Result<(), EngineError> {
+        if name == self.current {
+            return Ok(());
+        }
+        if !self.clips.contains_key(name) {
+            return Err(EngineError::NotFound {
+                kind: "animation clip",
+                name: name.to_string(),
+            });
+        }
+
+        self.current = name;
+        self.frame = 0;
+        self.elapsed = 0.0;
+        Ok(())
+    }
+
+    pub fn frame(&self) -> &AnimationFrame {
+        &self.clip().frames[self.frame]
+    }
+
+    /// A one-shot clip reached the end of its last frame. Looping clips never finish.
+    pub fn is_finished(&self) -> bool {
+        let clip = self.clip();
+        !clip.looping && self.frame == clip.frames.len() - 1 && self.elapsed >= self.frame().duration
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        self.elapsed += delta;
+        let clip = &self.clips[self.current];
+        // Frames without a duration hold the clip, which also keeps this from spinning forever
+        while clip.frames[self.frame].duration > 0.0 && self.elapsed >= clip.frames[self.frame].duration {
+            if self.frame + 1 < clip.frames.len() {
+                self.elapsed -= clip.frames[self.frame].duration;
+                self.frame += 1;
+            } else if clip.looping {
+                self.elapsed -= clip.frames[self.frame].duration;
+                self.frame = 0;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn clip(&self) -> &AnimationClip {
+        &self.clips[self.current]
+    }
+}
+
+// FUNCTIONS ------
+/// Where `sprite` is drawn, moved by the current frame offset of its animator if it has one
+pub fn sprite_draw_position(sprite: &Sprite, m_animator: Option<&Animator>) -> Vector2 {
+    match m_animator {
+        Some(animator) => sprite.position + animator.frame().offset,
+        None => sprite.position,
+    }
+}
+
+/// Moves every animator forward by the simulation delta and shows its frame on the sprite
+pub fn advance_animations(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let delta = ctx.delta;
+    for (_, (animator, sprite)) in world.query_mut::<(&mut Animator, &mut Sprite)>() {
+        animator.advance(delta);
+        sprite.rect = animator.frame().rect;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,414 @@
+use std::{
+    any::type_name,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    fs,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    time::SystemTime,
+};
+
+use raylib::{prelude::Font, texture::Texture2D, RaylibHandle, RaylibThread};
+
+use super::{error::EngineError, resources::Resources};
+
+// TRAITS ------
+/// Anything the asset manager can store. Ties an asset type to its storage inside `Assets`.
+pub trait Asset: Sized + 'static {
+    fn storage(assets: &Assets) -> &AssetStorage<Self>;
+    fn storage_mut(assets: &mut Assets) -> &mut AssetStorage<Self>;
+}
+
+// STRUCTS ------
+/// Typed reference to an asset. The id is derived from the asset path, so code that knows the
+/// path can build the handle without going through `Assets`.
+pub struct Handle<T> {
+    id: u64,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub fn from_path(path: &str) -> Handle<T> {
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        Handle {
+            id: hasher.finish(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Handle<T> {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Handle<T>) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle<{}>({})", type_name::<T>(), self.id)
+    }
+}
+
+/// Contents of a text data file (config, definitions...) loaded through the asset manager
+pub struct DataFile {
+    pub text: String,
+}
+
+/// A data file loaded through `Assets`, along with the version game state was last parsed
+/// from. Lets code that parses data files notice when a hot reload changed them.
+#[derive(Debug, Clone, Copy)]
+pub struct DataSource {
+    pub handle: Handle<DataFile>,
+    version: u32,
+}
+
+impl DataSource {
+    pub fn load(assets: &mut Assets, path: &str) -> Result<DataSource, EngineError> {
+        let handle = assets.load_data(path)?;
+
+        Ok(DataSource {
+            handle,
+            version: assets.version(handle).unwrap_or_default(),
+        })
+    }
+
+    pub fn path<'a>(&self, assets: &'a Assets) -> &'a str {
+        assets.path_of(self.handle).unwrap_or_default()
+    }
+
+    /// Text of the file when it was reloaded since the last call, to be parsed again
+    pub fn poll_changed<'a>(&mut self, assets: &'a Assets) -> Option<&'a str> {
+        let version = assets.version(self.handle)?;
+        if version == self.version {
+            return None;
+        }
+        self.version = version;
+
+        assets.get(self.handle).ok().map(|file| file.text.as_str())
+    }
+}
+
+struct AssetEntry<T> {
+    path: String,
+    ref_count: usize,
+    /// Bumped on every hot reload
+    version: u32,
+    m_modified: Option<SystemTime>,
+    asset: T,
+}
+
+pub struct AssetStorage<T> {
+    entries: HashMap<u64, AssetEntry<T>>,
+}
+
+impl<T> Default for AssetStorage<T> {
+    fn default() -> AssetStorage<T> {
+        AssetStorage {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<T> AssetStorage<T> {
+    /// Returns the handle for `path`, calling `loader` only when the asset isn't loaded yet.
+    /// Every call adds a reference that must be given back with `release`.
+    fn load(
+        &mut self,
+        path: &str,
+        loader: impl FnOnce() -> Result<T, String>,
+    ) -> Result<Handle<T>, EngineError> {
+        let handle = Handle::from_path(path);
+
+        if let Some(entry) = self.entries.get_mut(&handle.id) {
+            entry.ref_count += 1;
+            return Ok(handle);
+        }
+
+        let asset = loader().map_err(|reason| EngineError::AssetLoad {
+            path: path.to_string(),
+            reason,
+        })?;
+        self.entries.insert(
+            handle.id,
+            AssetEntry {
+                path: path.to_string(),
+                ref_count: 1,
+                version: 0,
+                m_modified: modified_time(path),
+                asset,
+            },
+        );
+        info!("Loaded {}", path);
+
+        Ok(handle)
+    }
+
+    /// Reloads every asset whose file changed on disk since it was last loaded and returns
+    /// their paths. When reloading fails the previous version is kept.
+    fn reload_changed(&mut self, mut loader: impl FnMut(&str) -> Result<T, String>) -> Vec<String> {
+        let mut reloaded: Vec<String> = vec![];
+
+        for entry in self.entries.values_mut() {
+            let m_modified = modified_time(&entry.path);
+            if m_modified.is_none() || m_modified == entry.m_modified {
+                continue;
+            }
+            entry.m_modified = m_modified;
+
+            match loader(&entry.path) {
+                Ok(asset) => {
+                    entry.asset = asset;
+                    entry.version += 1;
+                    reloaded.push(entry.path.clone());
+                }
+                Err(reason) => warn!("Failed to reload {}: {}", entry.path, reason),
+            }
+        }
+
+        reloaded
+    }
+}
+
+/// Polls the files of every loaded asset and reloads the ones that changed. Meant for
+/// development builds, where artists edit assets while the game runs.
+pub struct AssetWatcher {
+    /// Seconds between two polls
+    pub interval: f32,
+    timer: f32,
+}
+
+impl AssetWatcher {
+    pub fn new(interval: f32) -> AssetWatcher {
+        AssetWatcher {
+            interval,
+            timer: interval,
+        }
+    }
+}
+
+/// Owns every loaded texture, font and data file. Stored as a resource.
+#[derive(Default)]
+pub struct Assets {
+    textures: AssetStorage<Texture2D>,
+    fonts: AssetStorage<Font>,
+    data_files: AssetStorage<DataFile>,
+}
+
+impl Asset for Texture2D {
+    fn storage(assets: &Assets) -> &AssetStorage<Self> {
+        &assets.textures
+    }
+
+    fn storage_mut(assets: &mut Assets) -> &mut AssetStorage<Self> {
+        &mut assets.textures
+    }
+}
+
+impl Asset for Font {
+    fn storage(assets: &Assets) -> &AssetStorage<Self> {
+        &assets.fonts
+    }
+
+    fn storage_mut(assets: &mut Assets) -> &mut AssetStorage<Self> {
+        &mut assets.fonts
+    }
+}
+
+impl Asset for DataFile {
+    fn storage(assets: &Assets) -> &AssetStorage<Self> {
+        &assets.data_files
+    }
+
+    fn storage_mut(assets: &mut Assets) -> &mut AssetStorage<Self> {
+        &mut assets.data_files
+    }
+}
+
+impl Assets {
+    pub fn new() -> Assets {
+        Assets::default()
+    }
+
+    pub fn load_texture(
+        &mut self,
+        raylib_handle: &mut RaylibHandle,
+        thread: &RaylibThread,
+        path: &str,
+    ) -> Result<Handle<Texture2D>, EngineError> {
+        self.textures
+            .load(path, || raylib_handle.load_texture(thread, path))
+    }
+
+    pub fn load_font(
+        &mut self,
+        raylib_handle: &mut RaylibHandle,
+        thread: &RaylibThread,
+        path: &str,
+    ) -> Result<Handle<Font>, EngineError> {
+        self.fonts.load(path, || raylib_handle.load_font(thread, path))
+    }
+
+    pub fn load_data(&mut self, path: &str) -> Result<Handle<DataFile>, EngineError> {
+        self.data_files.load(path, || read_data_file(path))
+    }
+
+    /// Reloads the textures, fonts and data files that changed on disk. Handles keep pointing
+    /// to the same slot, so whatever references them draws the new version on the next frame.
+    pub fn reload_changed(&mut self, raylib_handle: &mut RaylibHandle, thread: &RaylibThread) -> Vec<String> {
+        let mut reloaded = self
+            .textures
+            .reload_changed(|path| raylib_handle.load_texture(thread, path));
+        reloaded.extend(
+            self.fonts
+                .reload_changed(|path| raylib_handle.load_font(thread, path)),
+        );
+        reloaded.extend(self.data_files.reload_changed(read_data_file));
+
+        reloaded
+    }
+
+    /// Version of the asset behind `handle`, bumped on every reload. Code that derives state
+    /// from an asset (parsed data files...) can compare versions to know when to rebuild it.
+    pub fn version<T: Asset>(&self, handle: Handle<T>) -> Option<u32> {
+        T::storage(self)
+            .entries
+            .get(&handle.id)
+            .map(|entry| entry.version)
+    }
+
+    pub fn get<T: Asset>(&self, handle: Handle<T>) -> Result<&T, EngineError> {
+        T::storage(self)
+            .entries
+            .get(&handle.id)
+            .map(|entry| &entry.asset)
+            .ok_or_else(|| EngineError::AssetNotLoaded(format!("{:?}", handle)))
+    }
+
+    pub fn is_loaded<T: Asset>(&self, handle: Handle<T>) -> bool {
+        T::storage(self).entries.contains_key(&handle.id)
+    }
+
+    pub fn path_of<T: Asset>(&self, handle: Handle<T>) -> Option<&str> {
+        T::storage(self)
+            .entries
+            .get(&handle.id)
+            .map(|entry| entry.path.as_str())
+    }
+
+    /// Adds a reference to an asset that is already loaded, for code that built the handle from
+    /// its path. Returns false when the asset isn't loaded.
+    pub fn retain<T: Asset>(&mut self, handle: Handle<T>) -> bool {
+        match T::storage_mut(self).entries.get_mut(&handle.id) {
+            Some(entry) => {
+                entry.ref_count += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Gives back one reference to the asset. The asset is unloaded once nothing references it.
+    pub fn release<T: Asset>(&mut self, handle: Handle<T>) {
+        let entries = &mut T::storage_mut(self).entries;
+        let unload = match entries.get_mut(&handle.id) {
+            Some(entry) => {
+                entry.ref_count -= 1;
+                entry.ref_count == 0
+            }
+            None => false,
+        };
+
+        if unload {
+            if let Some(entry) = entries.remove(&handle.id) {
+                info!("Unloaded {}", entry.path);
+            }
+        }
+    }
+}
+
+// FUNCTIONS ------
+/// Runs the `AssetWatcher` resource, if any, reloading changed assets once per interval.
+pub fn hot_reload_assets(
+    resources: &Resources,
+    raylib_handle: &mut RaylibHandle,
+    thread: &RaylibThread,
+    delta: f32,
+) -> Result<(), EngineError> {
+    if !resources.contains::<AssetWatcher>() {
+        return Ok(());
+    }
+
+    {
+        let mut watcher = resources.get_mut::<AssetWatcher>()?;
+        watcher.timer -= delta;
+        if watcher.timer > 0.0 {
+            return Ok(());
+        }
+        watcher.timer = watcher.interval;
+    }
+
+    let mut assets = resources.get_mut::<Assets>()?;
+    for path in assets.reload_changed(raylib_handle, thread) {
+        info!("Reloaded {}", path);
+    }
+
+    Ok(())
+}
+
+fn read_data_file(path: &str) -> Result<DataFile, String> {
+    fs::read_to_string(path)
+        .map(|text| DataFile { text })
+        .map_err(|error| error.to_string())
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn assets_unload_when_the_last_reference_is_released() {
+        let path = env::temp_dir().join(format!("asset_ref_count_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "{}").unwrap();
+        let mut assets = Assets::new();
+
+        let handle = assets.load_data(path).unwrap();
+        assert_eq!(assets.load_data(path).unwrap(), handle);
+        assert!(assets.retain(handle));
+        fs::remove_file(path).unwrap();
+
+        assets.release(handle);
+        assets.release(handle);
+        assert!(assets.is_loaded(handle));
+        assets.release(handle);
+        assert!(!assets.is_loaded(handle));
+        assert!(!assets.retain(handle));
+    }
+}
@@ -3,29 +3,158 @@ use std::collections::HashMap;
 use hecs::{World, Entity};
 use raylib::prelude::*;
 
+use super::{
+  enums::{CollisionLayer, RenderLayer},
+  error::EngineError,
+  render::{DrawCommand, RenderQueue},
+  schedule::{DrawContext, SystemContext},
+  spatial_hash::CollisionGrid,
+};
+
+const COLLISION_LAYERS: [CollisionLayer; 5] = [
+  CollisionLayer::Villager,
+  CollisionLayer::Building,
+  CollisionLayer::Placement,
+  CollisionLayer::Terrain,
+  CollisionLayer::Ui,
+];
+
 // TAGS ------
 pub struct DrawCollisions;
 
 // STRUCTS ------
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactPhase {
+  /// Started overlapping this frame
+  Enter,
+  /// Was already overlapping last frame
+  Stay,
+  /// Stopped overlapping this frame, dropped on the next one
+  Exit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contact {
+  pub other: Entity,
+  /// Layer of the other box, so handlers can tell what touched them without probing components
+  pub layer: CollisionLayer,
+  pub phase: ContactPhase,
+}
+
+/// Every box a collider overlaps, plus the ones it stopped overlapping this frame. Boxes
+/// still overlapping come first, in the order the detection systems found them.
+#[derive(Debug, Clone, Default)]
+pub struct Contacts {
+  list: Vec<Contact>,
+}
+
+impl Contacts {
+  pub fn iter(&self) -> impl Iterator<Item = &Contact> {
+    self.list.iter()
+  }
+
+  /// Entities overlapping the collider, new or not
+  pub fn touching(&self) -> impl Iterator<Item = Entity> + '_ {
+    self.with_phase(|phase| phase != ContactPhase::Exit)
+  }
+
+  pub fn entered(&self) -> impl Iterator<Item = Entity> + '_ {
+    self.with_phase(|phase| phase == ContactPhase::Enter)
+  }
+
+  pub fn exited(&self) -> impl Iterator<Item = Entity> + '_ {
+    self.with_phase(|phase| phase == ContactPhase::Exit)
+  }
+
+  pub fn is_touching(&self, entity: Entity) -> bool {
+    self.touching().any(|other| other == entity)
+  }
+
+  fn with_phase(&self, filter: fn(ContactPhase) -> bool) -> impl Iterator<Item = Entity> + '_ {
+    self.list.iter().filter(move |contact| filter(contact.phase)).map(|contact| contact.other)
+  }
+
+  /// Replaces the contacts with `overlapping`, working out the phase of each from the last frame
+  fn update(&mut self, overlapping: &[(Entity, CollisionLayer)]) {
+    let previous = std::mem::take(&mut self.list);
+    let was_touching = |other: Entity| {
+      previous.iter().any(|contact| contact.other == other && contact.phase != ContactPhase::Exit)
+    };
+
+    for &(other, layer) in overlapping {
+      let phase = if was_touching(other) { ContactPhase::Stay } else { ContactPhase::Enter };
+      self.list.push(Contact { other, layer, phase });
+    }
+    for contact in previous.iter() {
+      let still_touching = overlapping.iter().any(|(other, _)| *other == contact.other);
+      if contact.phase != ContactPhase::Exit && !still_touching {
+        self.list.push(Contact { phase: ContactPhase::Exit, ..*contact });
+      }
+    }
+  }
+}
+
 #[derive(Default)]
 pub struct BodyCollision {
   pub colliding: bool,
-  pub other_body: Option<Entity>
+  pub contacts: Contacts,
 }
 
 pub struct TriggerCollision {
   pub colliding: bool,
-  pub other_trigger: Option<Entity>,
+  pub contacts: Contacts,
 }
 
 impl TriggerCollision {
   pub fn new() -> TriggerCollision {
-    TriggerCollision { colliding: false, other_trigger: None }
+    TriggerCollision { colliding: false, contacts: Contacts::default() }
+  }
+}
+
+/// Set of `CollisionLayer`s
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CollisionMask(u32);
+
+impl CollisionMask {
+  pub const NONE: CollisionMask = CollisionMask(0);
+  pub const ALL: CollisionMask = CollisionMask(u32::MAX);
+
+  pub const fn with(self, layer: CollisionLayer) -> CollisionMask {
+    CollisionMask(self.0 | 1 << layer as u32)
+  }
+
+  pub fn contains(self, layer: CollisionLayer) -> bool {
+    self.0 & 1 << layer as u32 != 0
+  }
+
+  pub fn layers(self) -> impl Iterator<Item = CollisionLayer> {
+    COLLISION_LAYERS.into_iter().filter(move |layer| self.contains(*layer))
+  }
+}
+
+impl FromIterator<CollisionLayer> for CollisionMask {
+  fn from_iter<I: IntoIterator<Item = CollisionLayer>>(layers: I) -> CollisionMask {
+    layers.into_iter().fold(CollisionMask::NONE, CollisionMask::with)
   }
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct CollisionBox {
-  pub rect: Rectangle
+  pub rect: Rectangle,
+  pub layer: CollisionLayer,
+  /// Layers this box gets contacts with. Contacts are one sided: a box whose mask leaves this
+  /// box's layer out never lists it back.
+  pub mask: CollisionMask,
+}
+
+impl CollisionBox {
+  pub fn new(rect: Rectangle, layer: CollisionLayer, mask: CollisionMask) -> CollisionBox {
+    CollisionBox { rect, layer, mask }
+  }
+
+  pub fn wants_contact_with(&self, other: &CollisionBox) -> bool {
+    self.mask.contains(other.layer)
+  }
 }
 
 
@@ -47,63 +176,105 @@ pub fn are_boxes_colliding(box1: &Rectangle, box2: &Rectangle) -> bool {
   return false;
 }
 
-pub fn detect_body_collisions(world: &mut World) {
-    let mut collided_entities: HashMap<Entity, Entity> = HashMap::new();
-
-    {
+pub fn detect_body_collisions(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let collided_entities = {
+      let grid = ctx.resources.get::<CollisionGrid>()?;
       let mut query = world.query::<&CollisionBox>().with::<BodyCollision>();
-      let mut query_2 = world.query::<&CollisionBox>().with::<BodyCollision>();
-      for (ety1, box1) in query.into_iter() {
-        for (ety2, box2) in query_2.into_iter() {
-          if ety1 != ety2 && are_boxes_colliding(&box1.rect, &box2.rect) {
-            collided_entities.insert(ety1, ety2);
-            collided_entities.insert(ety2, ety1);
-          }
-        }
-      }
-    }
+      let boxes: Vec<(Entity, CollisionBox)> = query.iter().map(|(ety, col_box)| (ety, *col_box)).collect();
+      with_layers(find_collisions(&grid, &boxes), &boxes)
+    };
 
     let query = world.query_mut::<&mut BodyCollision>();
     for (ety, body_collision) in query.into_iter() {
-      if collided_entities.contains_key(&ety) {
-        body_collision.colliding = true;
-        body_collision.other_body = Some(collided_entities[&ety]);
-      } else {
-        body_collision.colliding = false;
-        body_collision.other_body = None;
-      }
+      let overlapping = collided_entities.get(&ety).map_or(&[][..], Vec::as_slice);
+      body_collision.colliding = !overlapping.is_empty();
+      body_collision.contacts.update(overlapping);
     }
-}
 
-pub fn detect_trigger_collisions(world: &mut World) {
-    let mut collided_entities: HashMap<Entity, Entity> = HashMap::new();
+    Ok(())
+}
 
-    {
+pub fn detect_trigger_collisions(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let collided_entities = {
+      let grid = ctx.resources.get::<CollisionGrid>()?;
       let mut query = world.query::<&CollisionBox>().with::<TriggerCollision>();
-      let mut query_2 = world.query::<&CollisionBox>().with::<TriggerCollision>();
-      query.into_iter().for_each(|(ety1, box1)| {
-        query_2.into_iter().for_each(|(ety2, box2)| {
-          if ety1 != ety2 && are_boxes_colliding(&box1.rect, &box2.rect) {
-            collided_entities.insert(ety1, ety2);
-            collided_entities.insert(ety2, ety1);
-          }
-        });
-      });
-    }
+      let boxes: Vec<(Entity, CollisionBox)> = query.iter().map(|(ety, col_box)| (ety, *col_box)).collect();
+      with_layers(find_collisions(&grid, &boxes), &boxes)
+    };
 
     let query = world.query_mut::<&mut TriggerCollision>();
     query.into_iter().for_each(|(ety, trigger_collision)| {
-      if collided_entities.contains_key(&ety) {
-        trigger_collision.colliding = true;
-        trigger_collision.other_trigger = Some(collided_entities[&ety]);
-      } else {
-        trigger_collision.colliding = false;
-        trigger_collision.other_trigger = None;
-      }
+      let overlapping = collided_entities.get(&ety).map_or(&[][..], Vec::as_slice);
+      trigger_collision.colliding = !overlapping.is_empty();
+      trigger_collision.contacts.update(overlapping);
     });
+
+    Ok(())
 }
 
-pub fn draw_collisions(world: &mut World, draw_handle: &mut RaylibMode2D<RaylibDrawHandle>, tile_size: f32) {
+/// Pairs every overlapped entity in `collisions` with the layer of its box in `boxes`
+fn with_layers(
+    collisions: HashMap<Entity, Vec<Entity>>,
+    boxes: &[(Entity, CollisionBox)],
+) -> HashMap<Entity, Vec<(Entity, CollisionLayer)>> {
+    let layer_of: HashMap<Entity, CollisionLayer> = boxes.iter().map(|(ety, col_box)| (*ety, col_box.layer)).collect();
+    collisions
+      .into_iter()
+      .map(|(ety, others)| (ety, others.into_iter().map(|other| (other, layer_of[&other])).collect()))
+      .collect()
+}
+
+/// Maps every box of `boxes` overlapping others to all the boxes it overlaps and has in its mask,
+/// in `boxes` order. Only boxes sharing a cell of `grid` are tested.
+pub fn find_collisions(grid: &CollisionGrid, boxes: &[(Entity, CollisionBox)]) -> HashMap<Entity, Vec<Entity>> {
+    let index_of: HashMap<Entity, usize> = boxes.iter().enumerate().map(|(index, (ety, _))| (*ety, index)).collect();
+    let mut collided_entities: HashMap<Entity, Vec<Entity>> = HashMap::new();
+
+    for (index, (ety, col_box)) in boxes.iter().enumerate() {
+      if col_box.mask == CollisionMask::NONE {
+        continue;
+      }
+
+      let mut others: Vec<usize> = grid
+        .query_rect(&col_box.rect)
+        .filter_map(|candidate| index_of.get(&candidate).copied())
+        .filter(|&other| other != index)
+        .collect();
+      others.sort_unstable();
+      others.dedup();
+      others.retain(|&other| {
+        let other_box = &boxes[other].1;
+        col_box.wants_contact_with(other_box) && are_boxes_colliding(&col_box.rect, &other_box.rect)
+      });
+
+      if !others.is_empty() {
+        collided_entities.insert(*ety, others.into_iter().map(|other| boxes[other].0).collect());
+      }
+    }
+
+    collided_entities
+}
+
+/// Same result as `find_collisions`, testing every pair. Kept to check and benchmark the grid.
+pub fn find_collisions_brute_force(boxes: &[(Entity, CollisionBox)]) -> HashMap<Entity, Vec<Entity>> {
+    let mut collided_entities: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for (ety1, box1) in boxes.iter() {
+      for (ety2, box2) in boxes.iter() {
+        if ety1 != ety2 && box1.wants_contact_with(box2) && are_boxes_colliding(&box1.rect, &box2.rect) {
+          collided_entities.entry(*ety1).or_default().push(*ety2);
+        }
+      }
+    }
+
+    collided_entities
+}
+
+pub fn draw_collisions(world: &mut World, _draw_handle: &mut RaylibMode2D<RaylibDrawHandle>, ctx: &DrawContext) -> Result<(), EngineError> {
+  let mut render_queue = ctx.resources.get_mut::<RenderQueue>()?;
+  let mut queue_box = |col_box: &CollisionBox, color: Color| {
+    let rect = col_box.rect;
+    render_queue.push(RenderLayer::Debug, rect.y + rect.height, DrawCommand::Rectangle { rect, color });
+  };
   let mut draw_query = world.query::<&DrawCollisions>();
   let draw_collisions_enabled: bool;
 
@@ -124,12 +295,7 @@ pub fn draw_collisions(world: &mut World, draw_handle: &mut RaylibMode2D<RaylibD
 
       drawn_entities.push(ety);
 
-      draw_handle.draw_rectangle(
-        col_box.rect.x as i32, 
-        col_box.rect.y as i32, 
-        tile_size as i32, 
-        tile_size as i32, 
-        selected_color);
+      queue_box(col_box, selected_color);
     });
     let query = &mut world.query::<(&TriggerCollision, &CollisionBox)>();
     query.into_iter().for_each(|(ety, (col_trigger, col_box))| {
@@ -143,12 +309,56 @@ pub fn draw_collisions(world: &mut World, draw_handle: &mut RaylibMode2D<RaylibD
         }
       }
 
-      draw_handle.draw_rectangle(
-        col_box.rect.x as i32, 
-        col_box.rect.y as i32, 
-        tile_size as i32, 
-        tile_size as i32, 
-        selected_color);
+      queue_box(col_box, selected_color);
     });
   }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn phases(contacts: &Contacts) -> Vec<(Entity, ContactPhase)> {
+    contacts.iter().map(|contact| (contact.other, contact.phase)).collect()
+  }
+
+  #[test]
+  fn update_tracks_enter_stay_and_exit() {
+    let mut world = World::new();
+    let a = world.spawn(());
+    let b = world.spawn(());
+    let mut contacts = Contacts::default();
+
+    contacts.update(&[(a, CollisionLayer::Villager)]);
+    assert_eq!(phases(&contacts), vec![(a, ContactPhase::Enter)]);
+
+    contacts.update(&[(a, CollisionLayer::Villager), (b, CollisionLayer::Building)]);
+    assert_eq!(phases(&contacts), vec![(a, ContactPhase::Stay), (b, ContactPhase::Enter)]);
+
+    contacts.update(&[(b, CollisionLayer::Building)]);
+    assert_eq!(phases(&contacts), vec![(b, ContactPhase::Stay), (a, ContactPhase::Exit)]);
+    assert_eq!(contacts.exited().collect::<Vec<Entity>>(), vec![a]);
+    assert!(!contacts.is_touching(a));
+
+    // Exits only last one frame, and coming back is a new enter
+    contacts.update(&[(b, CollisionLayer::Building)]);
+    assert_eq!(phases(&contacts), vec![(b, ContactPhase::Stay)]);
+    contacts.update(&[(a, CollisionLayer::Villager), (b, CollisionLayer::Building)]);
+    assert_eq!(phases(&contacts), vec![(a, ContactPhase::Enter), (b, ContactPhase::Stay)]);
+  }
+
+  #[test]
+  fn update_keeps_the_layer_of_exited_contacts() {
+    let mut world = World::new();
+    let a = world.spawn(());
+    let mut contacts = Contacts::default();
+
+    contacts.update(&[(a, CollisionLayer::Villager)]);
+    contacts.update(&[]);
+
+    let contact = contacts.iter().next().unwrap();
+    assert_eq!((contact.phase, contact.layer), (ContactPhase::Exit, CollisionLayer::Villager));
+  }
 }
@@ -1,14 +1,20 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
 use raylib::prelude::*;
 
+use super::assets::Handle;
+
 #[derive(Clone)]
 pub struct Sprite {
+    pub texture: Handle<Texture2D>,
     pub rect: Rectangle,
     pub position: Vector2,
 }
 
 impl Sprite {
-    pub fn new(position: Vector2, atlas_tile: Vector2, tile_size: f32) -> Sprite {
+    pub fn new(texture: Handle<Texture2D>, position: Vector2, atlas_tile: Vector2, tile_size: f32) -> Sprite {
         Sprite {
+            texture,
             position,
             rect: Rectangle {
                 x: atlas_tile.x * tile_size,
@@ -19,3 +25,50 @@ impl Sprite {
         }
     }
 }
+
+/// The single random number generator every simulation system draws from. Seeding it with the
+/// same value reproduces the same simulation. ChaCha12 is what `StdRng` uses, picked directly
+/// so the generator state can be written to save files.
+pub struct GameRng(pub ChaCha12Rng);
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> GameRng {
+        GameRng(ChaCha12Rng::seed_from_u64(seed))
+    }
+}
+
+/// Hands out ids for game objects that aren't entities, like tasks. Stored as a resource next
+/// to `GameRng`, so ids follow the simulation they belong to and are written to save files.
+#[derive(Debug, Clone, Copy)]
+pub struct IdCounter {
+    next: usize,
+}
+
+impl Default for IdCounter {
+    fn default() -> IdCounter {
+        IdCounter::starting_at(1)
+    }
+}
+
+impl IdCounter {
+    pub fn starting_at(next: usize) -> IdCounter {
+        IdCounter { next }
+    }
+
+    pub fn next_id(&mut self) -> usize {
+        let id = self.next;
+        self.next += 1;
+        id
+    }
+
+    /// Id the next call to `next_id` will return
+    pub fn peek(&self) -> usize {
+        self.next
+    }
+}
+
+/// Set `requested` to close the game at the end of the frame. Stored as a resource.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct AppExit {
+    pub requested: bool,
+}
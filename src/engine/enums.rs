@@ -1,9 +1,5 @@
-#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
-pub enum GameResource {
-    #[default]
-    Wood,
-    Stone
-}
+use raylib::prelude::Vector2;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CollisionType {
@@ -12,6 +8,55 @@ pub enum CollisionType {
     All
 }
 
+/// What a `CollisionBox` is. Boxes only get contacts with the layers in their mask.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum CollisionLayer {
+    Villager,
+    Building,
+    /// Ghost of a building still being placed
+    Placement,
+    Terrain,
+    Ui
+}
+
+/// Clockwise quarter turns of a drawable or building
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270
+}
+
+impl Rotation {
+    pub fn clockwise(self) -> Rotation {
+        match self {
+            Rotation::Deg0 => Rotation::Deg90,
+            Rotation::Deg90 => Rotation::Deg180,
+            Rotation::Deg180 => Rotation::Deg270,
+            Rotation::Deg270 => Rotation::Deg0,
+        }
+    }
+
+    pub fn degrees(self) -> f32 {
+        match self {
+            Rotation::Deg0 => 0.0,
+            Rotation::Deg90 => 90.0,
+            Rotation::Deg180 => 180.0,
+            Rotation::Deg270 => 270.0,
+        }
+    }
+
+    /// Size of a `size` rectangle once turned, the sides swap on odd quarter turns
+    pub fn rotate_size(self, size: Vector2) -> Vector2 {
+        match self {
+            Rotation::Deg0 | Rotation::Deg180 => size,
+            Rotation::Deg90 | Rotation::Deg270 => Vector2 { x: size.y, y: size.x },
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ButtonState {
     Normal,
@@ -20,7 +65,7 @@ pub enum ButtonState {
     Toggled
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum VillagerState {
     Idle,
     Loading,
@@ -34,8 +79,23 @@ pub enum VillagerType {
     Builder
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum BuildingType {
-    Warehouse,
-    House
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Stage {
+    Startup,
+    Input,
+    PreUpdate,
+    Update,
+    PostUpdate,
+    UI,
+    Render
+}
+
+/// Draw order of world drawables, lowest first. Drawables of the same layer are sorted by
+/// their y, so buildings and villagers share `Objects` to hide behind each other.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum RenderLayer {
+    Ground,
+    Objects,
+    Overlay,
+    Debug
 }
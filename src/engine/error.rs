@@ -0,0 +1,71 @@
+use std::{any::type_name, fmt};
+
+use hecs::{ComponentError, Entity};
+
+#[derive(Debug)]
+pub enum EngineError {
+    AssetLoad { path: String, reason: String },
+    /// A handle was used before its asset was loaded
+    AssetNotLoaded(String),
+    NoSuchEntity(Entity),
+    MissingComponent { entity: Entity, component: &'static str },
+    MissingResource(&'static str),
+    /// The resource is already borrowed mutably somewhere else
+    ResourceInUse(&'static str),
+    /// A system needed a raylib handle, camera or font the current run doesn't provide
+    MissingContext(&'static str),
+    Scene(String),
+    Schedule(String),
+    /// A data file couldn't be written, read or understood. `kind` names the file for the
+    /// player, like "Save file" or "Config".
+    Data { kind: &'static str, path: String, reason: String },
+    /// Nothing of `kind` is named `name`, like an animation clip or a definition id
+    NotFound { kind: &'static str, name: String },
+}
+
+impl EngineError {
+    /// Maps a hecs `ComponentError` for component `T` on `entity` into an `EngineError`.
+    /// Meant for `map_err`: `world.get::<Label>(entity).map_err(EngineError::component::<Label>(entity))`
+    pub fn component<T>(entity: Entity) -> impl FnOnce(ComponentError) -> EngineError {
+        move |error| match error {
+            ComponentError::NoSuchEntity => EngineError::NoSuchEntity(entity),
+            ComponentError::MissingComponent(_) => EngineError::MissingComponent {
+                entity,
+                component: type_name::<T>(),
+            },
+        }
+    }
+
+    /// Builds a `Data` error, for `map_err` closures reading `kind` files
+    pub fn data(kind: &'static str, path: &str, reason: String) -> EngineError {
+        EngineError::Data {
+            kind,
+            path: path.to_string(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::AssetLoad { path, reason } => {
+                write!(f, "Failed to load asset {:?}: {}", path, reason)
+            }
+            EngineError::AssetNotLoaded(handle) => write!(f, "Asset {} is not loaded", handle),
+            EngineError::NoSuchEntity(entity) => write!(f, "No such entity {:?}", entity),
+            EngineError::MissingComponent { entity, component } => {
+                write!(f, "Entity {:?} has no {} component", entity, component)
+            }
+            EngineError::MissingResource(name) => write!(f, "Resource {} is missing", name),
+            EngineError::ResourceInUse(name) => write!(f, "Resource {} is already borrowed", name),
+            EngineError::MissingContext(name) => write!(f, "{} is not available", name),
+            EngineError::Scene(reason) => write!(f, "Scene error: {}", reason),
+            EngineError::Schedule(reason) => write!(f, "Schedule error: {}", reason),
+            EngineError::Data { kind, path, reason } => write!(f, "{} {:?}: {}", kind, path, reason),
+            EngineError::NotFound { kind, name } => write!(f, "No {} named {:?}", kind, name),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
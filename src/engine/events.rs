@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+// STRUCTS ------
+/// Typed event channel, stored as a resource. Systems `send` events and `read` them back under
+/// a reader name, which keeps its own cursor so every reader sees each event once, whatever
+/// stage it runs in or how many simulation ticks run per frame.
+///
+/// `update` must run once per frame. Events are kept for two updates, so a reader that reads at
+/// least once per frame never misses one.
+pub struct Events<T> {
+    events: Vec<T>,
+    /// Id of `events[0]`. Ids keep growing as events are sent.
+    first_id: usize,
+    /// Number of events sent before the last `update`
+    previous_len: usize,
+    readers: HashMap<&'static str, usize>,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Events<T> {
+        Events {
+            events: vec![],
+            first_id: 0,
+            previous_len: 0,
+            readers: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Events<T> {
+        Events::default()
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.events.push(event);
+    }
+
+    /// Returns the events `reader` hasn't seen yet.
+    pub fn read(&mut self, reader: &'static str) -> &[T] {
+        let end_id = self.first_id + self.events.len();
+        let cursor = self.readers.insert(reader, end_id).unwrap_or(self.first_id);
+        let start = cursor.max(self.first_id) - self.first_id;
+
+        &self.events[start..]
+    }
+
+    /// Drops the events sent before the previous update.
+    pub fn update(&mut self) {
+        self.events.drain(..self.previous_len);
+        self.first_id += self.previous_len;
+        self.previous_len = self.events.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readers_see_each_event_once() {
+        let mut events: Events<u32> = Events::new();
+        events.send(1);
+        events.send(2);
+
+        assert_eq!(events.read("first"), &[1, 2]);
+        assert_eq!(events.read("first"), &[] as &[u32]);
+
+        events.send(3);
+        assert_eq!(events.read("first"), &[3]);
+        assert_eq!(events.read("second"), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn events_are_kept_for_two_updates() {
+        let mut events: Events<u32> = Events::new();
+        events.send(1);
+        events.update();
+        events.send(2);
+
+        assert_eq!(events.read("late"), &[1, 2]);
+
+        events.update();
+        events.send(3);
+        assert_eq!(events.read("late"), &[3]);
+        assert_eq!(events.read("new"), &[2, 3]);
+    }
+
+    #[test]
+    fn cursors_survive_dropped_events() {
+        let mut events: Events<u32> = Events::new();
+        events.send(1);
+        assert_eq!(events.read("reader"), &[1]);
+
+        events.update();
+        events.update();
+        events.update();
+        events.send(2);
+
+        assert_eq!(events.read("reader"), &[2]);
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+};
+
+use raylib::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{actions::InputBinding, error::EngineError};
+
+/// Gamepad whose buttons are captured
+const GAMEPAD: i32 = 0;
+
+/// Bumped whenever the layout of `InputRecording` changes
+pub const RECORDING_VERSION: u32 = 1;
+
+const MOUSE_BUTTONS: [MouseButton; 3] = [
+    MouseButton::MOUSE_LEFT_BUTTON,
+    MouseButton::MOUSE_RIGHT_BUTTON,
+    MouseButton::MOUSE_MIDDLE_BUTTON,
+];
+
+// STRUCTS ------
+/// Snapshot of the keyboard and mouse for the current frame, stored as a resource. Input
+/// systems read it instead of polling raylib, so a recorded frame can be fed back in place of
+/// the real devices.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputState {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keys_down: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keys_pressed: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keys_released: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    buttons_down: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    buttons_pressed: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    buttons_released: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pad_buttons_down: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pad_buttons_pressed: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pad_buttons_released: Vec<i32>,
+    mouse_position: [f32; 2],
+    #[serde(default, skip_serializing_if = "is_zero")]
+    wheel_move: f32,
+}
+
+impl InputState {
+    /// Reads the keys and gamepad buttons in `bindings`, and the whole mouse, from raylib.
+    /// Keys and gamepad buttons nobody is bound to read as never pressed.
+    pub fn capture(raylib_handle: &RaylibHandle, bindings: &[InputBinding]) -> InputState {
+        let mut input = InputState::default();
+        let gamepad_available = raylib_handle.is_gamepad_available(GAMEPAD);
+
+        for binding in bindings {
+            match *binding {
+                InputBinding::Key(key) => {
+                    if raylib_handle.is_key_down(key) {
+                        input.keys_down.push(key as i32);
+                    }
+                    if raylib_handle.is_key_pressed(key) {
+                        input.keys_pressed.push(key as i32);
+                    }
+                    if raylib_handle.is_key_released(key) {
+                        input.keys_released.push(key as i32);
+                    }
+                }
+                InputBinding::GamepadButton(button) if gamepad_available => {
+                    if raylib_handle.is_gamepad_button_down(GAMEPAD, button) {
+                        input.pad_buttons_down.push(button as i32);
+                    }
+                    if raylib_handle.is_gamepad_button_pressed(GAMEPAD, button) {
+                        input.pad_buttons_pressed.push(button as i32);
+                    }
+                    if raylib_handle.is_gamepad_button_released(GAMEPAD, button) {
+                        input.pad_buttons_released.push(button as i32);
+                    }
+                }
+                // The mouse is read below as a whole
+                _ => {}
+            }
+        }
+
+        for button in MOUSE_BUTTONS {
+            if raylib_handle.is_mouse_button_down(button) {
+                input.buttons_down.push(button as i32);
+            }
+            if raylib_handle.is_mouse_button_pressed(button) {
+                input.buttons_pressed.push(button as i32);
+            }
+            if raylib_handle.is_mouse_button_released(button) {
+                input.buttons_released.push(button as i32);
+            }
+        }
+
+        let mouse_position = raylib_handle.get_mouse_position();
+        input.mouse_position = [mouse_position.x, mouse_position.y];
+        input.wheel_move = raylib_handle.get_mouse_wheel_move();
+
+        input
+    }
+
+    pub fn is_key_down(&self, key: KeyboardKey) -> bool {
+        self.keys_down.contains(&(key as i32))
+    }
+
+    pub fn is_key_pressed(&self, key: KeyboardKey) -> bool {
+        self.keys_pressed.contains(&(key as i32))
+    }
+
+    pub fn is_key_released(&self, key: KeyboardKey) -> bool {
+        self.keys_released.contains(&(key as i32))
+    }
+
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.buttons_down.contains(&(button as i32))
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_pressed.contains(&(button as i32))
+    }
+
+    pub fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.buttons_released.contains(&(button as i32))
+    }
+
+    pub fn is_gamepad_button_down(&self, button: GamepadButton) -> bool {
+        self.pad_buttons_down.contains(&(button as i32))
+    }
+
+    pub fn is_gamepad_button_pressed(&self, button: GamepadButton) -> bool {
+        self.pad_buttons_pressed.contains(&(button as i32))
+    }
+
+    pub fn is_gamepad_button_released(&self, button: GamepadButton) -> bool {
+        self.pad_buttons_released.contains(&(button as i32))
+    }
+
+    /// Mouse position in screen space
+    pub fn mouse_position(&self) -> Vector2 {
+        Vector2 {
+            x: self.mouse_position[0],
+            y: self.mouse_position[1],
+        }
+    }
+
+    /// How far the mouse wheel turned this frame, positive away from the user
+    pub fn mouse_wheel_move(&self) -> f32 {
+        self.wheel_move
+    }
+
+    /// Mouse position in world space, as seen through `camera`
+    pub fn mouse_world_position(&self, camera: &Camera2D) -> Vector2 {
+        screen_to_world(self.mouse_position(), camera)
+    }
+}
+
+/// Input of one render frame, along with the simulation tick it was read at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub tick: u64,
+    pub delta: f32,
+    pub input: InputState,
+}
+
+/// Every input frame of a session, plus what's needed to start the session again. Replaying
+/// the frames over the same start reproduces the same simulation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputRecording {
+    pub version: u32,
+    pub seed: u64,
+    /// Save file the session started from, if any
+    pub load_path: Option<String>,
+    /// Simulation ticks run by the session
+    pub ticks: u64,
+    pub frames: Vec<RecordedFrame>,
+    /// Files the session read while running, see `RecordedFiles`
+    #[serde(default)]
+    pub files: Vec<RecordedFile>,
+    /// Settings the session started with, like the game config and input bindings, keyed by
+    /// name. Replays use them instead of the files on the replaying machine.
+    #[serde(default)]
+    pub settings: BTreeMap<String, serde_json::Value>,
+}
+
+impl InputRecording {
+    pub fn new(seed: u64, load_path: Option<String>) -> InputRecording {
+        InputRecording {
+            version: RECORDING_VERSION,
+            seed,
+            load_path,
+            ticks: 0,
+            frames: vec![],
+            files: vec![],
+            settings: BTreeMap::new(),
+        }
+    }
+
+    /// Keeps `value` as the setting `name`, see `settings`
+    pub fn record_setting<T: Serialize>(&mut self, name: &str, value: &T) -> Result<(), EngineError> {
+        let value = serde_json::to_value(value).map_err(|error| setting_error(name, error.to_string()))?;
+        self.settings.insert(name.to_string(), value);
+
+        Ok(())
+    }
+
+    /// The setting `name` as the session had it. Recordings that didn't keep it fall back to
+    /// `local`.
+    pub fn setting_or<T: Clone + DeserializeOwned>(&self, name: &str, local: &T) -> Result<T, EngineError> {
+        match self.settings.get(name) {
+            Some(value) => {
+                serde_json::from_value(value.clone()).map_err(|error| setting_error(name, error.to_string()))
+            }
+            None => {
+                warn!("The recording has no {} setting, using the local one", name);
+                Ok(local.clone())
+            }
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, delta: f32, input: &InputState) {
+        self.frames.push(RecordedFrame {
+            tick,
+            delta,
+            input: input.clone(),
+        });
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), EngineError> {
+        let text = serde_json::to_string(self).map_err(|error| recording_error(path, error.to_string()))?;
+        fs::write(path, text).map_err(|error| recording_error(path, error.to_string()))?;
+        info!("Saved input recording to {}", path);
+
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<InputRecording, EngineError> {
+        let text = fs::read_to_string(path).map_err(|error| recording_error(path, error.to_string()))?;
+        let recording: InputRecording =
+            serde_json::from_str(&text).map_err(|error| recording_error(path, error.to_string()))?;
+        if recording.version != RECORDING_VERSION {
+            return Err(recording_error(
+                path,
+                format!(
+                    "unsupported version {} (expected {})",
+                    recording.version, RECORDING_VERSION
+                ),
+            ));
+        }
+
+        Ok(recording)
+    }
+
+    /// Tick the frame after `index` was read at, or the end of the session for the last frame.
+    /// The simulation runs up to that tick before the next frame's input applies.
+    pub fn end_tick_of(&self, index: usize) -> u64 {
+        self.frames
+            .get(index + 1)
+            .map_or(self.ticks, |next_frame| next_frame.tick)
+    }
+}
+
+/// A file the simulation read while recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedFile {
+    pub path: String,
+    pub text: String,
+}
+
+/// Files read during a recorded or replayed session, stored as a resource. Recording keeps a
+/// copy of every file read through it, replaying hands the copies back in the same order, so a
+/// replay loads the same saves even when the files changed on disk since.
+#[derive(Debug, Default)]
+pub struct RecordedFiles {
+    replaying: bool,
+    files: VecDeque<RecordedFile>,
+}
+
+impl RecordedFiles {
+    pub fn recording() -> RecordedFiles {
+        RecordedFiles::default()
+    }
+
+    pub fn replaying(files: Vec<RecordedFile>) -> RecordedFiles {
+        RecordedFiles {
+            replaying: true,
+            files: files.into(),
+        }
+    }
+
+    /// Reads `path` from disk, keeping a copy when recording. When replaying, returns the next
+    /// recorded copy instead, falling back to the disk for recordings that didn't keep any.
+    pub fn read(&mut self, path: &str) -> Result<String, String> {
+        if self.replaying {
+            match self.files.pop_front() {
+                Some(file) if file.path == path => return Ok(file.text),
+                Some(file) => {
+                    return Err(format!("the recording read {} at this point, not {}", file.path, path))
+                }
+                None => warn!("{} isn't in the recording, reading it from disk", path),
+            }
+        }
+
+        let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        if !self.replaying {
+            self.files.push_back(RecordedFile {
+                path: path.to_string(),
+                text: text.clone(),
+            });
+        }
+
+        Ok(text)
+    }
+
+    pub fn into_files(self) -> Vec<RecordedFile> {
+        self.files.into()
+    }
+}
+
+// FUNCTIONS ------
+/// Same transform as raylib's `GetScreenToWorld2D`, without needing a raylib handle.
+pub fn screen_to_world(position: Vector2, camera: &Camera2D) -> Vector2 {
+    let scaled = Vector2 {
+        x: (position.x - camera.offset.x) / camera.zoom,
+        y: (position.y - camera.offset.y) / camera.zoom,
+    };
+    let (sin, cos) = (-camera.rotation.to_radians()).sin_cos();
+
+    Vector2 {
+        x: scaled.x * cos - scaled.y * sin + camera.target.x,
+        y: scaled.x * sin + scaled.y * cos + camera.target.y,
+    }
+}
+
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+fn recording_error(path: &str, reason: String) -> EngineError {
+    EngineError::data("Input recording", path, reason)
+}
+
+fn setting_error(name: &str, reason: String) -> EngineError {
+    EngineError::data("Recorded setting", name, reason)
+}
@@ -1,14 +1,19 @@
+pub mod actions;
+pub mod animation;
+pub mod assets;
 pub mod collision;
 pub mod datatypes;
 pub mod draw;
 pub mod enums;
+pub mod error;
+pub mod events;
+pub mod input;
+pub mod render;
+pub mod resources;
+pub mod scene;
+pub mod schedule;
+pub mod spatial_hash;
+pub mod spatial_query;
 pub mod startup;
+pub mod timestep;
 pub mod ui;
-pub mod utils;
-
-use once_cell::sync::OnceCell;
-use raylib::texture::Texture2D;
-
-// GLOBAL TEXTURES
-pub static TILESET: OnceCell<Texture2D> = OnceCell::new();
-pub static UI_ATLAS: OnceCell<Texture2D> = OnceCell::new();
@@ -0,0 +1,117 @@
+use hecs::World;
+use raylib::prelude::*;
+
+use super::{
+    assets::{Assets, Handle},
+    datatypes::Sprite,
+    enums::{RenderLayer, Rotation},
+    error::EngineError,
+    schedule::DrawContext,
+};
+
+// STRUCTS ------
+#[derive(Debug, Clone)]
+pub enum DrawCommand {
+    /// `position` is the top left corner of the texture once turned by `rotation`
+    Texture {
+        texture: Handle<Texture2D>,
+        source: Rectangle,
+        position: Vector2,
+        rotation: Rotation,
+        tint: Color,
+    },
+    Rectangle { rect: Rectangle, color: Color },
+}
+
+struct QueuedDraw {
+    layer: RenderLayer,
+    sort_y: f32,
+    command: DrawCommand,
+}
+
+/// World drawables of the current frame, stored as a resource. Draw systems push into it and
+/// `draw_render_queue` draws everything at once, sorted by layer then y. Drawables with the same
+/// layer and y keep the order they were pushed in.
+#[derive(Default)]
+pub struct RenderQueue {
+    queued: Vec<QueuedDraw>,
+}
+
+impl RenderQueue {
+    pub fn new() -> RenderQueue {
+        RenderQueue::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queued.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    /// `sort_y` is usually the bottom edge of the drawable, so whatever stands lower on the
+    /// screen is drawn in front
+    pub fn push(&mut self, layer: RenderLayer, sort_y: f32, command: DrawCommand) {
+        self.queued.push(QueuedDraw { layer, sort_y, command });
+    }
+
+    /// Queues `sprite` drawn at `position`, sorted by the bottom edge of the sprite itself
+    pub fn push_sprite(&mut self, layer: RenderLayer, sprite: &Sprite, position: Vector2, tint: Color) {
+        self.push(
+            layer,
+            sprite.position.y + sprite.rect.height,
+            DrawCommand::Texture {
+                texture: sprite.texture,
+                source: sprite.rect,
+                position,
+                rotation: Rotation::Deg0,
+                tint,
+            },
+        );
+    }
+
+    /// Empties the queue, returning the commands in draw order
+    pub fn drain_sorted(&mut self) -> Vec<DrawCommand> {
+        let mut queued = std::mem::take(&mut self.queued);
+        // Stable, so ties stay in push order
+        queued.sort_by(|draw1, draw2| {
+            draw1.layer.cmp(&draw2.layer).then(draw1.sort_y.total_cmp(&draw2.sort_y))
+        });
+
+        queued.into_iter().map(|draw| draw.command).collect()
+    }
+}
+
+// FUNCTIONS ------
+/// Draws the `RenderQueue`. Runs after every system that queues world drawables.
+pub fn draw_render_queue(
+    _world: &mut World,
+    mode2d: &mut RaylibMode2D<RaylibDrawHandle>,
+    ctx: &DrawContext,
+) -> Result<(), EngineError> {
+    let commands = ctx.resources.get_mut::<RenderQueue>()?.drain_sorted();
+    let assets = ctx.resources.get::<Assets>()?;
+    for command in commands {
+        match command {
+            DrawCommand::Texture { texture, source, position, rotation: Rotation::Deg0, tint } => {
+                mode2d.draw_texture_rec(assets.get(texture)?, source, position, tint);
+            }
+            DrawCommand::Texture { texture, source, position, rotation, tint } => {
+                // Turned around its center, which stays in the middle of the turned rectangle
+                let size = Vector2 { x: source.width, y: source.height };
+                let center = position + rotation.rotate_size(size) / 2.0;
+                let dest = Rectangle {
+                    x: center.x,
+                    y: center.y,
+                    width: size.x,
+                    height: size.y,
+                };
+                mode2d.draw_texture_pro(assets.get(texture)?, source, dest, size / 2.0, rotation.degrees(), tint);
+            }
+            DrawCommand::Rectangle { rect, color } => mode2d.draw_rectangle_rec(rect, color),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+};
+
+use super::error::EngineError;
+
+// STRUCTS ------
+/// Typed store for one-of-a-kind game state (camera zoom, open tasks, tilemap...) that lives
+/// next to the `World` instead of on lone entities. Each type is stored at most once.
+///
+/// Values sit behind a `RefCell`, so systems can borrow several resources at the same time
+/// through a shared reference. Borrowing the same resource mutably twice is an error.
+#[derive(Default)]
+pub struct Resources {
+    resources: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+}
+
+impl Resources {
+    pub fn new() -> Resources {
+        Resources::default()
+    }
+
+    /// Stores `resource`, replacing any previous value of the same type.
+    pub fn insert<T: 'static>(&mut self, resource: T) {
+        self.resources
+            .insert(TypeId::of::<T>(), RefCell::new(Box::new(resource)));
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        let resource = self.resources.remove(&TypeId::of::<T>())?;
+        resource.into_inner().downcast::<T>().ok().map(|resource| *resource)
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn get<T: 'static>(&self) -> Result<Ref<'_, T>, EngineError> {
+        let cell = self.cell::<T>()?;
+        let resource = cell
+            .try_borrow()
+            .map_err(|_| EngineError::ResourceInUse(type_name::<T>()))?;
+
+        Ok(Ref::map(resource, |resource| {
+            resource.downcast_ref::<T>().expect("Resource stored under the wrong type")
+        }))
+    }
+
+    pub fn get_mut<T: 'static>(&self) -> Result<RefMut<'_, T>, EngineError> {
+        let cell = self.cell::<T>()?;
+        let resource = cell
+            .try_borrow_mut()
+            .map_err(|_| EngineError::ResourceInUse(type_name::<T>()))?;
+
+        Ok(RefMut::map(resource, |resource| {
+            resource.downcast_mut::<T>().expect("Resource stored under the wrong type")
+        }))
+    }
+
+    fn cell<T: 'static>(&self) -> Result<&RefCell<Box<dyn Any>>, EngineError> {
+        self.resources
+            .get(&TypeId::of::<T>())
+            .ok_or(EngineError::MissingResource(type_name::<T>()))
+    }
+}
@@ -0,0 +1,198 @@
+use hecs::{Entity, World};
+use raylib::prelude::{Font, Texture2D};
+
+use super::{
+    assets::{Assets, Handle},
+    datatypes::Sprite,
+    error::EngineError,
+    schedule::{DrawScreenSystem, UpdateSystem},
+    ui::datatypes::{Button, Label, MouseSelection, ToggleButton},
+};
+
+// TAGS ------
+/// Marks an entity as part of a scene. Entities are despawned when their scene is left.
+///
+/// Entities spawned without the tag are claimed by the scene on top of the stack the next time
+/// scene transitions are applied, so scene code rarely needs to add it by hand.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneOwned(pub &'static str);
+
+// STRUCTS ------
+/// Asset references taken for the textures and fonts an entity draws with. Added when a scene
+/// claims the entity and given back when the scene despawns it, so an asset stays loaded while
+/// a live scene uses it.
+#[derive(Debug, Default)]
+pub struct AssetRefs {
+    textures: Vec<Handle<Texture2D>>,
+    fonts: Vec<Handle<Font>>,
+}
+
+/// A scene and its lifecycle callbacks, registered with `Schedule::add_scene`.
+///
+/// `on_enter` and `on_exit` run when the scene is pushed on or popped off the scene stack.
+/// `on_update` runs in `Stage::Update` and `on_draw` on screen after the world, the same way
+/// scene systems do.
+#[derive(Clone, Copy)]
+pub struct SceneDefinition {
+    pub label: &'static str,
+    pub on_enter: Option<UpdateSystem>,
+    pub on_update: Option<UpdateSystem>,
+    pub on_draw: Option<DrawScreenSystem>,
+    pub on_exit: Option<UpdateSystem>,
+}
+
+impl SceneDefinition {
+    pub fn new(label: &'static str) -> SceneDefinition {
+        SceneDefinition {
+            label,
+            on_enter: None,
+            on_update: None,
+            on_draw: None,
+            on_exit: None,
+        }
+    }
+
+    pub fn on_enter(mut self, system: UpdateSystem) -> SceneDefinition {
+        self.on_enter = Some(system);
+        self
+    }
+
+    pub fn on_update(mut self, system: UpdateSystem) -> SceneDefinition {
+        self.on_update = Some(system);
+        self
+    }
+
+    pub fn on_draw(mut self, system: DrawScreenSystem) -> SceneDefinition {
+        self.on_draw = Some(system);
+        self
+    }
+
+    pub fn on_exit(mut self, system: UpdateSystem) -> SceneDefinition {
+        self.on_exit = Some(system);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SceneRequest {
+    /// Leaves every scene on the stack, then enters the new one
+    Switch(&'static str),
+    /// Enters a scene over the current one, which stays alive but stops updating
+    Push(&'static str),
+    /// Leaves the top scene and resumes the one below
+    Pop,
+    /// Leaves every scene on the stack, then runs the system, which rebuilds the world and
+    /// sets the new stack itself
+    Replace(UpdateSystem),
+}
+
+/// The scenes currently alive, bottom to top, stored as a resource. Only the top scene updates
+/// and handles input, every scene on the stack is drawn.
+///
+/// Systems ask for transitions through `switch_to`, `push` and `pop`. They are applied by
+/// `Schedule::apply_scene_transitions` at the start of the next frame or tick.
+#[derive(Debug, Default, Clone)]
+pub struct SceneStack {
+    pub(crate) stack: Vec<&'static str>,
+    pub(crate) requests: Vec<SceneRequest>,
+}
+
+impl SceneStack {
+    /// Empty stack that enters `scene` on the first transition
+    pub fn starting_with(scene: &'static str) -> SceneStack {
+        SceneStack {
+            stack: vec![],
+            requests: vec![SceneRequest::Switch(scene)],
+        }
+    }
+
+    pub fn current(&self) -> Option<&'static str> {
+        self.stack.last().copied()
+    }
+
+    pub fn scenes(&self) -> &[&'static str] {
+        &self.stack
+    }
+
+    pub fn switch_to(&mut self, scene: &'static str) {
+        self.requests.push(SceneRequest::Switch(scene));
+    }
+
+    pub fn push(&mut self, scene: &'static str) {
+        self.requests.push(SceneRequest::Push(scene));
+    }
+
+    pub fn pop(&mut self) {
+        self.requests.push(SceneRequest::Pop);
+    }
+
+    /// Leaves every scene, then runs `system` between frames. Meant for code that rebuilds the
+    /// whole world, like loading a save, so no system runs against a half replaced world.
+    pub fn replace_with(&mut self, system: UpdateSystem) {
+        self.requests.push(SceneRequest::Replace(system));
+    }
+
+    /// Replaces the stack with `scene` without running any callback and drops pending
+    /// requests. Meant for code that rebuilt the world itself, like loading a save.
+    pub fn reset_to(&mut self, scene: &'static str) {
+        self.stack = vec![scene];
+        self.requests.clear();
+    }
+}
+
+// FUNCTIONS ------
+/// Takes asset references for every entity that draws with a texture or font and doesn't hold
+/// references yet.
+pub(crate) fn retain_asset_refs(world: &mut World, assets: &mut Assets) -> Result<(), EngineError> {
+    let mut refs: Vec<(Entity, AssetRefs)> = vec![];
+
+    let mut query = world
+        .query::<(
+            Option<&Sprite>,
+            Option<&Button>,
+            Option<&ToggleButton>,
+            Option<&Label>,
+            Option<&MouseSelection>,
+        )>()
+        .without::<AssetRefs>();
+    for (entity, (m_sprite, m_button, m_toggle_button, m_label, m_mouse_selection)) in query.iter() {
+        let textures: Vec<Handle<Texture2D>> = m_sprite
+            .map(|sprite| sprite.texture)
+            .into_iter()
+            .chain(m_button.map(|button| button.texture))
+            .chain(m_toggle_button.map(|button| button.texture))
+            .chain(m_mouse_selection.map(|selection| selection.texture))
+            .filter(|texture| assets.retain(*texture))
+            .collect();
+        let fonts: Vec<Handle<Font>> = m_label
+            .map(|label| label.font)
+            .into_iter()
+            .filter(|font| assets.retain(*font))
+            .collect();
+
+        if !textures.is_empty() || !fonts.is_empty() {
+            refs.push((entity, AssetRefs { textures, fonts }));
+        }
+    }
+    drop(query);
+
+    for (entity, asset_refs) in refs {
+        world
+            .insert_one(entity, asset_refs)
+            .map_err(|_| EngineError::NoSuchEntity(entity))?;
+    }
+
+    Ok(())
+}
+
+/// Gives back the asset references `entity` holds, before it's despawned
+pub(crate) fn release_asset_refs(world: &World, entity: Entity, assets: &mut Assets) {
+    if let Ok(asset_refs) = world.get::<AssetRefs>(entity) {
+        for texture in &asset_refs.textures {
+            assets.release(*texture);
+        }
+        for font in &asset_refs.fonts {
+            assets.release(*font);
+        }
+    }
+}
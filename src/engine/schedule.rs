@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+
+use hecs::{Entity, World};
+use raylib::prelude::*;
+
+use super::{
+    assets::Assets,
+    enums::Stage,
+    error::EngineError,
+    resources::Resources,
+    scene::{release_asset_refs, retain_asset_refs, SceneDefinition, SceneOwned, SceneRequest, SceneStack},
+};
+
+// TYPES ------
+pub type UpdateSystem = fn(&mut World, &mut SystemContext) -> Result<(), EngineError>;
+pub type DrawWorldSystem =
+    fn(&mut World, &mut RaylibMode2D<RaylibDrawHandle>, &DrawContext) -> Result<(), EngineError>;
+pub type DrawScreenSystem = fn(&mut World, &mut RaylibDrawHandle, &DrawContext) -> Result<(), EngineError>;
+
+// STRUCTS ------
+/// Everything a non-render system may need besides the world. The raylib handle and camera are
+/// missing when the simulation runs headless, so systems that need them should fail or skip
+/// accordingly.
+pub struct SystemContext<'a> {
+    pub delta: f32,
+    pub resources: &'a mut Resources,
+    pub m_raylib_handle: Option<&'a mut RaylibHandle>,
+    pub m_camera: Option<&'a mut Camera2D>,
+}
+
+impl<'a> SystemContext<'a> {
+    pub fn new(delta: f32, resources: &'a mut Resources) -> SystemContext<'a> {
+        SystemContext {
+            delta,
+            resources,
+            m_raylib_handle: None,
+            m_camera: None,
+        }
+    }
+}
+
+pub struct DrawContext<'a> {
+    pub camera: &'a Camera2D,
+    pub tile_size: f32,
+    pub resources: &'a Resources,
+}
+
+#[derive(Clone, Copy)]
+pub enum System {
+    Update(UpdateSystem),
+    /// Drawn inside the camera, during `Stage::Render`
+    DrawWorld(DrawWorldSystem),
+    /// Drawn on screen space after the world, during `Stage::Render`
+    DrawScreen(DrawScreenSystem),
+}
+
+pub struct SystemEntry {
+    pub name: &'static str,
+    pub stage: Stage,
+    pub system: System,
+    pub before: Vec<&'static str>,
+    pub after: Vec<&'static str>,
+    /// Scenes the system runs in. Empty means every scene.
+    pub scenes: Vec<&'static str>,
+    pub enabled: bool,
+}
+
+impl SystemEntry {
+    pub fn new(name: &'static str, stage: Stage, system: System) -> SystemEntry {
+        SystemEntry {
+            name,
+            stage,
+            system,
+            before: vec![],
+            after: vec![],
+            scenes: vec![],
+            enabled: true,
+        }
+    }
+
+    pub fn before(mut self, name: &'static str) -> SystemEntry {
+        self.before.push(name);
+        self
+    }
+
+    pub fn after(mut self, name: &'static str) -> SystemEntry {
+        self.after.push(name);
+        self
+    }
+
+    pub fn in_scene(mut self, scene: &'static str) -> SystemEntry {
+        self.scenes.push(scene);
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<SystemEntry>,
+    ordered: HashMap<Stage, Vec<usize>>,
+    scenes: HashMap<&'static str, SceneDefinition>,
+    /// Scene stack as of the last transition, bottom to top
+    active_scenes: Vec<&'static str>,
+    dirty: bool,
+}
+
+impl Schedule {
+    pub fn new() -> Schedule {
+        Schedule::default()
+    }
+
+    pub fn add_system(&mut self, entry: SystemEntry) {
+        self.systems.push(entry);
+        self.dirty = true;
+    }
+
+    /// Enables or disables every system named `name`. Scenes register their update and draw
+    /// callbacks under the scene label, so both are toggled together.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), EngineError> {
+        let mut found = false;
+        for entry in self.systems.iter_mut().filter(|entry| entry.name == name) {
+            entry.enabled = enabled;
+            found = true;
+        }
+
+        if found {
+            Ok(())
+        } else {
+            Err(EngineError::Schedule(format!("No system named {}", name)))
+        }
+    }
+
+    /// Registers a scene. Its update and draw callbacks become systems of that scene.
+    pub fn add_scene(&mut self, definition: SceneDefinition) {
+        if let Some(on_update) = definition.on_update {
+            self.add_system(
+                SystemEntry::new(definition.label, Stage::Update, System::Update(on_update))
+                    .in_scene(definition.label),
+            );
+        }
+        if let Some(on_draw) = definition.on_draw {
+            self.add_system(
+                SystemEntry::new(definition.label, Stage::Render, System::DrawScreen(on_draw))
+                    .after("draw_ui")
+                    .in_scene(definition.label),
+            );
+        }
+        self.scenes.insert(definition.label, definition);
+    }
+
+    /// Applies the transitions queued in the `SceneStack` resource, running the enter and exit
+    /// callbacks of the scenes involved, then enables the systems of the scenes left on the stack.
+    pub fn apply_scene_transitions(&mut self, world: &mut World, resources: &mut Resources) -> Result<(), EngineError> {
+        if let Some(scene) = resources.get::<SceneStack>()?.current() {
+            claim_unowned_entities(world, resources, scene)?;
+        }
+
+        loop {
+            let requests = std::mem::take(&mut resources.get_mut::<SceneStack>()?.requests);
+            if requests.is_empty() {
+                break;
+            }
+
+            for request in requests {
+                match request {
+                    SceneRequest::Switch(scene) => {
+                        while resources.get::<SceneStack>()?.current().is_some() {
+                            self.exit_top_scene(world, resources)?;
+                        }
+                        self.enter_scene(scene, world, resources)?;
+                    }
+                    SceneRequest::Push(scene) => self.enter_scene(scene, world, resources)?,
+                    SceneRequest::Pop => self.exit_top_scene(world, resources)?,
+                    SceneRequest::Replace(system) => {
+                        while resources.get::<SceneStack>()?.current().is_some() {
+                            self.exit_top_scene(world, resources)?;
+                        }
+                        system(world, &mut SystemContext::new(0.0, resources))?;
+                    }
+                }
+            }
+        }
+
+        self.active_scenes = resources.get::<SceneStack>()?.stack.clone();
+
+        Ok(())
+    }
+
+    fn enter_scene(&mut self, scene: &'static str, world: &mut World, resources: &mut Resources) -> Result<(), EngineError> {
+        let definition = *self
+            .scenes
+            .get(scene)
+            .ok_or_else(|| EngineError::Scene(format!("No scene named {}", scene)))?;
+
+        resources.get_mut::<SceneStack>()?.stack.push(scene);
+        self.active_scenes = resources.get::<SceneStack>()?.stack.clone();
+        if let Some(on_enter) = definition.on_enter {
+            on_enter(world, &mut SystemContext::new(0.0, resources))?;
+        }
+        claim_unowned_entities(world, resources, scene)?;
+        info!("Entered scene {}", scene);
+
+        Ok(())
+    }
+
+    fn exit_top_scene(&mut self, world: &mut World, resources: &mut Resources) -> Result<(), EngineError> {
+        let scene = resources
+            .get::<SceneStack>()?
+            .current()
+            .ok_or_else(|| EngineError::Scene("No scene to leave".to_string()))?;
+
+        claim_unowned_entities(world, resources, scene)?;
+        if let Some(on_exit) = self.scenes.get(scene).and_then(|definition| definition.on_exit) {
+            on_exit(world, &mut SystemContext::new(0.0, resources))?;
+        }
+
+        let owned: Vec<Entity> = world
+            .query::<&SceneOwned>()
+            .iter()
+            .filter(|(_, owner)| owner.0 == scene)
+            .map(|(entity, _)| entity)
+            .collect();
+        for entity in owned {
+            if resources.contains::<Assets>() {
+                release_asset_refs(world, entity, &mut *resources.get_mut::<Assets>()?);
+            }
+            world.despawn(entity).map_err(|_| EngineError::NoSuchEntity(entity))?;
+        }
+
+        resources.get_mut::<SceneStack>()?.stack.pop();
+        self.active_scenes = resources.get::<SceneStack>()?.stack.clone();
+        info!("Left scene {}", scene);
+
+        Ok(())
+    }
+
+    pub fn run_stage(
+        &mut self,
+        stage: Stage,
+        world: &mut World,
+        ctx: &mut SystemContext,
+    ) -> Result<(), EngineError> {
+        for index in self.runnable_systems(stage)? {
+            if let System::Update(system) = self.systems[index].system {
+                system(world, ctx)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn run_stages(
+        &mut self,
+        stages: &[Stage],
+        world: &mut World,
+        ctx: &mut SystemContext,
+    ) -> Result<(), EngineError> {
+        for stage in stages {
+            self.run_stage(*stage, world, ctx)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn run_draw_world(
+        &mut self,
+        world: &mut World,
+        mode2d: &mut RaylibMode2D<RaylibDrawHandle>,
+        ctx: &DrawContext,
+    ) -> Result<(), EngineError> {
+        for index in self.runnable_systems(Stage::Render)? {
+            if let System::DrawWorld(system) = self.systems[index].system {
+                system(world, mode2d, ctx)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn run_draw_screen(
+        &mut self,
+        world: &mut World,
+        draw_handle: &mut RaylibDrawHandle,
+        ctx: &DrawContext,
+    ) -> Result<(), EngineError> {
+        for index in self.runnable_systems(Stage::Render)? {
+            if let System::DrawScreen(system) = self.systems[index].system {
+                system(world, draw_handle, ctx)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn runnable_systems(&mut self, stage: Stage) -> Result<Vec<usize>, EngineError> {
+        if self.dirty {
+            self.rebuild()?;
+        }
+
+        // Every scene on the stack is drawn, only the top one updates
+        let scenes: &[&'static str] = match stage {
+            Stage::Render => &self.active_scenes,
+            _ => self.active_scenes.last().map(std::slice::from_ref).unwrap_or(&[]),
+        };
+        let ordered = self.ordered.get(&stage).cloned().unwrap_or_default();
+
+        Ok(ordered
+            .into_iter()
+            .filter(|index| {
+                let entry = &self.systems[*index];
+                entry.enabled
+                    && (entry.scenes.is_empty()
+                        || scenes.iter().any(|scene| entry.scenes.contains(scene)))
+            })
+            .collect())
+    }
+
+    /// Orders every stage from the before/after constraints. Systems without constraints
+    /// between them keep their registration order.
+    fn rebuild(&mut self) -> Result<(), EngineError> {
+        self.ordered.clear();
+
+        for (index, entry) in self.systems.iter().enumerate() {
+            let is_draw_system = !matches!(entry.system, System::Update(_));
+            if is_draw_system != (entry.stage == Stage::Render) {
+                return Err(EngineError::Schedule(format!(
+                    "System {} can't run in stage {:?}",
+                    entry.name, entry.stage
+                )));
+            }
+            self.ordered.entry(entry.stage).or_default().push(index);
+        }
+
+        for (stage, indices) in self.ordered.iter_mut() {
+            *indices = sort_stage(&self.systems, indices, *stage)?;
+        }
+
+        self.dirty = false;
+
+        Ok(())
+    }
+}
+
+// FUNCTIONS ------
+/// Tags every entity without a `SceneOwned` as owned by `scene`, and takes references on the
+/// assets new entities draw with. Headless runs have no assets to reference.
+fn claim_unowned_entities(world: &mut World, resources: &Resources, scene: &'static str) -> Result<(), EngineError> {
+    let unowned: Vec<Entity> = world
+        .query::<()>()
+        .without::<SceneOwned>()
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in unowned {
+        world
+            .insert_one(entity, SceneOwned(scene))
+            .map_err(|_| EngineError::NoSuchEntity(entity))?;
+    }
+
+    if resources.contains::<Assets>() {
+        retain_asset_refs(world, &mut *resources.get_mut::<Assets>()?)?;
+    }
+
+    Ok(())
+}
+
+fn sort_stage(systems: &[SystemEntry], indices: &[usize], stage: Stage) -> Result<Vec<usize>, EngineError> {
+    let position_of = |name: &str| -> Option<usize> {
+        indices.iter().position(|index| systems[*index].name == name)
+    };
+
+    // edges[a] holds every system that must run after a
+    let mut edges: Vec<Vec<usize>> = vec![vec![]; indices.len()];
+    let mut incoming: Vec<usize> = vec![0; indices.len()];
+
+    for (position, index) in indices.iter().enumerate() {
+        let entry = &systems[*index];
+        for name in entry.before.iter() {
+            match position_of(name) {
+                Some(other) => {
+                    edges[position].push(other);
+                    incoming[other] += 1;
+                }
+                None => warn!("{} runs before unknown system {} in {:?}", entry.name, name, stage),
+            }
+        }
+        for name in entry.after.iter() {
+            match position_of(name) {
+                Some(other) => {
+                    edges[other].push(position);
+                    incoming[position] += 1;
+                }
+                None => warn!("{} runs after unknown system {} in {:?}", entry.name, name, stage),
+            }
+        }
+    }
+
+    let mut sorted: Vec<usize> = vec![];
+    let mut done: Vec<bool> = vec![false; indices.len()];
+
+    while sorted.len() < indices.len() {
+        let next = (0..indices.len()).find(|position| !done[*position] && incoming[*position] == 0);
+        match next {
+            Some(position) => {
+                done[position] = true;
+                sorted.push(indices[position]);
+                for other in edges[position].iter() {
+                    incoming[*other] -= 1;
+                }
+            }
+            None => {
+                let cycle: Vec<&str> = (0..indices.len())
+                    .filter(|position| !done[*position])
+                    .map(|position| systems[indices[position]].name)
+                    .collect();
+                return Err(EngineError::Schedule(format!(
+                    "Ordering cycle in {:?} between {:?}",
+                    stage, cycle
+                )));
+            }
+        }
+    }
+
+    Ok(sorted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop(_world: &mut World, _ctx: &mut SystemContext) -> Result<(), EngineError> {
+        Ok(())
+    }
+
+    fn entry(name: &'static str) -> SystemEntry {
+        SystemEntry::new(name, Stage::Update, System::Update(noop))
+    }
+
+    fn sorted_names(systems: &[SystemEntry]) -> Result<Vec<&'static str>, EngineError> {
+        let indices: Vec<usize> = (0..systems.len()).collect();
+        let sorted = sort_stage(systems, &indices, Stage::Update)?;
+
+        Ok(sorted.into_iter().map(|index| systems[index].name).collect())
+    }
+
+    #[test]
+    fn sort_stage_keeps_registration_order_without_constraints() {
+        let systems = vec![entry("a"), entry("b"), entry("c")];
+
+        assert_eq!(sorted_names(&systems).unwrap(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn sort_stage_follows_before_and_after() {
+        let systems = vec![entry("a").after("c"), entry("b"), entry("c").after("b"), entry("d").before("b")];
+
+        assert_eq!(sorted_names(&systems).unwrap(), vec!["d", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn sort_stage_ignores_unknown_systems() {
+        let systems = vec![entry("a").after("missing"), entry("b").before("missing")];
+
+        assert_eq!(sorted_names(&systems).unwrap(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn sort_stage_reports_cycles() {
+        let systems = vec![entry("a").after("b"), entry("b").after("a"), entry("c")];
+
+        match sorted_names(&systems) {
+            Err(EngineError::Schedule(message)) => {
+                assert!(message.contains("\"a\", \"b\""), "{}", message);
+                assert!(!message.contains("\"c\""), "{}", message);
+            }
+            other => panic!("expected a cycle error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use hecs::{Entity, World};
+use raylib::prelude::*;
+
+use super::{collision::CollisionBox, error::EngineError, schedule::SystemContext};
+
+// STRUCTS ------
+/// Cells covered by a box, inclusive on both ends
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CellRange {
+    min: (i32, i32),
+    max: (i32, i32),
+}
+
+impl CellRange {
+    fn cells(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (min, max) = (self.min, self.max);
+        (min.1..=max.1).flat_map(move |y| (min.0..=max.0).map(move |x| (x, y)))
+    }
+}
+
+struct GridEntry {
+    range: CellRange,
+    /// Update pass that last saw the entity
+    pass: u64,
+}
+
+/// Uniform grid of every `CollisionBox`, stored as a resource. Collision detection only tests
+/// boxes sharing a cell instead of every pair.
+///
+/// Kept up to date by `update_collision_grid`. A box is only moved between cells when it
+/// crosses a cell border, so boxes standing still or moving inside their cells cost a lookup.
+pub struct CollisionGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+    entries: HashMap<Entity, GridEntry>,
+    pass: u64,
+}
+
+impl CollisionGrid {
+    /// `cell_size` works best around the size of the common boxes, a bit larger for a few big
+    /// ones.
+    pub fn new(cell_size: f32) -> CollisionGrid {
+        CollisionGrid {
+            cell_size,
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+            pass: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds `entity` or moves it to the cells covering `rect`
+    pub fn update(&mut self, entity: Entity, rect: &Rectangle) {
+        let range = self.cell_range(rect);
+        let pass = self.pass;
+        match self.entries.get_mut(&entity) {
+            Some(entry) if entry.range == range => {
+                entry.pass = pass;
+                return;
+            }
+            Some(entry) => {
+                let old_range = entry.range;
+                entry.range = range;
+                entry.pass = pass;
+                self.remove_from_cells(entity, old_range);
+            }
+            None => {
+                self.entries.insert(entity, GridEntry { range, pass });
+            }
+        }
+
+        for cell in range.cells() {
+            self.cells.entry(cell).or_default().push(entity);
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(entry) = self.entries.remove(&entity) {
+            self.remove_from_cells(entity, entry.range);
+        }
+    }
+
+    /// Every entity whose cells overlap `rect`. Entities covering several of those cells show
+    /// up once per cell, callers test the actual boxes anyway.
+    pub fn query_rect(&self, rect: &Rectangle) -> impl Iterator<Item = Entity> + '_ {
+        self.cell_range(rect)
+            .cells()
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+
+    /// Starts an update pass. Entities not updated before `end_pass` are dropped.
+    pub fn begin_pass(&mut self) {
+        self.pass += 1;
+    }
+
+    pub fn end_pass(&mut self) {
+        let pass = self.pass;
+        let stale: Vec<Entity> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.pass != pass)
+            .map(|(entity, _)| *entity)
+            .collect();
+        for entity in stale {
+            self.remove(entity);
+        }
+    }
+
+    fn cell_range(&self, rect: &Rectangle) -> CellRange {
+        // Boxes collide on their last pixel, not past it, see `are_boxes_colliding`
+        let cell_of = |value: f32| (value / self.cell_size).floor() as i32;
+        CellRange {
+            min: (cell_of(rect.x), cell_of(rect.y)),
+            max: (
+                cell_of(rect.x + (rect.width - 1.0).max(0.0)),
+                cell_of(rect.y + (rect.height - 1.0).max(0.0)),
+            ),
+        }
+    }
+
+    fn remove_from_cells(&mut self, entity: Entity, range: CellRange) {
+        for cell in range.cells() {
+            if let Some(entities) = self.cells.get_mut(&cell) {
+                entities.retain(|other| *other != entity);
+                if entities.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+}
+
+// FUNCTIONS ------
+/// Syncs the `CollisionGrid` with the collision boxes: new boxes are added, moved ones change
+/// cells when needed and the ones gone since the last frame are dropped.
+pub fn update_collision_grid(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let mut grid = ctx.resources.get_mut::<CollisionGrid>()?;
+
+    grid.begin_pass();
+    for (entity, collision_box) in world.query_mut::<&CollisionBox>() {
+        grid.update(entity, &collision_box.rect);
+    }
+    grid.end_pass();
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use hecs::{Entity, World};
+use raylib::prelude::*;
+
+use super::{
+    collision::{are_boxes_colliding, is_point_inside_box, CollisionBox, CollisionMask},
+    spatial_hash::CollisionGrid,
+};
+
+// Lookups go through the `CollisionGrid`, which is rebuilt in `Stage::PostUpdate`. Boxes are
+// tested where they are now, but a box moved into other cells since the last rebuild can be
+// missed until the next one.
+
+// FUNCTIONS ------
+/// Entities whose `CollisionBox` contains `point`, in entity order. Only boxes on a layer of
+/// `mask` count, `CollisionMask::ALL` disables the filter.
+pub fn entities_at_point(world: &World, grid: &CollisionGrid, point: Vector2, mask: CollisionMask) -> Vec<Entity> {
+    let area = Rectangle {
+        x: point.x,
+        y: point.y,
+        width: 1.0,
+        height: 1.0,
+    };
+    candidates(world, grid, &area, mask)
+        .into_iter()
+        .filter(|(_, rect)| is_point_inside_box(&point, rect))
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+/// Entities whose `CollisionBox` overlaps `rect`, in entity order
+pub fn entities_in_rect(world: &World, grid: &CollisionGrid, rect: &Rectangle, mask: CollisionMask) -> Vec<Entity> {
+    candidates(world, grid, rect, mask)
+        .into_iter()
+        .filter(|(_, other)| are_boxes_colliding(rect, other))
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+/// Entities whose `CollisionBox` has a point within `radius` of `center`, nearest first
+pub fn entities_in_radius(
+    world: &World,
+    grid: &CollisionGrid,
+    center: Vector2,
+    radius: f32,
+    mask: CollisionMask,
+) -> Vec<Entity> {
+    let area = Rectangle {
+        x: center.x - radius,
+        y: center.y - radius,
+        width: radius * 2.0 + 1.0,
+        height: radius * 2.0 + 1.0,
+    };
+    let mut found: Vec<(f32, Entity)> = candidates(world, grid, &area, mask)
+        .into_iter()
+        .map(|(entity, rect)| (distance_to_box(center, &rect), entity))
+        .filter(|(distance, _)| *distance <= radius)
+        .collect();
+    found.sort_by(|(distance1, entity1), (distance2, entity2)| {
+        distance1.total_cmp(distance2).then(entity1.cmp(entity2))
+    });
+
+    found.into_iter().map(|(_, entity)| entity).collect()
+}
+
+/// Distance from `point` to the closest pixel of `rect`, 0 when inside
+pub fn distance_to_box(point: Vector2, rect: &Rectangle) -> f32 {
+    let closest = Vector2 {
+        x: point.x.clamp(rect.x, rect.x + (rect.width - 1.0).max(0.0)),
+        y: point.y.clamp(rect.y, rect.y + (rect.height - 1.0).max(0.0)),
+    };
+
+    (closest - point).length()
+}
+
+/// Boxes of the grid cells covering `area` on a layer of `mask`, each entity once
+fn candidates(world: &World, grid: &CollisionGrid, area: &Rectangle, mask: CollisionMask) -> Vec<(Entity, Rectangle)> {
+    let mut entities: Vec<Entity> = grid.query_rect(area).collect();
+    entities.sort_unstable();
+    entities.dedup();
+
+    entities
+        .into_iter()
+        .filter_map(|entity| {
+            let col_box = world.get::<CollisionBox>(entity).ok()?;
+            if mask.contains(col_box.layer) {
+                Some((entity, col_box.rect))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::enums::CollisionLayer;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rectangle {
+        Rectangle { x, y, width, height }
+    }
+
+    /// Spawns boxes and adds them to a fresh grid
+    fn setup(boxes: &[(Rectangle, CollisionLayer)]) -> (World, CollisionGrid, Vec<Entity>) {
+        let mut world = World::new();
+        let mut grid = CollisionGrid::new(32.0);
+        let mut entities: Vec<Entity> = vec![];
+        for (rect, layer) in boxes.iter() {
+            let entity = world.spawn((CollisionBox::new(*rect, *layer, CollisionMask::NONE),));
+            grid.update(entity, rect);
+            entities.push(entity);
+        }
+
+        (world, grid, entities)
+    }
+
+    #[test]
+    fn entities_at_point_filters_by_box_and_mask() {
+        let (world, grid, entities) = setup(&[
+            (rect(0.0, 0.0, 16.0, 16.0), CollisionLayer::Villager),
+            (rect(8.0, 8.0, 32.0, 32.0), CollisionLayer::Building),
+        ]);
+
+        let point = Vector2 { x: 10.0, y: 10.0 };
+        assert_eq!(entities_at_point(&world, &grid, point, CollisionMask::ALL), entities);
+        assert_eq!(
+            entities_at_point(&world, &grid, point, CollisionMask::NONE.with(CollisionLayer::Building)),
+            vec![entities[1]]
+        );
+        // Boxes end one pixel before x + width
+        assert!(entities_at_point(&world, &grid, Vector2 { x: 16.0, y: 0.0 }, CollisionMask::ALL).is_empty());
+    }
+
+    #[test]
+    fn entities_in_rect_finds_overlapping_boxes_across_cells() {
+        let (world, grid, entities) = setup(&[
+            (rect(0.0, 0.0, 16.0, 16.0), CollisionLayer::Villager),
+            (rect(100.0, 100.0, 16.0, 16.0), CollisionLayer::Villager),
+            (rect(40.0, 0.0, 16.0, 16.0), CollisionLayer::Villager),
+        ]);
+
+        let found = entities_in_rect(&world, &grid, &rect(10.0, 0.0, 40.0, 8.0), CollisionMask::ALL);
+        assert_eq!(found, vec![entities[0], entities[2]]);
+    }
+
+    #[test]
+    fn entities_in_radius_sorts_nearest_first() {
+        let (world, grid, entities) = setup(&[
+            (rect(50.0, 0.0, 10.0, 10.0), CollisionLayer::Villager),
+            (rect(20.0, 0.0, 10.0, 10.0), CollisionLayer::Villager),
+            (rect(200.0, 0.0, 10.0, 10.0), CollisionLayer::Villager),
+        ]);
+
+        let found = entities_in_radius(&world, &grid, Vector2 { x: 0.0, y: 0.0 }, 60.0, CollisionMask::ALL);
+        assert_eq!(found, vec![entities[1], entities[0]]);
+    }
+
+    #[test]
+    fn distance_to_box_is_zero_inside() {
+        let area = rect(10.0, 10.0, 10.0, 10.0);
+
+        assert_eq!(distance_to_box(Vector2 { x: 15.0, y: 15.0 }, &area), 0.0);
+        assert_eq!(distance_to_box(Vector2 { x: 0.0, y: 15.0 }, &area), 10.0);
+        assert_eq!(distance_to_box(Vector2 { x: 22.0, y: 23.0 }, &area), 5.0);
+    }
+}
@@ -1,63 +1,103 @@
 use hecs::World;
-use raylib::{RaylibThread, RaylibHandle, prelude::Font};
+use raylib::{RaylibThread, RaylibHandle};
 
-use crate::engine::{TILESET, UI_ATLAS};
+use super::{
+    animation::advance_animations,
+    assets::Assets,
+    collision::{detect_body_collisions, detect_trigger_collisions, draw_collisions},
+    enums::Stage,
+    error::EngineError,
+    render::{draw_render_queue, RenderQueue},
+    resources::Resources,
+    schedule::{Schedule, System, SystemContext, SystemEntry},
+    spatial_hash::update_collision_grid,
+    ui::draw::{draw_mouse_selection, draw_ui},
+};
 
-pub fn world_setup<'a>(
+/// Loads the base textures and fonts into the `Assets` resource, creating it if needed, and
+/// runs the startup stage of `schedule`, which should have every system registered already.
+pub fn world_setup(
     world: &mut World, 
+    resources: &mut Resources,
+    schedule: &mut Schedule,
     raylib_handle: &mut RaylibHandle, 
     thread: &RaylibThread,
-    font_path: &str,
-    tileset_path: &str,
-    ui_atlas_path: &str,
-    m_game_setup: Option<fn(&mut World, &mut RaylibHandle, thread: &RaylibThread) -> Result<(), String>>
-) -> Result<Font, String> {
-    load_tileset(raylib_handle, thread, tileset_path, ui_atlas_path)?;
-
-    if let Some(game_setup) = m_game_setup { 
-        (game_setup)(world, raylib_handle, thread)?;
+    texture_paths: &[&str],
+    font_paths: &[&str],
+) -> Result<(), EngineError> {
+    let mut assets = resources.remove::<Assets>().unwrap_or_default();
+    for path in texture_paths {
+        assets.load_texture(raylib_handle, thread, path)?;
+    }
+    for path in font_paths {
+        assets.load_font(raylib_handle, thread, path)?;
     }
+    resources.insert(assets);
+    resources.insert(RenderQueue::new());
 
-    return raylib_handle.load_font(thread, &font_path);
+    let mut ctx = SystemContext {
+        delta: 0.0,
+        resources,
+        m_raylib_handle: Some(raylib_handle),
+        m_camera: None,
+    };
+    schedule.run_stage(Stage::Startup, world, &mut ctx)
 }
 
-fn load_tileset(
-    raylib_handle: &mut RaylibHandle, 
-    thread: &RaylibThread, 
-    tileset_path: &str, 
-    ui_atlas_path: &str
-) -> Result<(), String> {
-    let result = raylib_handle
-            .load_texture(&thread, &tileset_path);
-    match result {
-        Ok(texture) => {
-            if let Err(_) = TILESET.set(texture) {
-                error!("Failed to set tileset static variable.");
-                return Err("Crash".to_string());
-            }
-            info!("Tileset loaded");
-        },
-        Err(..) => {
-            error!("Failed to load tileset on path {:?}", tileset_path);
-            return Err("Crash".to_string());
-        }
-    }
-
-    let result = raylib_handle
-            .load_texture(&thread, &ui_atlas_path);
-    match result {
-        Ok(texture) => {
-            if let Err(_) = UI_ATLAS.set(texture) {
-                error!("Failed to set ui atlas static variable.");
-                return Err("Crash".to_string());
-            }
-            info!("UI atlas loaded");
-        },
-        Err(..) => {
-            error!("Failed to load tileset on path {:?}", ui_atlas_path);
-            return Err("Crash".to_string());
-        }
-    }
+/// Registers the systems every game gets from the engine: collision detection, sprite animation,
+/// the render queue and the core collision, mouse selection and UI drawing.
+pub fn register_engine_systems(schedule: &mut Schedule) {
+    schedule.add_system(SystemEntry::new(
+        "update_collision_grid",
+        Stage::PostUpdate,
+        System::Update(update_collision_grid),
+    ));
+    schedule.add_system(
+        SystemEntry::new(
+            "detect_body_collisions",
+            Stage::PostUpdate,
+            System::Update(detect_body_collisions),
+        )
+        .after("update_collision_grid"),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "detect_trigger_collisions",
+            Stage::PostUpdate,
+            System::Update(detect_trigger_collisions),
+        )
+        .after("update_collision_grid"),
+    );
+    schedule.add_system(SystemEntry::new(
+        "advance_animations",
+        Stage::PostUpdate,
+        System::Update(advance_animations),
+    ));
 
-    Ok(())
+    schedule.add_system(
+        SystemEntry::new(
+            "draw_collisions",
+            Stage::Render,
+            System::DrawWorld(draw_collisions),
+        )
+        .before("draw_render_queue"),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "draw_mouse_selection",
+            Stage::Render,
+            System::DrawWorld(draw_mouse_selection),
+        )
+        .before("draw_render_queue"),
+    );
+    schedule.add_system(SystemEntry::new(
+        "draw_render_queue",
+        Stage::Render,
+        System::DrawWorld(draw_render_queue),
+    ));
+    schedule.add_system(SystemEntry::new(
+        "draw_ui",
+        Stage::Render,
+        System::DrawScreen(draw_ui),
+    ));
 }
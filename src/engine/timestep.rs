@@ -0,0 +1,66 @@
+// STRUCTS ------
+/// Accumulates render frame time and hands out a whole number of fixed simulation ticks, so the
+/// simulation advances by the same `step` regardless of the render frame rate.
+pub struct FixedTimestep {
+    pub step: f32,
+    pub max_ticks_per_frame: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f32) -> FixedTimestep {
+        FixedTimestep {
+            step,
+            max_ticks_per_frame: 5,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Adds the frame time to the accumulator and returns how many ticks should run this frame.
+    /// Time beyond `max_ticks_per_frame` ticks is dropped so a long stall can't snowball.
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += frame_time;
+
+        let mut ticks: u32 = 0;
+        while self.accumulator >= self.step && ticks < self.max_ticks_per_frame {
+            self.accumulator -= self.step;
+            ticks += 1;
+        }
+
+        if ticks == self.max_ticks_per_frame {
+            self.accumulator = self.accumulator.min(self.step);
+        }
+
+        ticks
+    }
+}
+
+/// Number of simulation ticks run so far. Stored as a resource.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct SimulationTick(pub u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_carries_leftover_time() {
+        let mut timestep = FixedTimestep::new(0.25);
+
+        assert_eq!(timestep.advance(0.1), 0);
+        assert_eq!(timestep.advance(0.2), 1);
+        assert_eq!(timestep.advance(0.2), 1);
+        assert_eq!(timestep.advance(0.5), 2);
+    }
+
+    #[test]
+    fn advance_caps_ticks_per_frame() {
+        let mut timestep = FixedTimestep::new(0.25);
+        timestep.max_ticks_per_frame = 3;
+
+        assert_eq!(timestep.advance(10.0), 3);
+        // Only up to one step of the stall is kept
+        assert_eq!(timestep.advance(0.0), 1);
+        assert_eq!(timestep.advance(0.0), 0);
+    }
+}
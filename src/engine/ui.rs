@@ -6,8 +6,9 @@ use hecs::{World, Entity};
 use raylib::prelude::*;
 
 use self::datatypes::{MouseSelection, DebugUI, Label, UIElement};
+use super::{assets::Handle, error::EngineError};
 use self::datatypes::ToggleButton;
-use self::datatypes::Button;
+use self::datatypes::{Button, ButtonAction, HandleAction};
 
 // FUNCTIONS ------
 pub fn spawn_button(
@@ -15,10 +16,11 @@ pub fn spawn_button(
     position: Vector2, 
     offset: Vector2, 
     layer: i32,
+    texture: Handle<Texture2D>,
     atlas_tile: Vector2, 
     tile_size: f32,
-    action: Option<fn(&mut World) -> Result<(), String>>,
-    handle_action: Option<fn(&mut World, &mut RaylibHandle) -> Result<(), String>>,
+    action: Option<ButtonAction>,
+    handle_action: Option<HandleAction>,
 ) -> Entity {
     let ui_element = UIElement {
         position,
@@ -27,6 +29,7 @@ pub fn spawn_button(
         visible: true
     };
     let button = Button::new(
+        texture,
         atlas_tile,
         tile_size,
         action,
@@ -41,10 +44,11 @@ pub fn spawn_toggle_button(
     position: Vector2, 
     offset: Vector2, 
     layer: i32,
+    texture: Handle<Texture2D>,
     atlas_tile: Vector2, 
     tile_size: f32,
-    basic_action: Option<fn(&mut World) -> Result<(), String>>,
-    handle_action: Option<fn(&mut World, &mut RaylibHandle) -> Result<(), String>>,
+    basic_action: Option<ButtonAction>,
+    handle_action: Option<HandleAction>,
 ) -> Entity {
     let ui_element = UIElement {
         position,
@@ -53,6 +57,7 @@ pub fn spawn_toggle_button(
         visible: true
     };
     let button = ToggleButton::new(
+        texture,
         atlas_tile,
         tile_size,
         basic_action,
@@ -67,6 +72,7 @@ pub fn spawn_label(
     position: Vector2, 
     offset: Vector2, 
     layer: i32,
+    font: Handle<Font>,
     font_size: f32,
     spacing: f32,
     color: Color
@@ -78,6 +84,7 @@ pub fn spawn_label(
         visible: true
     };
     let label = Label {
+        font,
         text: String::new(),
         font_size,
         spacing,
@@ -87,7 +94,7 @@ pub fn spawn_label(
     return world.spawn((label, ui_element));
 }
 
-pub fn toggle_mouse_selection(world: &mut World) -> Result<(), String> {
+pub fn toggle_mouse_selection(world: &mut World, texture: Handle<Texture2D>) -> Result<(), EngineError> {
     let mut entity_list: Vec<Entity> = vec![];
     {
         let mut selection_query = world.query::<&MouseSelection>();
@@ -98,10 +105,10 @@ pub fn toggle_mouse_selection(world: &mut World) -> Result<(), String> {
         }
     }
     if entity_list.len() <= 0 {
-        world.spawn((MouseSelection,));
+        world.spawn((MouseSelection { texture },));
     } else {
         for entity in entity_list {
-            world.despawn(entity).map_err(|_| "No such entity")?;
+            world.despawn(entity).map_err(|_| EngineError::NoSuchEntity(entity))?;
         }
     }
 
@@ -1,19 +1,28 @@
 use hecs::World;
 use raylib::prelude::*;
 
-use crate::engine::enums::ButtonState;
+use crate::engine::{assets::Handle, enums::ButtonState, error::EngineError, schedule::SystemContext};
+
+// TYPES ------
+/// Runs when a button is clicked, during `Stage::Input`
+pub type ButtonAction = fn(&mut World, &mut SystemContext) -> Result<(), EngineError>;
+/// Runs when a button is clicked, for actions on the window itself. Skipped without a window.
+pub type HandleAction = fn(&mut World, &mut RaylibHandle) -> Result<(), EngineError>;
 
 // TAGS ------
-pub struct MouseSelection;
 pub struct DebugUI;
 pub struct UIAtlas;
-pub struct CameraZoom(pub(crate) f32);
 
 // STRUCTS ------
-pub struct Fonts {
-    pub base_font: Font
+/// Highlights the tile under the mouse with a tile from `texture`
+pub struct MouseSelection {
+    pub texture: Handle<Texture2D>,
 }
 
+/// Size multiplier of UI elements, stored as a resource. Independent of the camera zoom.
+#[derive(Clone, Copy)]
+pub struct UiScale(pub f32);
+
 pub struct UIElement {
     pub position: Vector2,
     pub offset: Vector2,
@@ -22,6 +31,7 @@ pub struct UIElement {
 }
 
 pub struct Label {
+    pub font: Handle<Font>,
     pub text: String,
     pub font_size: f32,
     pub spacing: f32,
@@ -29,20 +39,23 @@ pub struct Label {
 }
 
 pub struct Button {
+    pub texture: Handle<Texture2D>,
     pub rect: Rectangle,
     pub state: ButtonState,
-    pub action: Option<fn(&mut World) -> Result<(), String>>,
-    pub handle_action: Option<fn(&mut World, &mut RaylibHandle) -> Result<(), String>>,
+    pub action: Option<ButtonAction>,
+    pub handle_action: Option<HandleAction>,
 }
 
 impl Button {
     pub fn new(
+        texture: Handle<Texture2D>,
         atlas_tile: Vector2, 
         tile_size: f32,
-        action: Option<fn(&mut World) -> Result<(), String>>,
-        handle_action: Option<fn(&mut World, &mut RaylibHandle) -> Result<(), String>>,
+        action: Option<ButtonAction>,
+        handle_action: Option<HandleAction>,
     ) -> Button {
         Button {
+            texture,
             state: ButtonState::Normal,
             action,
             handle_action,
@@ -57,20 +70,23 @@ impl Button {
 }
 
 pub struct ToggleButton {
+    pub texture: Handle<Texture2D>,
     pub rect: Rectangle,
     pub state: ButtonState,
-    pub action: Option<fn(&mut World) -> Result<(), String>>,
-    pub handle_action: Option<fn(&mut World, &mut RaylibHandle) -> Result<(), String>>,
+    pub action: Option<ButtonAction>,
+    pub handle_action: Option<HandleAction>,
 }
 
 impl ToggleButton {
     pub fn new(
+        texture: Handle<Texture2D>,
         atlas_tile: Vector2, 
         tile_size: f32,
-        action: Option<fn(&mut World) -> Result<(), String>>,
-        handle_action: Option<fn(&mut World, &mut RaylibHandle) -> Result<(), String>>,
+        action: Option<ButtonAction>,
+        handle_action: Option<HandleAction>,
     ) -> ToggleButton {
         ToggleButton {
+            texture,
             state: ButtonState::Normal,
             action,
             handle_action,
@@ -1,14 +1,23 @@
-use hecs::{Entity, World};
-use raylib::{prelude::*, text::Font};
+use hecs::World;
+use raylib::prelude::*;
 
-use crate::engine::{enums::ButtonState, TILESET, UI_ATLAS};
+use crate::engine::{
+    assets::Assets,
+    enums::{ButtonState, RenderLayer, Rotation},
+    error::EngineError,
+    render::{DrawCommand, RenderQueue},
+    schedule::DrawContext,
+};
 
 use super::{
-    datatypes::{CameraZoom, Label, UIElement},
+    datatypes::{Label, UIElement, UiScale},
     Button, DebugUI, MouseSelection, ToggleButton,
 };
 
-pub fn draw_ui(world: &mut World, draw_handle: &mut RaylibDrawHandle, font: &Font, tile_size: f32) {
+pub fn draw_ui(world: &mut World, draw_handle: &mut RaylibDrawHandle, ctx: &DrawContext) -> Result<(), EngineError> {
+    let UiScale(scale) = *ctx.resources.get::<UiScale>()?;
+    let assets = ctx.resources.get::<Assets>()?;
+
     {
         let mut debug_query = world.query::<&DebugUI>();
         debug_query.into_iter().for_each(|_| {
@@ -17,22 +26,24 @@ pub fn draw_ui(world: &mut World, draw_handle: &mut RaylibDrawHandle, font: &Fon
     }
 
     for layer in 0..101 {
-        draw_ui_buttons(world, draw_handle, layer, tile_size);
-        draw_ui_toggle_buttons(world, draw_handle, layer, tile_size);
-        draw_labels(world, draw_handle, font, layer);
+        draw_ui_buttons(world, draw_handle, &assets, layer, ctx.tile_size, scale)?;
+        draw_ui_toggle_buttons(world, draw_handle, &assets, layer, ctx.tile_size, scale)?;
+        draw_labels(world, draw_handle, &assets, layer)?;
     }
+
+    Ok(())
 }
 
 pub fn draw_ui_buttons(
     world: &mut World,
     draw_handle: &mut RaylibDrawHandle,
+    assets: &Assets,
     layer: i32,
     tile_size: f32,
-) {
-    let mut zoom_query = world.query::<&CameraZoom>();
-    let (_, CameraZoom(zoom)): (Entity, &CameraZoom) = zoom_query.into_iter().nth(0).unwrap();
+    scale: f32,
+) -> Result<(), EngineError> {
     let mut query = world.query::<(&Button, &UIElement)>();
-    query.into_iter().for_each(|(_, (button, element))| {
+    for (_, (button, element)) in query.into_iter() {
         if element.layer == layer && element.visible {
             let mut src = button.rect.clone();
             match button.state {
@@ -46,12 +57,12 @@ pub fn draw_ui_buttons(
             }
             let dest = Rectangle {
                 x: element.position.x,
-                y: element.position.y - (tile_size * zoom),
-                width: tile_size * zoom,
-                height: tile_size * zoom,
+                y: element.position.y - (tile_size * scale),
+                width: tile_size * scale,
+                height: tile_size * scale,
             };
             draw_handle.draw_texture_pro(
-                UI_ATLAS.get().unwrap(),
+                assets.get(button.texture)?,
                 src,
                 dest,
                 Vector2::zero(),
@@ -59,19 +70,21 @@ pub fn draw_ui_buttons(
                 Color::WHITE,
             );
         }
-    });
+    }
+
+    Ok(())
 }
 
 pub fn draw_ui_toggle_buttons(
     world: &mut World,
     draw_handle: &mut RaylibDrawHandle,
+    assets: &Assets,
     layer: i32,
     tile_size: f32,
-) {
-    let mut zoom_query = world.query::<&CameraZoom>();
-    let (_, CameraZoom(zoom)) = zoom_query.into_iter().nth(0).unwrap();
+    scale: f32,
+) -> Result<(), EngineError> {
     let mut query = world.query::<(&ToggleButton, &UIElement)>();
-    query.into_iter().for_each(|(_, (button, element))| {
+    for (_, (button, element)) in query.into_iter() {
         if element.layer == layer && element.visible {
             let mut src = button.rect.clone();
             match button.state {
@@ -88,12 +101,12 @@ pub fn draw_ui_toggle_buttons(
             }
             let dest = Rectangle {
                 x: element.position.x,
-                y: element.position.y - (tile_size * zoom),
-                width: tile_size * zoom,
-                height: tile_size * zoom,
+                y: element.position.y - (tile_size * scale),
+                width: tile_size * scale,
+                height: tile_size * scale,
             };
             draw_handle.draw_texture_pro(
-                UI_ATLAS.get().unwrap(),
+                assets.get(button.texture)?,
                 src,
                 dest,
                 Vector2::zero(),
@@ -101,18 +114,21 @@ pub fn draw_ui_toggle_buttons(
                 Color::WHITE,
             );
         }
-    });
+    }
+
+    Ok(())
 }
 
 pub fn draw_mouse_selection(
     world: &mut World,
     draw_handle: &mut RaylibMode2D<RaylibDrawHandle>,
-    camera: &Camera2D,
-    tile_size: f32,
-) {
-    let mouse_pos = draw_handle.get_screen_to_world2D(draw_handle.get_mouse_position(), camera);
+    ctx: &DrawContext,
+) -> Result<(), EngineError> {
+    let tile_size = ctx.tile_size;
+    let mut render_queue = ctx.resources.get_mut::<RenderQueue>()?;
+    let mouse_pos = draw_handle.get_screen_to_world2D(draw_handle.get_mouse_position(), ctx.camera);
     let mut selection_query = world.query::<&MouseSelection>();
-    selection_query.into_iter().for_each(|(_, _)| {
+    for (_, selection) in selection_query.into_iter() {
         let mut current_tile_x = (mouse_pos.x / tile_size) as i32;
         let mut current_tile_y = (mouse_pos.y / tile_size) as i32;
 
@@ -134,16 +150,33 @@ pub fn draw_mouse_selection(
             height: tile_size,
             width: tile_size,
         };
-        draw_handle.draw_texture_rec(TILESET.get().unwrap(), rect, tile_position, Color::WHITE);
-    });
+        render_queue.push(
+            RenderLayer::Overlay,
+            tile_position.y + tile_size,
+            DrawCommand::Texture {
+                texture: selection.texture,
+                source: rect,
+                position: tile_position,
+                rotation: Rotation::Deg0,
+                tint: Color::WHITE,
+            },
+        );
+    }
+
+    Ok(())
 }
 
-pub fn draw_labels(world: &mut World, draw_handle: &mut RaylibDrawHandle, font: &Font, layer: i32) {
+pub fn draw_labels(
+    world: &mut World,
+    draw_handle: &mut RaylibDrawHandle,
+    assets: &Assets,
+    layer: i32,
+) -> Result<(), EngineError> {
     let mut query = world.query::<(&Label, &UIElement)>();
-    query.into_iter().for_each(|(_, (label, element))| {
+    for (_, (label, element)) in query.into_iter() {
         if element.layer == layer && element.visible {
             draw_handle.draw_text_ex(
-                font,
+                assets.get(label.font)?,
                 &label.text,
                 element.position + element.offset,
                 label.font_size,
@@ -151,5 +184,7 @@ pub fn draw_labels(world: &mut World, draw_handle: &mut RaylibDrawHandle, font:
                 label.color,
             );
         }
-    });
+    }
+
+    Ok(())
 }
@@ -0,0 +1,76 @@
+use raylib::consts::GamepadButton::*;
+use raylib::consts::KeyboardKey::*;
+use raylib::consts::MouseButton::*;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::actions::{ActionMap, InputBinding};
+
+// TYPES ------
+pub type GameActions = ActionMap<Action>;
+
+// STRUCTS ------
+/// Everything the player can do from an input device. Bound to inputs through `GameActions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    CameraPanUp,
+    CameraPanDown,
+    CameraPanLeft,
+    CameraPanRight,
+    /// Pans the camera along with the mouse while held
+    CameraDrag,
+    ZoomIn,
+    ZoomOut,
+    /// Clicks UI buttons
+    Click,
+    PlaceBuilding,
+    CancelPlacement,
+    /// Turns the building being placed a quarter clockwise
+    RotateBuilding,
+    ToggleSelection,
+    Pause,
+    QuitToMenu,
+    MenuUp,
+    MenuDown,
+    MenuConfirm,
+    QuickSave,
+    QuickLoad,
+}
+
+// FUNCTIONS ------
+pub fn default_actions() -> GameActions {
+    use InputBinding::{GamepadButton as Pad, Key, MouseButton as Mouse};
+
+    let defaults = [
+        (Action::CameraPanUp, vec![Key(KEY_W), Pad(GAMEPAD_BUTTON_LEFT_FACE_UP)]),
+        (Action::CameraPanDown, vec![Key(KEY_S), Pad(GAMEPAD_BUTTON_LEFT_FACE_DOWN)]),
+        (Action::CameraPanLeft, vec![Key(KEY_A), Pad(GAMEPAD_BUTTON_LEFT_FACE_LEFT)]),
+        (Action::CameraPanRight, vec![Key(KEY_D), Pad(GAMEPAD_BUTTON_LEFT_FACE_RIGHT)]),
+        (Action::CameraDrag, vec![Mouse(MOUSE_MIDDLE_BUTTON)]),
+        (Action::ZoomIn, vec![Key(KEY_EQUAL), Pad(GAMEPAD_BUTTON_RIGHT_TRIGGER_1)]),
+        (Action::ZoomOut, vec![Key(KEY_MINUS), Pad(GAMEPAD_BUTTON_LEFT_TRIGGER_1)]),
+        (Action::Click, vec![Mouse(MOUSE_LEFT_BUTTON)]),
+        (Action::PlaceBuilding, vec![Mouse(MOUSE_LEFT_BUTTON)]),
+        (
+            Action::CancelPlacement,
+            vec![Mouse(MOUSE_RIGHT_BUTTON), Key(KEY_BACKSPACE), Pad(GAMEPAD_BUTTON_RIGHT_FACE_RIGHT)],
+        ),
+        (Action::RotateBuilding, vec![Key(KEY_R), Pad(GAMEPAD_BUTTON_RIGHT_FACE_UP)]),
+        (Action::ToggleSelection, vec![Key(KEY_F10)]),
+        (Action::Pause, vec![Key(KEY_P), Pad(GAMEPAD_BUTTON_MIDDLE_RIGHT)]),
+        (Action::QuitToMenu, vec![Key(KEY_M), Pad(GAMEPAD_BUTTON_MIDDLE_LEFT)]),
+        (Action::MenuUp, vec![Key(KEY_UP), Key(KEY_W), Pad(GAMEPAD_BUTTON_LEFT_FACE_UP)]),
+        (Action::MenuDown, vec![Key(KEY_DOWN), Key(KEY_S), Pad(GAMEPAD_BUTTON_LEFT_FACE_DOWN)]),
+        (Action::MenuConfirm, vec![Key(KEY_ENTER), Pad(GAMEPAD_BUTTON_RIGHT_FACE_DOWN)]),
+        (Action::QuickSave, vec![Key(KEY_F5)]),
+        (Action::QuickLoad, vec![Key(KEY_F9)]),
+    ];
+
+    let mut actions = GameActions::new();
+    for (action, bindings) in defaults {
+        for binding in bindings {
+            actions.bind(action, binding);
+        }
+    }
+
+    actions
+}
@@ -0,0 +1,202 @@
+use hecs::{Entity, World};
+use raylib::prelude::*;
+
+use crate::engine::{
+    animation::{AnimationClip, AnimationFrame, Animator},
+    datatypes::Sprite,
+    error::EngineError,
+    schedule::SystemContext,
+};
+
+use super::{
+    buildings::{
+        datatypes::{Building, BuildingKind, ConstructionPlacement, ConstructionStorage, Footprint, OngoingConstruction},
+        definitions::BuildingDefinitions,
+    },
+    constants::{CONSTRUCTION_PROGRESS_RECT, CONSTRUCTION_RECT, TILE_SIZE},
+    villagers::datatypes::{CarryingState, Hauler, LoadingState},
+};
+
+// Villager clips
+pub const IDLE_CLIP: &str = "idle";
+pub const WALK_CLIP: &str = "walk";
+pub const CARRY_CLIP: &str = "carry";
+
+// Building clips
+pub const PLACED_CLIP: &str = "placed";
+pub const CONSTRUCTION_CLIP: &str = "construction";
+/// One-shot played when construction ends, settles on the building tile
+pub const BUILT_CLIP: &str = "built";
+pub const FINISHED_CLIP: &str = "finished";
+
+// FUNCTIONS ------
+/// The villager tile bobs up and down, faster when walking somewhere and lower when carrying
+pub fn villager_animator(rect: Rectangle) -> Animator {
+    let bob = |duration: f32, low: f32, high: f32| {
+        AnimationClip::looping(vec![
+            AnimationFrame::new(rect, duration).with_offset(Vector2 { x: 0.0, y: low }),
+            AnimationFrame::new(rect, duration).with_offset(Vector2 { x: 0.0, y: high }),
+        ])
+    };
+
+    Animator::new(IDLE_CLIP, bob(0.4, 0.0, -1.0))
+        .with_clip(WALK_CLIP, bob(0.15, 0.0, -1.0))
+        .with_clip(CARRY_CLIP, bob(0.25, 1.0, 0.0))
+}
+
+/// Starts on `initial`, `FINISHED_CLIP` for buildings spawned already built. The construction
+/// clips show a single tile, repeated over the footprint when drawn.
+pub fn building_animator(rect: Rectangle, initial: &'static str) -> Animator {
+    let clip = |name: &str| match name {
+        PLACED_CLIP => AnimationClip::still(CONSTRUCTION_RECT),
+        CONSTRUCTION_CLIP => AnimationClip::looping(vec![
+            AnimationFrame::new(CONSTRUCTION_RECT, 0.5),
+            AnimationFrame::new(CONSTRUCTION_PROGRESS_RECT, 0.5),
+        ]),
+        BUILT_CLIP => AnimationClip::one_shot(vec![
+            AnimationFrame::new(rect, 0.1).with_offset(Vector2 { x: 0.0, y: -2.0 }),
+            AnimationFrame::new(rect, 0.1).with_offset(Vector2 { x: 0.0, y: -1.0 }),
+            AnimationFrame::new(rect, 0.0),
+        ]),
+        _ => AnimationClip::still(rect),
+    };
+
+    let mut animator = Animator::new(initial, clip(initial));
+    for name in [FINISHED_CLIP, PLACED_CLIP, CONSTRUCTION_CLIP, BUILT_CLIP] {
+        if name != initial {
+            animator = animator.with_clip(name, clip(name));
+        }
+    }
+
+    animator
+}
+
+/// Atlas rectangle of building art starting at `atlas_tile` and spanning the whole footprint
+pub fn atlas_rect(atlas_tile: Vector2, footprint: &Footprint) -> Rectangle {
+    let size = footprint.size();
+    Rectangle {
+        x: atlas_tile.x * TILE_SIZE,
+        y: atlas_tile.y * TILE_SIZE,
+        width: size.x,
+        height: size.y,
+    }
+}
+
+/// Animator for an entity rebuilt from a save, picked from its components. Clips start over,
+/// they aren't saved.
+pub fn animator_for(
+    world: &World,
+    definitions: &BuildingDefinitions,
+    entity: Entity,
+) -> Result<Option<Animator>, EngineError> {
+    if world.get::<Hauler>(entity).is_ok() {
+        let sprite = world.get::<Sprite>(entity).map_err(EngineError::component::<Sprite>(entity))?;
+        return Ok(Some(villager_animator(sprite.rect)));
+    }
+
+    let definition = match world.get::<BuildingKind>(entity) {
+        Ok(kind) => definitions.get(&kind.0)?,
+        Err(_) => return Ok(None),
+    };
+    let initial = building_clip(world, entity, FINISHED_CLIP);
+
+    // The art is laid out unturned in the atlas, whatever the footprint of the building
+    Ok(Some(building_animator(atlas_rect(definition.atlas_tile(), &definition.footprint), initial)))
+}
+
+/// Plays the clip matching each villager's state
+pub fn update_villager_animations(world: &mut World, _ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let query = world
+        .query_mut::<(&mut Animator, Option<&LoadingState>, Option<&CarryingState>)>()
+        .with::<Hauler>();
+    for (_, (animator, loading, carrying)) in query {
+        let clip = if loading.is_some() {
+            WALK_CLIP
+        } else if carrying.is_some() {
+            CARRY_CLIP
+        } else {
+            IDLE_CLIP
+        };
+        animator.play(clip)?;
+    }
+
+    Ok(())
+}
+
+/// Plays the clip matching each building's construction progress
+pub fn update_building_animations(world: &mut World, _ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let mut clips: Vec<(Entity, &'static str)> = vec![];
+    {
+        let mut query = world.query::<&Animator>().with::<Building>();
+        for (entity, animator) in query.iter() {
+            clips.push((entity, building_clip(world, entity, animator.current_clip())));
+        }
+    }
+
+    for (entity, clip) in clips {
+        world
+            .get_mut::<Animator>(entity)
+            .map_err(EngineError::component::<Animator>(entity))?
+            .play(clip)?;
+    }
+
+    Ok(())
+}
+
+/// Clip a building in its current state plays, coming from `current`
+fn building_clip(world: &World, entity: Entity, current: &'static str) -> &'static str {
+    if world.get::<ConstructionPlacement>(entity).is_ok() || world.get::<ConstructionStorage>(entity).is_ok() {
+        PLACED_CLIP
+    } else if world.get::<OngoingConstruction>(entity).is_ok() {
+        CONSTRUCTION_CLIP
+    } else if current == PLACED_CLIP || current == CONSTRUCTION_CLIP {
+        BUILT_CLIP
+    } else {
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        engine::{events::Events, resources::Resources},
+        game::{
+            buildings::{
+                spawn::spawn_building,
+                step::{advance_construction, check_construction_resources, place_construction_resource},
+            },
+            events::GameEvent,
+            headless::tests::game_data,
+            villagers::datatypes::GameItem,
+        },
+    };
+
+    use super::*;
+
+    fn clip_after_update(world: &mut World, resources: &mut Resources, building: Entity) -> &'static str {
+        update_building_animations(world, &mut SystemContext::new(0.0, resources)).unwrap();
+        world.get::<Animator>(building).unwrap().current_clip()
+    }
+
+    #[test]
+    fn building_clips_follow_construction_progress() {
+        let (_, _, content) = game_data();
+        let mut world = World::new();
+        let mut resources = Resources::new();
+        resources.insert(Events::<GameEvent>::new());
+        let building = spawn_building(&mut world, &content.buildings, "house", Vector2::zero(), false).unwrap();
+        resources.insert(content.buildings);
+        assert_eq!(clip_after_update(&mut world, &mut resources, building), PLACED_CLIP);
+
+        let cost = world.get::<ConstructionStorage>(building).unwrap().required_item_list.clone();
+        for (resource, amount) in cost {
+            assert!(place_construction_resource(&mut world, building, GameItem::new(resource, amount)));
+        }
+        check_construction_resources(&mut world, &mut SystemContext::new(0.0, &mut resources)).unwrap();
+        assert_eq!(clip_after_update(&mut world, &mut resources, building), CONSTRUCTION_CLIP);
+
+        let work_required = world.get::<OngoingConstruction>(building).unwrap().work_required;
+        advance_construction(&mut world, &mut SystemContext::new(work_required, &mut resources)).unwrap();
+        assert_eq!(clip_after_update(&mut world, &mut resources, building), BUILT_CLIP);
+    }
+}
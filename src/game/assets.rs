@@ -0,0 +1,19 @@
+use raylib::{prelude::Font, texture::Texture2D};
+
+use crate::engine::assets::Handle;
+
+use super::constants::{FONT_PATH, TILESET_PATH, UI_ATLAS_PATH};
+
+// FUNCTIONS ------
+/// Handles to the assets the game loads at startup
+pub fn tileset() -> Handle<Texture2D> {
+    Handle::from_path(TILESET_PATH)
+}
+
+pub fn ui_atlas() -> Handle<Texture2D> {
+    Handle::from_path(UI_ATLAS_PATH)
+}
+
+pub fn base_font() -> Handle<Font> {
+    Handle::from_path(FONT_PATH)
+}
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use hecs::{Entity, World};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use raylib::prelude::*;
+
+use crate::engine::{
+    collision::{
+        detect_body_collisions, detect_trigger_collisions, find_collisions_brute_force, BodyCollision,
+        CollisionBox, Contacts, TriggerCollision,
+    },
+    enums::CollisionLayer,
+    error::EngineError,
+    resources::Resources,
+    schedule::SystemContext,
+    spatial_hash::{update_collision_grid, CollisionGrid},
+};
+
+use super::constants::{BUILDING_COLLISION_MASK, COLLISION_CELL_SIZE, TILE_SIZE, VILLAGER_COLLISION_MASK};
+
+const BENCH_FRAMES: u32 = 20;
+
+/// Spawns `count` moving collision boxes and times collision detection through the
+/// `CollisionGrid` against testing every pair, checking both find the same collisions.
+/// Meant to be run on a release build.
+pub fn run_collision_bench(count: u32, seed: u64) -> Result<(), EngineError> {
+    let mut rng = ChaCha12Rng::seed_from_u64(seed);
+    let mut world = World::new();
+    let mut resources = Resources::new();
+    resources.insert(CollisionGrid::new(COLLISION_CELL_SIZE));
+
+    // Roughly one box every four tiles, so most boxes touch a few others
+    let side = (count as f32).sqrt() * TILE_SIZE * 2.0;
+    for _ in 0..count {
+        let rect = Rectangle {
+            x: rng.gen_range(0.0..side),
+            y: rng.gen_range(0.0..side),
+            width: TILE_SIZE,
+            height: TILE_SIZE,
+        };
+        let collision_box = if rng.gen_bool(0.5) {
+            CollisionBox::new(rect, CollisionLayer::Villager, VILLAGER_COLLISION_MASK)
+        } else {
+            CollisionBox::new(rect, CollisionLayer::Building, BUILDING_COLLISION_MASK)
+        };
+        if rng.gen_bool(0.5) {
+            world.spawn((collision_box, BodyCollision::default()));
+        } else {
+            world.spawn((collision_box, TriggerCollision::new()));
+        }
+    }
+
+    let mut grid_time = Duration::ZERO;
+    let mut brute_force_time = Duration::ZERO;
+    let mut mismatches = 0;
+    for _ in 0..BENCH_FRAMES {
+        for (_, collision_box) in world.query_mut::<&mut CollisionBox>() {
+            collision_box.rect.x += rng.gen_range(-2.0..2.0);
+            collision_box.rect.y += rng.gen_range(-2.0..2.0);
+        }
+
+        let start = Instant::now();
+        let mut ctx = SystemContext::new(0.0, &mut resources);
+        update_collision_grid(&mut world, &mut ctx)?;
+        detect_body_collisions(&mut world, &mut ctx)?;
+        detect_trigger_collisions(&mut world, &mut ctx)?;
+        grid_time += start.elapsed();
+
+        let start = Instant::now();
+        let bodies = collect_boxes::<BodyCollision>(&world);
+        let triggers = collect_boxes::<TriggerCollision>(&world);
+        let body_collisions = find_collisions_brute_force(&bodies);
+        let trigger_collisions = find_collisions_brute_force(&triggers);
+        brute_force_time += start.elapsed();
+
+        let differs = |contacts: &Contacts, expected: Option<&Vec<Entity>>| {
+            !contacts.touching().eq(expected.into_iter().flatten().copied())
+        };
+        for (entity, body) in world.query::<&BodyCollision>().iter() {
+            if differs(&body.contacts, body_collisions.get(&entity)) {
+                mismatches += 1;
+            }
+        }
+        for (entity, trigger) in world.query::<&TriggerCollision>().iter() {
+            if differs(&trigger.contacts, trigger_collisions.get(&entity)) {
+                mismatches += 1;
+            }
+        }
+    }
+
+    let colliding = world.query::<&BodyCollision>().iter().filter(|(_, body)| body.colliding).count()
+        + world.query::<&TriggerCollision>().iter().filter(|(_, trigger)| trigger.colliding).count();
+    let per_frame = |time: Duration| time.as_secs_f64() * 1000.0 / BENCH_FRAMES as f64;
+
+    println!("Collision benchmark: {} boxes over {} frames ({} colliding on the last one)", count, BENCH_FRAMES, colliding);
+    println!("Every pair:      {:>9.3} ms/frame", per_frame(brute_force_time));
+    println!("Collision grid:  {:>9.3} ms/frame", per_frame(grid_time));
+    println!("Speedup:         {:>9.1}x", brute_force_time.as_secs_f64() / grid_time.as_secs_f64());
+    if mismatches == 0 {
+        println!("Both found the same collisions");
+    } else {
+        println!("Results differ on {} box frames", mismatches);
+    }
+
+    Ok(())
+}
+
+fn collect_boxes<T: hecs::Component>(world: &World) -> Vec<(Entity, CollisionBox)> {
+    world
+        .query::<&CollisionBox>()
+        .with::<T>()
+        .iter()
+        .map(|(entity, collision_box)| (entity, *collision_box))
+        .collect()
+}
@@ -1,8 +1,12 @@
 use std::collections::HashMap;
 
 use raylib::prelude::*;
+use serde::Deserialize;
 
-use crate::engine::enums::GameResource;
+use crate::{
+    engine::enums::Rotation,
+    game::{constants::TILE_SIZE, resource_registry::ResourceId},
+};
 
 // TAGS ------
 pub struct Building;
@@ -16,16 +20,147 @@ pub struct ConstructionPlacement {
     pub building_rect: Rectangle,
 }
 
+/// Type id of the `BuildingDefinition` a building was spawned from
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildingKind(pub String);
+
 pub struct StorageSpace {
-    pub item_list: HashMap<GameResource, i32>,
-    pub reserved_item_list: HashMap<GameResource, i32>,
+    pub item_list: HashMap<ResourceId, i32>,
+    pub reserved_item_list: HashMap<ResourceId, i32>,
+    /// Total amount of resources the storage holds at most
+    pub capacity: i32,
+}
+
+impl StorageSpace {
+    pub fn new(capacity: i32) -> StorageSpace {
+        StorageSpace {
+            item_list: HashMap::new(),
+            reserved_item_list: HashMap::new(),
+            capacity,
+        }
+    }
+
+    pub fn stored(&self) -> i32 {
+        self.item_list.values().sum()
+    }
+
+    pub fn has_room_for(&self, amount: i32) -> bool {
+        self.stored() + amount <= self.capacity
+    }
 }
 
 pub struct ConstructionStorage {
     pub tasks_generated: bool,
-    pub required_item_list: HashMap<GameResource, i32>,
+    pub required_item_list: HashMap<ResourceId, i32>,
 }
 
 pub struct OngoingConstruction {
     pub work_required: f32,
 }
+
+/// Tiles a building covers, `position` being the top left corner of its top left tile. Sizes
+/// and the entrance are the turned ones, the building art is drawn turned by `rotation`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Footprint {
+    pub width: i32,
+    pub height: i32,
+    /// Tile haulers pick up from and deliver to, counted from the top left tile
+    pub entrance: (i32, i32),
+    /// Definitions describe buildings unturned
+    #[serde(skip)]
+    pub rotation: Rotation,
+}
+
+impl Footprint {
+    /// The footprint turned a quarter clockwise, entrance included
+    pub fn rotated_clockwise(&self) -> Footprint {
+        Footprint {
+            width: self.height,
+            height: self.width,
+            entrance: (self.height - 1 - self.entrance.1, self.entrance.0),
+            rotation: self.rotation.clockwise(),
+        }
+    }
+
+    pub fn size(&self) -> Vector2 {
+        Vector2 {
+            x: self.width as f32 * TILE_SIZE,
+            y: self.height as f32 * TILE_SIZE,
+        }
+    }
+
+    pub fn rect(&self, position: Vector2) -> Rectangle {
+        let size = self.size();
+        Rectangle {
+            x: position.x,
+            y: position.y,
+            width: size.x,
+            height: size.y,
+        }
+    }
+
+    pub fn entrance_position(&self, position: Vector2) -> Vector2 {
+        position + self.tile_offset(self.entrance)
+    }
+
+    /// Offsets of every covered tile from `position`, row by row
+    pub fn tile_offsets(&self) -> impl Iterator<Item = Vector2> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| self.tile_offset((x, y))))
+    }
+
+    fn tile_offset(&self, tile: (i32, i32)) -> Vector2 {
+        Vector2 {
+            x: tile.0 as f32 * TILE_SIZE,
+            y: tile.1 as f32 * TILE_SIZE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn footprint(width: i32, height: i32, entrance: (i32, i32)) -> Footprint {
+        Footprint {
+            width,
+            height,
+            entrance,
+            rotation: Rotation::Deg0,
+        }
+    }
+
+    #[test]
+    fn rotated_clockwise_swaps_size_and_turns_entrance() {
+        // 3x2 with the entrance on the bottom row, second column
+        let rotated = footprint(3, 2, (1, 1)).rotated_clockwise();
+
+        assert_eq!(rotated.width, 2);
+        assert_eq!(rotated.height, 3);
+        assert_eq!(rotated.entrance, (0, 1));
+        assert_eq!(rotated.rotation, Rotation::Deg90);
+    }
+
+    #[test]
+    fn four_clockwise_turns_come_back_to_the_start() {
+        let start = footprint(3, 2, (2, 0));
+        let mut turned = start;
+        for _ in 0..4 {
+            turned = turned.rotated_clockwise();
+            assert!(turned.entrance.0 < turned.width && turned.entrance.1 < turned.height);
+        }
+
+        assert_eq!(turned, start);
+    }
+
+    #[test]
+    fn has_room_for_counts_every_resource() {
+        let mut storage = StorageSpace::new(30);
+        storage.item_list.insert(ResourceId::new("wood"), 10);
+        storage.item_list.insert(ResourceId::new("stone"), 10);
+
+        assert!(storage.has_room_for(10));
+        assert!(!storage.has_room_for(11));
+        assert!(StorageSpace::new(0).has_room_for(0));
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use raylib::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    engine::{assets::Assets, error::EngineError},
+    game::resource_registry::{ResourceId, ResourceRegistry},
+};
+
+use super::datatypes::Footprint;
+
+// STRUCTS ------
+/// Tag components a definition can ask for, on top of the ones every building gets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum BuildingComponent {
+    House,
+    Warehouse,
+}
+
+/// One building type as written in the definitions file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildingDefinition {
+    /// Shown to the player
+    pub name: String,
+    pub footprint: Footprint,
+    /// Top left atlas tile of the building art in the tileset, the art covers the footprint
+    pub atlas_tile: [f32; 2],
+    #[serde(default)]
+    pub construction_cost: BTreeMap<ResourceId, i32>,
+    /// Work builders put in once the construction resources are delivered
+    #[serde(default)]
+    pub work_required: f32,
+    /// Total amount of resources the building stores. Buildings without one store nothing.
+    #[serde(default)]
+    pub storage_capacity: Option<i32>,
+    #[serde(default)]
+    pub components: Vec<BuildingComponent>,
+}
+
+impl BuildingDefinition {
+    pub fn atlas_tile(&self) -> Vector2 {
+        Vector2 {
+            x: self.atlas_tile[0],
+            y: self.atlas_tile[1],
+        }
+    }
+
+    fn problems(&self, type_id: &str, resources: &ResourceRegistry) -> Vec<String> {
+        let mut problems: Vec<String> = vec![];
+        let footprint = &self.footprint;
+        if footprint.width < 1 || footprint.height < 1 {
+            problems.push(format!(
+                "{}: footprint {}x{} must be at least 1x1",
+                type_id, footprint.width, footprint.height
+            ));
+        }
+        let (entrance_x, entrance_y) = footprint.entrance;
+        if !(0..footprint.width).contains(&entrance_x) || !(0..footprint.height).contains(&entrance_y) {
+            problems.push(format!(
+                "{}: entrance ({}, {}) is outside the footprint",
+                type_id, entrance_x, entrance_y
+            ));
+        }
+        if !self.atlas_tile.iter().all(|value| value.is_finite() && *value >= 0.0) {
+            problems.push(format!("{}: atlas_tile must be two numbers of 0 or more", type_id));
+        }
+        for (resource, amount) in self.construction_cost.iter() {
+            if !resources.contains(resource) {
+                problems.push(format!("{}: construction cost uses unknown resource {:?}", type_id, resource.0));
+            }
+            if *amount < 0 {
+                problems.push(format!("{}: construction cost of {} is below 0", type_id, resource));
+            }
+        }
+        if !self.work_required.is_finite() || self.work_required < 0.0 {
+            problems.push(format!("{}: work_required must be 0 or more, got {}", type_id, self.work_required));
+        }
+        if let Some(capacity) = self.storage_capacity {
+            if capacity <= 0 {
+                problems.push(format!("{}: storage_capacity must be above 0, got {}", type_id, capacity));
+            }
+        }
+
+        problems
+    }
+}
+
+/// Every building type, keyed by type id. Read from the definitions file at startup and
+/// stored as a resource.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct BuildingDefinitions {
+    definitions: BTreeMap<String, BuildingDefinition>,
+}
+
+impl BuildingDefinitions {
+    /// Reads the definitions at `path` through `assets`
+    pub fn load(assets: &mut Assets, path: &str, resources: &ResourceRegistry) -> Result<BuildingDefinitions, EngineError> {
+        let handle = assets.load_data(path)?;
+        let definitions = BuildingDefinitions::parse(path, &assets.get(handle)?.text, resources)?;
+        info!("Loaded {} building definitions from {}", definitions.definitions.len(), path);
+
+        Ok(definitions)
+    }
+
+    pub fn parse(path: &str, text: &str, resources: &ResourceRegistry) -> Result<BuildingDefinitions, EngineError> {
+        let definitions: BuildingDefinitions =
+            serde_json::from_str(text).map_err(|error| definitions_error(path, error.to_string()))?;
+        definitions.validate(resources).map_err(|reason| definitions_error(path, reason))?;
+
+        Ok(definitions)
+    }
+
+    /// Checks every definition is usable with `resources`, reporting all the bad values at once
+    pub fn validate(&self, resources: &ResourceRegistry) -> Result<(), String> {
+        let problems: Vec<String> = self
+            .definitions
+            .iter()
+            .flat_map(|(type_id, definition)| definition.problems(type_id, resources))
+            .collect();
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join(", "))
+        }
+    }
+
+    pub fn get(&self, type_id: &str) -> Result<&BuildingDefinition, EngineError> {
+        self.definitions
+            .get(type_id)
+            .ok_or_else(|| EngineError::NotFound {
+                kind: "building definition",
+                name: type_id.to_string(),
+            })
+    }
+}
+
+// FUNCTIONS ------
+fn definitions_error(path: &str, reason: String) -> EngineError {
+    EngineError::data("Definitions", path, reason)
+}
@@ -1,5 +1,5 @@
 pub mod datatypes;
+pub mod definitions;
 pub mod step;
-pub mod warehouse;
-pub mod house;
+pub mod spawn;
 pub mod utils;
@@ -0,0 +1,97 @@
+use hecs::{Entity, EntityBuilder, World};
+use raylib::prelude::*;
+
+use crate::{
+    engine::{
+        collision::{CollisionBox, TriggerCollision},
+        datatypes::Sprite,
+        enums::CollisionLayer,
+        error::EngineError,
+    },
+    game::{
+        assets::tileset,
+        animations::{atlas_rect, building_animator, FINISHED_CLIP, PLACED_CLIP},
+        constants::{BUILDING_COLLISION_MASK, PLACEMENT_COLLISION_MASK, TILE_SIZE},
+    },
+};
+
+use super::{
+    datatypes::{
+        Building, BuildingKind, ConstructionPlacement, ConstructionStorage, House, Storage, StorageSpace,
+        Warehouse,
+    },
+    definitions::{BuildingComponent, BuildingDefinitions},
+};
+
+/// Spawns a building of type `type_id` with its top left tile at `position`. Unfinished
+/// buildings wait for their construction resources first.
+pub fn spawn_building(
+    world: &mut World,
+    definitions: &BuildingDefinitions,
+    type_id: &str,
+    position: Vector2,
+    finished: bool,
+) -> Result<Entity, EngineError> {
+    let definition = definitions.get(type_id)?;
+    let footprint = definition.footprint;
+
+    let initial_clip = if finished { FINISHED_CLIP } else { PLACED_CLIP };
+    let animator = building_animator(atlas_rect(definition.atlas_tile(), &footprint), initial_clip);
+    let mut sprite = Sprite::new(tileset(), position, definition.atlas_tile(), TILE_SIZE);
+    sprite.rect = animator.frame().rect;
+
+    let col_box = CollisionBox::new(footprint.rect(position), CollisionLayer::Building, BUILDING_COLLISION_MASK);
+
+    let mut builder = EntityBuilder::new();
+    builder.add_bundle((
+        Building,
+        BuildingKind(type_id.to_string()),
+        sprite,
+        col_box,
+        footprint,
+        TriggerCollision::new(),
+        animator,
+    ));
+    for component in definition.components.iter() {
+        match component {
+            BuildingComponent::House => builder.add(House),
+            BuildingComponent::Warehouse => builder.add(Warehouse),
+        };
+    }
+    if let Some(capacity) = definition.storage_capacity {
+        builder.add_bundle((Storage, StorageSpace::new(capacity)));
+    }
+    if !finished {
+        builder.add(ConstructionStorage {
+            tasks_generated: false,
+            required_item_list: definition.construction_cost.clone().into_iter().collect(),
+        });
+    }
+
+    Ok(world.spawn(builder.build()))
+}
+
+/// Spawns the ghost of a `type_id` building following the mouse until it's placed
+pub fn spawn_building_placement(
+    world: &mut World,
+    definitions: &BuildingDefinitions,
+    type_id: &str,
+    position: Vector2,
+) -> Result<Entity, EngineError> {
+    let building = spawn_building(world, definitions, type_id, position, false)?;
+    let definition = definitions.get(type_id)?;
+
+    let placement = ConstructionPlacement {
+        position,
+        building_rect: atlas_rect(definition.atlas_tile(), &definition.footprint),
+    };
+    world.insert_one(building, placement).map_err(|_| EngineError::NoSuchEntity(building))?;
+
+    let mut col_box = world
+        .get_mut::<CollisionBox>(building)
+        .map_err(EngineError::component::<CollisionBox>(building))?;
+    col_box.layer = CollisionLayer::Placement;
+    col_box.mask = PLACEMENT_COLLISION_MASK;
+
+    Ok(building)
+}
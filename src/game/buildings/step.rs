@@ -1,42 +1,46 @@
-use raylib::consts::MouseButton::*;
 use std::collections::HashMap;
 
 use hecs::{Entity, World};
-use raylib::prelude::{Camera2D, Vector2};
-use raylib::RaylibHandle;
-
-use crate::engine::collision::{CollisionBox, TriggerCollision};
-use crate::game::constants::TILE_SIZE;
+use raylib::prelude::Vector2;
+
+use crate::engine::collision::{CollisionBox, Contact, ContactPhase, TriggerCollision};
+use crate::engine::error::EngineError;
+use crate::engine::events::Events;
+use crate::engine::input::InputState;
+use crate::engine::schedule::SystemContext;
+use crate::engine::spatial_hash::CollisionGrid;
+use crate::game::constants::{BUILDING_COLLISION_MASK, TILE_SIZE};
 use crate::{
     engine::{
-        datatypes::Sprite,
-        enums::{GameResource, VillagerState, VillagerType},
+        datatypes::{IdCounter, Sprite},
+        enums::{CollisionLayer, VillagerState, VillagerType},
     },
     game::{
-        constants::HAULER_CAPACITY,
-        tasks::{generate_haul_task, HaulTask},
+        actions::{Action, GameActions},
+        events::GameEvent,
+        resource_registry::{ResourceId, ResourceRegistry},
+        tasks::{generate_haul_task, nearest_storage_with_resource, HaulTask, OpenTasks},
         villagers::{
-            datatypes::{CarryingState, GameItem, IdleState, LoadingState},
+            datatypes::{Backpack, GameItem},
             hauler::{deliver_resource, receive_resource},
+            step::{villager_state, villager_type},
         },
     },
 };
 
-use super::datatypes::{
-    Building, ConstructionPlacement, ConstructionStorage, OngoingConstruction, StorageSpace,
+use super::{
+    datatypes::{
+        Building, BuildingKind, ConstructionPlacement, ConstructionStorage, Footprint, OngoingConstruction,
+        StorageSpace,
+    },
+    definitions::BuildingDefinitions,
+    utils::building_entrance,
 };
 
-pub fn update_buildings(world: &mut World) -> Result<(), String> {
-    generate_construction_haul_tasks(world);
-    check_storage_collided_with_entity(world);
-    check_construction_collided_with_entity(world);
-    check_construction_resources(world)?;
-
-    Ok(())
-}
-
-pub fn check_construction_collided_with_entity(world: &mut World) {
-    let mut collided_entities: HashMap<Entity, Entity> = HashMap::new();
+pub fn check_construction_collided_with_entity(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let mut events = ctx.resources.get_mut::<Events<GameEvent>>()?;
+    let changed_villagers = villagers_with_new_state(&mut events, "check_construction_collided_with_entity");
+    let mut collided_entities: Vec<(Entity, Entity)> = vec![];
 
     {
         let mut query = world
@@ -44,40 +48,28 @@ pub fn check_construction_collided_with_entity(world: &mut World) {
             .with::<Building>()
             .with::<ConstructionStorage>();
         query.into_iter().for_each(|(ety, trigger_col)| {
-            if trigger_col.colliding {
-                if let Some(other) = trigger_col.other_trigger {
-                    collided_entities.insert(ety, other);
+            for contact in trigger_col.contacts.iter() {
+                if contact.layer == CollisionLayer::Villager && is_new_arrival(contact, &changed_villagers) {
+                    collided_entities.push((ety, contact.other));
                 }
             }
         });
     }
+    for (building, villager) in collided_entities.into_iter() {
+        let m_villager_info = villager_type(world, villager).zip(villager_state(world, villager));
 
-    collided_entities
-        .into_iter()
-        .for_each(|(building, villager)| {
-            let mut m_villager_info: Option<(VillagerType, VillagerState)> = None;
-
-            if let Ok(_) = world.get::<IdleState>(villager) {
-                m_villager_info = Some((VillagerType::Hauler, VillagerState::Idle));
-            } else if let Ok(_) = world.get::<LoadingState>(villager) {
-                m_villager_info = Some((VillagerType::Hauler, VillagerState::Loading));
-            } else if let Ok(_) = world.get::<CarryingState>(villager) {
-                m_villager_info = Some((VillagerType::Hauler, VillagerState::Carrying));
-            }
+        if let Some((VillagerType::Hauler, villager_state)) = m_villager_info {
+            construction_handle_hauler(world, &mut events, building, villager, villager_state);
+        }
+    }
 
-            if let Some((villager_type, villager_state)) = m_villager_info {
-                match villager_type {
-                    VillagerType::Hauler => {
-                        construction_handle_hauler(world, building, villager, villager_state)
-                    }
-                    _ => {}
-                }
-            }
-        });
+    Ok(())
 }
 
-pub fn check_storage_collided_with_entity(world: &mut World) {
-    let mut collided_entities: HashMap<Entity, Entity> = HashMap::new();
+pub fn check_storage_collided_with_entity(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let mut events = ctx.resources.get_mut::<Events<GameEvent>>()?;
+    let changed_villagers = villagers_with_new_state(&mut events, "check_storage_collided_with_entity");
+    let mut collided_entities: Vec<(Entity, Entity)> = vec![];
 
     {
         let mut query = world
@@ -85,62 +77,64 @@ pub fn check_storage_collided_with_entity(world: &mut World) {
             .with::<Building>()
             .without::<ConstructionStorage>();
         query.into_iter().for_each(|(ety, trigger_col)| {
-            if trigger_col.colliding {
-                if let Some(other) = trigger_col.other_trigger {
-                    collided_entities.insert(ety, other);
+            for contact in trigger_col.contacts.iter() {
+                // Haulers carrying to a full storage wait inside it, they're checked again every
+                // frame until there's room
+                let is_waiting = contact.phase == ContactPhase::Stay
+                    && villager_state(world, contact.other) == Some(VillagerState::Carrying);
+                if contact.layer == CollisionLayer::Villager
+                    && (is_new_arrival(contact, &changed_villagers) || is_waiting)
+                {
+                    collided_entities.push((ety, contact.other));
                 }
             }
         });
     }
+    for (building, villager) in collided_entities.into_iter() {
+        let m_villager_info = villager_type(world, villager).zip(villager_state(world, villager));
 
-    collided_entities
-        .into_iter()
-        .for_each(|(building, villager)| {
-            let mut m_villager_info: Option<(VillagerType, VillagerState)> = None;
-
-            if let Ok(_) = world.get::<IdleState>(villager) {
-                m_villager_info = Some((VillagerType::Hauler, VillagerState::Idle));
-            } else if let Ok(_) = world.get::<LoadingState>(villager) {
-                m_villager_info = Some((VillagerType::Hauler, VillagerState::Loading));
-            } else if let Ok(_) = world.get::<CarryingState>(villager) {
-                m_villager_info = Some((VillagerType::Hauler, VillagerState::Carrying));
-            }
+        if let Some((VillagerType::Hauler, villager_state)) = m_villager_info {
+            storage_handle_hauler(world, &mut events, building, villager, villager_state);
+        }
+    }
 
-            if let Some((villager_type, villager_state)) = m_villager_info {
-                match villager_type {
-                    VillagerType::Hauler => {
-                        storage_handle_hauler(world, building, villager, villager_state)
-                    }
-                    _ => {}
-                }
-            }
-        });
+    Ok(())
+}
+
+/// Whether the villager behind `contact` has to be handled this frame: it just walked in, or it
+/// was already inside and changed state since, like a hauler given a task at the building it
+/// stands in.
+fn is_new_arrival(contact: &Contact, changed_villagers: &[Entity]) -> bool {
+    match contact.phase {
+        ContactPhase::Enter => true,
+        ContactPhase::Stay => changed_villagers.contains(&contact.other),
+        ContactPhase::Exit => false,
+    }
+}
+
+fn villagers_with_new_state(events: &mut Events<GameEvent>, reader: &'static str) -> Vec<Entity> {
+    events
+        .read(reader)
+        .iter()
+        .filter_map(|event| match event {
+            GameEvent::VillagerStateChanged { villager, .. } => Some(*villager),
+            _ => None,
+        })
+        .collect()
 }
 
 pub fn construction_handle_hauler(
     world: &mut World,
+    events: &mut Events<GameEvent>,
     building: Entity,
     hauler: Entity,
     state: VillagerState,
 ) {
     let mut building_position: Vector2 = Vector2::zero();
     let mut is_hauler_destination: bool = false;
-    let mut construction_finished: bool = false;
-
-    {
-        let result = world.get::<ConstructionStorage>(building);
-        if let Ok(storage) = result {
-            construction_finished = is_storage_empty(&storage.required_item_list);
-        }
-    }
 
-    if construction_finished {
-        world.remove_one::<ConstructionStorage>(building).unwrap();
-        return;
-    }
-
-    if let Ok(sprite) = world.get::<Sprite>(building) {
-        building_position = sprite.position;
+    if let Some(entrance) = building_entrance(world, building) {
+        building_position = entrance;
     }
 
     if let Ok(task) = world.get::<HaulTask>(hauler) {
@@ -151,21 +145,23 @@ pub fn construction_handle_hauler(
         }
     }
 
-    match state {
-        VillagerState::Carrying => {
-            if is_hauler_destination {
-                let m_item = deliver_resource(world, hauler);
-                if let Some(item) = m_item {
-                    place_construction_resource(world, building, item);
-                }
+    if state == VillagerState::Carrying && is_hauler_destination {
+        let m_item = deliver_resource(world, events, hauler);
+        if let Some(item) = m_item {
+            if place_construction_resource(world, building, item.clone()) {
+                events.send(GameEvent::ItemDelivered {
+                    villager: hauler,
+                    building,
+                    item,
+                });
             }
         }
-        _ => {}
     }
 }
 
 pub fn storage_handle_hauler(
     world: &mut World,
+    events: &mut Events<GameEvent>,
     building: Entity,
     hauler: Entity,
     state: VillagerState,
@@ -174,8 +170,8 @@ pub fn storage_handle_hauler(
     let mut is_hauler_origin: bool = false;
     let mut is_hauler_destination: bool = false;
 
-    if let Ok(sprite) = world.get::<Sprite>(building) {
-        building_position = sprite.position;
+    if let Some(entrance) = building_entrance(world, building) {
+        building_position = entrance;
     }
 
     if let Ok(task) = world.get::<HaulTask>(hauler) {
@@ -194,17 +190,37 @@ pub fn storage_handle_hauler(
     match state {
         VillagerState::Loading => {
             if is_hauler_origin {
-                let m_item = receive_resource(world, hauler);
+                let m_item = receive_resource(world, events, hauler);
                 if let Some(item) = m_item {
-                    remove_from_storage(world, building, item);
+                    if remove_from_storage(world, building, item.clone()) {
+                        events.send(GameEvent::ItemPickedUp {
+                            villager: hauler,
+                            building,
+                            item,
+                        });
+                    }
                 }
             }
         }
         VillagerState::Carrying => {
-            if is_hauler_destination {
-                let m_item = deliver_resource(world, hauler);
+            // A full storage leaves the hauler carrying, `check_storage_collided_with_entity`
+            // tries again on the next frames
+            let carried = world
+                .get::<Backpack>(hauler)
+                .map_or(0, |backpack| backpack.item.as_ref().map_or(0, |item| item.amount));
+            let has_room = world
+                .get::<StorageSpace>(building)
+                .is_ok_and(|storage| storage.has_room_for(carried));
+            if is_hauler_destination && has_room {
+                let m_item = deliver_resource(world, events, hauler);
                 if let Some(item) = m_item {
-                    add_to_storage(world, building, item);
+                    if add_to_storage(world, building, item.clone()) {
+                        events.send(GameEvent::ItemDelivered {
+                            villager: hauler,
+                            building,
+                            item,
+                        });
+                    }
                 }
             }
         }
@@ -212,72 +228,78 @@ pub fn storage_handle_hauler(
     }
 }
 
-pub fn generate_construction_haul_tasks(world: &mut World) {
-    let mut task_data_list: Vec<(Vector2, HashMap<GameResource, i32>)> = vec![];
+pub fn generate_construction_haul_tasks(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let mut task_data_list: Vec<(Vector2, HashMap<ResourceId, i32>)> = vec![];
 
     {
         let query = world
-            .query_mut::<(&mut ConstructionStorage, &Sprite)>()
+            .query_mut::<(&mut ConstructionStorage, &Sprite, &Footprint)>()
             .without::<ConstructionPlacement>();
         query
             .into_iter()
-            .for_each(|(entity, (mut construction, sprite))| {
+            .for_each(|(_, (construction, sprite, footprint))| {
                 if !construction.tasks_generated {
-                    println!("Listing tasks to be generated for {:?}", entity);
-                    task_data_list.push((sprite.position, construction.required_item_list.clone()));
+                    let entrance = footprint.entrance_position(sprite.position);
+                    task_data_list.push((entrance, construction.required_item_list.clone()));
                     construction.tasks_generated = true;
                 }
             });
     }
 
-    task_data_list
-        .into_iter()
-        .for_each(|(destination, resource_list)| {
-            resource_list.into_iter().for_each(|(resource, amount)| {
-                let task_count: i32 = amount / HAULER_CAPACITY;
-
-                for _ in 0..task_count {
-                    let mut m_origin_pos: Option<Vector2> = None;
-                    {
-                        let mut origin_query = world
-                            .query::<(&StorageSpace, &Sprite)>()
-                            .without::<ConstructionStorage>();
-                        origin_query.into_iter().for_each(|(_, (storage, sprite))| {
-                            if storage_has_required_resource(&storage, resource, HAULER_CAPACITY)
-                                && m_origin_pos.is_none()
-                            {
-                                m_origin_pos = Some(sprite.position);
-                            }
-                        });
-                    }
-                    generate_haul_task(world, m_origin_pos, destination, resource);
-                }
-            });
-        });
+    let mut open_tasks = ctx.resources.get_mut::<OpenTasks>()?;
+    let mut ids = ctx.resources.get_mut::<IdCounter>()?;
+    let resources = ctx.resources.get::<ResourceRegistry>()?;
+    let mut events = ctx.resources.get_mut::<Events<GameEvent>>()?;
+    let grid = ctx.resources.get::<CollisionGrid>()?;
+    for (destination, resource_list) in task_data_list.into_iter() {
+        // Sorted so tasks are queued in the same order on every run
+        let mut resource_list: Vec<(ResourceId, i32)> = resource_list.into_iter().collect();
+        resource_list.sort();
+        for (resource, amount) in resource_list.into_iter() {
+            // Full stacks, then whatever is left over
+            let stack_size = resources.stack_size(&resource)?;
+            let mut remaining = amount;
+            while remaining > 0 {
+                let task_amount = remaining.min(stack_size);
+                remaining -= task_amount;
+
+                let m_origin_pos = nearest_storage_with_resource(world, &grid, destination, &resource, task_amount);
+                generate_haul_task(
+                    &mut open_tasks,
+                    &mut ids,
+                    &mut events,
+                    m_origin_pos,
+                    destination,
+                    resource.clone(),
+                    task_amount,
+                );
+            }
+        }
+    }
+
+    Ok(())
 }
 
 pub fn storage_has_required_resource(
     storage: &StorageSpace,
-    resource: GameResource,
+    resource: &ResourceId,
     amount: i32,
 ) -> bool {
-    if storage.item_list.contains_key(&resource) {
-        if storage.reserved_item_list.contains_key(&resource) {
-            return storage.item_list[&resource] - storage.reserved_item_list[&resource] >= amount;
+    if storage.item_list.contains_key(resource) {
+        if storage.reserved_item_list.contains_key(resource) {
+            storage.item_list[resource] - storage.reserved_item_list[resource] >= amount
         } else {
-            return storage.item_list[&resource] >= amount;
+            storage.item_list[resource] >= amount
         }
     } else {
-        return false;
+        false
     }
 }
 
 pub fn remove_from_storage(world: &mut World, building: Entity, item: GameItem) -> bool {
     let result = world.get_mut::<StorageSpace>(building);
     if let Ok(mut storage) = result {
-        println!("Storage got");
         if storage.item_list.contains_key(&item.resource) {
-            println!("Giving resource to hauler");
             *storage.item_list.get_mut(&item.resource).unwrap() -= item.amount;
             return true;
         }
@@ -287,8 +309,11 @@ pub fn remove_from_storage(world: &mut World, building: Entity, item: GameItem)
 }
 
 pub fn add_to_storage(world: &mut World, building: Entity, item: GameItem) -> bool {
-    let result = world.get_mut::<&mut StorageSpace>(building);
+    let result = world.get_mut::<StorageSpace>(building);
     if let Ok(mut storage) = result {
+        if !storage.has_room_for(item.amount) {
+            return false;
+        }
         if storage.item_list.contains_key(&item.resource) {
             *storage.item_list.get_mut(&item.resource).unwrap() += item.amount;
         } else {
@@ -300,32 +325,33 @@ pub fn add_to_storage(world: &mut World, building: Entity, item: GameItem) -> bo
     return false;
 }
 
-pub fn place_construction_resource(world: &mut World, building: Entity, item: GameItem) {
-    let result = world.get_mut::<&mut ConstructionStorage>(building);
+pub fn place_construction_resource(world: &mut World, building: Entity, item: GameItem) -> bool {
+    let result = world.get_mut::<ConstructionStorage>(building);
     if let Ok(mut storage) = result {
-        if storage.required_item_list.contains_key(&item.resource) {
-            *storage.required_item_list.get_mut(&item.resource).unwrap() -= item.amount;
+        if let Some(required) = storage.required_item_list.get_mut(&item.resource) {
+            *required -= item.amount;
+            return true;
         }
     }
+
+    false
 }
 
-pub fn is_storage_empty(list: &HashMap<GameResource, i32>) -> bool {
+pub fn is_storage_empty(list: &HashMap<ResourceId, i32>) -> bool {
     let mut count: i32 = 0;
     list.into_iter().for_each(|(_, amount)| count += amount);
     return count <= 0;
 }
 
-pub fn update_construction_hover(
-    world: &mut World,
-    raylib_handle: &mut RaylibHandle,
-    camera: &Camera2D,
-) {
-    let mut query = world.query::<(&mut ConstructionPlacement, &mut CollisionBox, &mut Sprite)>();
+pub fn update_construction_hover(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let input = ctx.resources.get::<InputState>()?;
+    let camera = ctx.m_camera.as_deref().ok_or(EngineError::MissingContext("Camera"))?;
+    let mouse_pos = input.mouse_world_position(camera);
+
+    let mut query = world.query::<(&mut ConstructionPlacement, &mut CollisionBox, &mut Sprite, &Footprint)>();
     query
         .into_iter()
-        .for_each(|(_, (placement, col_box, sprite))| {
-            let mouse_pos =
-                raylib_handle.get_screen_to_world2D(raylib_handle.get_mouse_position(), camera);
+        .for_each(|(_, (placement, col_box, sprite, footprint))| {
             let mut current_tile_x = (mouse_pos.x / TILE_SIZE) as i32;
             let mut current_tile_y = (mouse_pos.y / TILE_SIZE) as i32;
 
@@ -335,26 +361,58 @@ pub fn update_construction_hover(
             if mouse_pos.y < 0.0 {
                 current_tile_y -= 1;
             }
+            // The mouse stays over the middle tile of the footprint, top left of the middle on
+            // even sides
+            current_tile_x -= (footprint.width - 1) / 2;
+            current_tile_y -= (footprint.height - 1) / 2;
 
             placement.position.x = current_tile_x as f32 * TILE_SIZE;
             placement.position.y = current_tile_y as f32 * TILE_SIZE;
-            col_box.rect.x = placement.position.x;
-            col_box.rect.y = placement.position.y;
+            col_box.rect = footprint.rect(placement.position);
             sprite.position = placement.position;
         });
+
+    Ok(())
 }
 
-pub fn place_hovering_building(world: &mut World, raylib_handle: &mut RaylibHandle) -> Result<(), String> {
-    if raylib_handle.is_mouse_button_released(MOUSE_LEFT_BUTTON) {
+/// Turns the building following the mouse a quarter clockwise, along with its collision box
+pub fn rotate_hovering_building(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let pressed = {
+        let input = ctx.resources.get::<InputState>()?;
+        ctx.resources.get::<GameActions>()?.is_pressed(&input, Action::RotateBuilding)
+    };
+    if pressed {
+        let query = world.query_mut::<(&ConstructionPlacement, &mut Footprint, &mut CollisionBox)>();
+        for (_, (placement, footprint, col_box)) in query {
+            *footprint = footprint.rotated_clockwise();
+            col_box.rect = footprint.rect(placement.position);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn place_hovering_building(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let released = {
+        let input = ctx.resources.get::<InputState>()?;
+        ctx.resources.get::<GameActions>()?.is_released(&input, Action::PlaceBuilding)
+    };
+    if released {
         let mut m_building: Option<Entity> = None;
         {
-            let query =
-                world.query_mut::<(&ConstructionPlacement, &TriggerCollision, &mut Sprite)>();
+            let query = world.query_mut::<(
+                &ConstructionPlacement,
+                &TriggerCollision,
+                &mut CollisionBox,
+                &mut Sprite,
+            )>();
             query
                 .into_iter()
-                .for_each(|(entity, (placement, trigger, mut sprite))| {
+                .for_each(|(entity, (placement, trigger, col_box, sprite))| {
                     if !trigger.colliding {
                         sprite.position = placement.position;
+                        col_box.layer = CollisionLayer::Building;
+                        col_box.mask = BUILDING_COLLISION_MASK;
                         m_building = Some(entity);
                     }
                 });
@@ -362,7 +420,12 @@ pub fn place_hovering_building(world: &mut World, raylib_handle: &mut RaylibHand
 
         {
             if let Some(building) = m_building {
-                world.remove_one::<ConstructionPlacement>(building).map_err(|_| "Component error")?;
+                world
+                    .remove_one::<ConstructionPlacement>(building)
+                    .map_err(EngineError::component::<ConstructionPlacement>(building))?;
+                ctx.resources
+                    .get_mut::<Events<GameEvent>>()?
+                    .send(GameEvent::BuildingPlaced { building });
             }
         }
     }
@@ -370,7 +433,27 @@ pub fn place_hovering_building(world: &mut World, raylib_handle: &mut RaylibHand
     Ok(())
 }
 
-pub fn check_construction_resources(world: &mut World) -> Result<(), String> {
+/// Drops the building following the mouse when the placement is cancelled
+pub fn cancel_hovering_building(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let pressed = {
+        let input = ctx.resources.get::<InputState>()?;
+        ctx.resources.get::<GameActions>()?.is_pressed(&input, Action::CancelPlacement)
+    };
+    if pressed {
+        let placements: Vec<Entity> = world
+            .query::<&ConstructionPlacement>()
+            .iter()
+            .map(|(entity, _)| entity)
+            .collect();
+        for placement in placements.into_iter() {
+            world.despawn(placement).map_err(|_| EngineError::NoSuchEntity(placement))?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn check_construction_resources(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
     let mut building_list: Vec<Entity> = vec![];
 
     {
@@ -382,17 +465,89 @@ pub fn check_construction_resources(world: &mut World) -> Result<(), String> {
         });
     }
 
+    let buildings = ctx.resources.get::<BuildingDefinitions>()?;
     for building in building_list.into_iter() {
-        world.remove_one::<ConstructionStorage>(building).map_err(|_| "Component error")?;
+        let work_required = {
+            let kind = world
+                .get::<BuildingKind>(building)
+                .map_err(EngineError::component::<BuildingKind>(building))?;
+            buildings.get(&kind.0)?.work_required
+        };
         world
-            .insert_one(
-                building,
-                OngoingConstruction {
-                    work_required: 100.0,
-                },
-            )
-            .map_err(|_| "No such entity")?;
+            .remove_one::<ConstructionStorage>(building)
+            .map_err(EngineError::component::<ConstructionStorage>(building))?;
+        world
+            .insert_one(building, OngoingConstruction { work_required })
+            .map_err(|_| EngineError::NoSuchEntity(building))?;
+        ctx.resources
+            .get_mut::<Events<GameEvent>>()?
+            .send(GameEvent::ConstructionResourcesComplete { building });
     };
 
     Ok(())
 }
+
+/// Puts one unit of work per simulated second into every building whose construction
+/// resources were delivered, and finishes the ones with no work left
+pub fn advance_construction(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let mut finished: Vec<Entity> = vec![];
+
+    for (entity, construction) in world.query_mut::<&mut OngoingConstruction>() {
+        construction.work_required -= ctx.delta;
+        if construction.work_required <= 0.0 {
+            finished.push(entity);
+        }
+    }
+
+    let mut events = ctx.resources.get_mut::<Events<GameEvent>>()?;
+    for building in finished.into_iter() {
+        world
+            .remove_one::<OngoingConstruction>(building)
+            .map_err(EngineError::component::<OngoingConstruction>(building))?;
+        events.send(GameEvent::ConstructionFinished { building });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::resources::Resources;
+
+    use super::*;
+
+    fn construction_site(world: &mut World, wood: i32) -> Entity {
+        world.spawn((ConstructionStorage {
+            tasks_generated: true,
+            required_item_list: [(ResourceId::new("wood"), wood)].into_iter().collect(),
+        },))
+    }
+
+    #[test]
+    fn construction_sites_only_take_required_resources() {
+        let mut world = World::new();
+        let building = construction_site(&mut world, 20);
+
+        assert!(place_construction_resource(&mut world, building, GameItem::new(ResourceId::new("wood"), 10)));
+        assert!(!place_construction_resource(&mut world, building, GameItem::new(ResourceId::new("stone"), 10)));
+
+        let storage = world.get::<ConstructionStorage>(building).unwrap();
+        assert_eq!(storage.required_item_list[&ResourceId::new("wood")], 10);
+    }
+
+    #[test]
+    fn construction_finishes_once_the_work_is_done() {
+        let mut world = World::new();
+        let building = world.spawn((OngoingConstruction { work_required: 1.5 },));
+        let mut resources = Resources::new();
+        resources.insert(Events::<GameEvent>::new());
+
+        advance_construction(&mut world, &mut SystemContext::new(1.0, &mut resources)).unwrap();
+        assert_eq!(world.get::<OngoingConstruction>(building).unwrap().work_required, 0.5);
+        advance_construction(&mut world, &mut SystemContext::new(1.0, &mut resources)).unwrap();
+
+        assert!(world.get::<OngoingConstruction>(building).is_err());
+        let mut events = resources.get_mut::<Events<GameEvent>>().unwrap();
+        assert_eq!(events.read("test"), &[GameEvent::ConstructionFinished { building }]);
+    }
+}
@@ -1,19 +1,15 @@
-use std::collections::HashMap;
+use hecs::{Entity, World};
+use raylib::prelude::*;
 
-use crate::engine::enums::{BuildingType, GameResource};
+use crate::engine::datatypes::Sprite;
 
-pub fn get_building_construction_cost(building_type: BuildingType) -> HashMap<GameResource, i32> {
-    let mut cost = HashMap::new();
-    match building_type {
-        BuildingType::Warehouse => {
-            cost.insert(GameResource::Wood, 70);
-            cost.insert(GameResource::Stone, 30);
-        }
-        BuildingType::House => {
-            cost.insert(GameResource::Wood, 50);
-        }
-    }
+use super::datatypes::Footprint;
 
-    return cost;
-}
+/// Where haulers stand to pick up from or deliver to `building`, the top left corner of its
+/// entrance tile
+pub fn building_entrance(world: &World, building: Entity) -> Option<Vector2> {
+    let sprite = world.get::<Sprite>(building).ok()?;
+    let footprint = world.get::<Footprint>(building).ok()?;
 
+    Some(footprint.entrance_position(sprite.position))
+}
@@ -0,0 +1,140 @@
+use hecs::World;
+use raylib::prelude::*;
+
+use crate::engine::{error::EngineError, input::InputState, schedule::SystemContext};
+
+use super::{
+    actions::{Action, GameActions},
+    config::GameConfig,
+    tilemap::Tilemap,
+};
+
+// STRUCTS ------
+/// Where the camera is headed, stored as a resource. Input moves the controller and the
+/// camera follows it, smoothed by `camera_smoothing`.
+#[derive(Debug, Clone)]
+pub struct CameraController {
+    pub target: Vector2,
+    pub zoom: f32,
+    /// Mouse position of the previous frame while a drag is going on
+    m_drag_position: Option<Vector2>,
+}
+
+impl CameraController {
+    pub fn new(config: &GameConfig) -> CameraController {
+        CameraController {
+            target: Vector2::zero(),
+            zoom: config.camera_zoom,
+            m_drag_position: None,
+        }
+    }
+}
+
+// FUNCTIONS ------
+/// Zooms with the mouse wheel around the cursor, pans with the pan actions, edge scrolling and
+/// mouse drag, then keeps the view inside the tilemap.
+pub fn update_camera(_world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let delta = ctx.delta;
+    let input = ctx.resources.get::<InputState>()?;
+    let actions = ctx.resources.get::<GameActions>()?;
+    let config = ctx.resources.get::<GameConfig>()?;
+    let bounds = ctx.resources.get::<Tilemap>()?.bounds();
+    let mut controller = ctx.resources.get_mut::<CameraController>()?;
+    let camera = ctx.m_camera.as_deref_mut().ok_or(EngineError::MissingContext("Camera"))?;
+
+    let screen_size = Vector2 {
+        x: config.screen_width_f(),
+        y: config.screen_height_f(),
+    };
+    let mouse_pos = input.mouse_position();
+
+    let mut zoom_steps = input.mouse_wheel_move();
+    if actions.is_pressed(&input, Action::ZoomIn) {
+        zoom_steps += 1.0;
+    }
+    if actions.is_pressed(&input, Action::ZoomOut) {
+        zoom_steps -= 1.0;
+    }
+    if zoom_steps != 0.0 {
+        // The world point under the cursor stays under it
+        let anchor = controller.target + mouse_pos / controller.zoom;
+        let zoom = (controller.zoom * (1.0 + config.camera_zoom_step).powf(zoom_steps))
+            .clamp(config.camera_min_zoom, config.camera_max_zoom);
+        controller.target = anchor - mouse_pos / zoom;
+        controller.zoom = zoom;
+    }
+
+    let near_edge = |position: f32, size: f32| -> f32 {
+        if !config.edge_scroll {
+            0.0
+        } else if position <= config.edge_scroll_margin {
+            -1.0
+        } else if position >= size - config.edge_scroll_margin {
+            1.0
+        } else {
+            0.0
+        }
+    };
+    let mut direction = Vector2 {
+        x: near_edge(mouse_pos.x, screen_size.x),
+        y: near_edge(mouse_pos.y, screen_size.y),
+    };
+    if actions.is_down(&input, Action::CameraPanRight) {
+        direction.x += 1.0;
+    }
+    if actions.is_down(&input, Action::CameraPanLeft) {
+        direction.x -= 1.0;
+    }
+    if actions.is_down(&input, Action::CameraPanUp) {
+        direction.y -= 1.0;
+    }
+    if actions.is_down(&input, Action::CameraPanDown) {
+        direction.y += 1.0;
+    }
+    let zoom = controller.zoom;
+    if direction.length() > 0.0 {
+        // Same speed on screen whatever the zoom
+        controller.target += direction.normalized() * (config.camera_speed * delta / zoom);
+    }
+
+    let dragging = actions.is_down(&input, Action::CameraDrag);
+    if dragging {
+        if let Some(last_position) = controller.m_drag_position {
+            controller.target -= (mouse_pos - last_position) / zoom;
+        }
+        controller.m_drag_position = Some(mouse_pos);
+    } else {
+        controller.m_drag_position = None;
+    }
+
+    controller.target = clamp_view(controller.target, controller.zoom, screen_size, bounds);
+
+    // Dragging follows the mouse exactly, anything else eases in
+    let amount = if dragging || config.camera_smoothing == 0.0 {
+        1.0
+    } else {
+        1.0 - (-config.camera_smoothing * delta).exp()
+    };
+    camera.zoom += (controller.zoom - camera.zoom) * amount;
+    camera.target = clamp_view(camera.target.lerp(controller.target, amount), camera.zoom, screen_size, bounds);
+
+    Ok(())
+}
+
+/// Moves `target`, the top left corner of the view, so the view stays inside `bounds`. A view
+/// larger than the bounds is centered on them.
+pub fn clamp_view(target: Vector2, zoom: f32, screen_size: Vector2, bounds: Rectangle) -> Vector2 {
+    let view_size = screen_size / zoom;
+    let clamp_axis = |position: f32, view: f32, start: f32, size: f32| -> f32 {
+        if view >= size {
+            start + (size - view) / 2.0
+        } else {
+            position.clamp(start, start + size - view)
+        }
+    };
+
+    Vector2 {
+        x: clamp_axis(target.x, view_size.x, bounds.x, bounds.width),
+        y: clamp_axis(target.y, view_size.y, bounds.y, bounds.height),
+    }
+}
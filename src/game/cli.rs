@@ -0,0 +1,84 @@
+// STRUCTS ------
+#[derive(Default, Debug, Clone)]
+pub struct CliOptions {
+    /// When set, the game runs this many simulation frames without opening a window.
+    pub headless_frames: Option<u32>,
+    /// Seed for the game rng. A random seed is picked (and logged) when missing.
+    pub seed: Option<u64>,
+    /// Colony save to start from instead of a fresh test map
+    pub load_path: Option<String>,
+    /// Where a headless run writes the colony once it's done
+    pub save_path: Option<String>,
+    /// Where the windowed game writes the input of the session when closed
+    pub record_path: Option<String>,
+    /// Input recording to play back headless instead of running a fresh simulation
+    pub replay_path: Option<String>,
+    /// Config file to read instead of the default one
+    pub config_path: Option<String>,
+    /// Input bindings file to read instead of the default one
+    pub bindings_path: Option<String>,
+    /// Print the effective config and exit
+    pub dump_config: bool,
+    /// When set, times collision detection with this many boxes and exits
+    pub bench_collisions: Option<u32>,
+}
+
+// FUNCTIONS ------
+pub fn parse_cli_args(args: &[String]) -> Result<CliOptions, String> {
+    let mut options = CliOptions::default();
+    let mut iter = args.iter().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--headless" => {
+                let frames = iter.next().ok_or("--headless expects a frame count")?;
+                let frames = frames
+                    .parse::<u32>()
+                    .map_err(|_| format!("Invalid frame count for --headless: {}", frames))?;
+                options.headless_frames = Some(frames);
+            }
+            "--seed" => {
+                let seed = iter.next().ok_or("--seed expects a number")?;
+                let seed = seed
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid seed: {}", seed))?;
+                options.seed = Some(seed);
+            }
+            "--load" => {
+                let path = iter.next().ok_or("--load expects a save file path")?;
+                options.load_path = Some(path.clone());
+            }
+            "--save" => {
+                let path = iter.next().ok_or("--save expects a save file path")?;
+                options.save_path = Some(path.clone());
+            }
+            "--record" => {
+                let path = iter.next().ok_or("--record expects a recording file path")?;
+                options.record_path = Some(path.clone());
+            }
+            "--replay" => {
+                let path = iter.next().ok_or("--replay expects a recording file path")?;
+                options.replay_path = Some(path.clone());
+            }
+            "--config" => {
+                let path = iter.next().ok_or("--config expects a config file path")?;
+                options.config_path = Some(path.clone());
+            }
+            "--bindings" => {
+                let path = iter.next().ok_or("--bindings expects a bindings file path")?;
+                options.bindings_path = Some(path.clone());
+            }
+            "--dump-config" => options.dump_config = true,
+            "--bench-collisions" => {
+                let count = iter.next().ok_or("--bench-collisions expects a box count")?;
+                let count = count
+                    .parse::<u32>()
+                    .map_err(|_| format!("Invalid box count for --bench-collisions: {}", count))?;
+                options.bench_collisions = Some(count);
+            }
+            other => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+
+    Ok(options)
+}
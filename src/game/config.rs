@@ -0,0 +1,158 @@
+use std::path::Path;
+
+use raylib::prelude::Vector2;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{assets::Assets, error::EngineError};
+
+use super::constants::{
+    CAMERA_MAX_ZOOM, CAMERA_MIN_ZOOM, CAMERA_SMOOTHING, CAMERA_SPEED, CAMERA_ZOOM, CAMERA_ZOOM_STEP,
+    DEFAULT_IDLE_POINT, EDGE_SCROLL_MARGIN, SCREEN_HEIGHT, SCREEN_WIDTH, TARGET_FPS, UI_SCALE,
+    VILLAGER_SPEED,
+};
+
+// STRUCTS ------
+/// Tunables read from the config file at startup, stored as a resource. Every field is
+/// optional in the file, missing ones keep the values from `constants.rs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub screen_width: i32,
+    pub screen_height: i32,
+    pub target_fps: u32,
+    pub ui_scale: f32,
+    /// Screen pixels per second the camera pans at while a pan key is held
+    pub camera_speed: f32,
+    /// Zoom the camera starts at
+    pub camera_zoom: f32,
+    pub camera_min_zoom: f32,
+    pub camera_max_zoom: f32,
+    /// Zoom change per mouse wheel notch, relative to the current zoom
+    pub camera_zoom_step: f32,
+    /// How fast the camera catches up with where it's headed. 0 disables smoothing.
+    pub camera_smoothing: f32,
+    /// Pans the camera when the mouse is near the screen border
+    pub edge_scroll: bool,
+    pub edge_scroll_margin: f32,
+    /// Pixels per second villagers walk at
+    pub villager_speed: f32,
+    /// Where haulers wander when they have nothing to do
+    pub default_idle_point: [f32; 2],
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            screen_width: SCREEN_WIDTH,
+            screen_height: SCREEN_HEIGHT,
+            target_fps: TARGET_FPS,
+            ui_scale: UI_SCALE,
+            camera_speed: CAMERA_SPEED,
+            camera_zoom: CAMERA_ZOOM,
+            camera_min_zoom: CAMERA_MIN_ZOOM,
+            camera_max_zoom: CAMERA_MAX_ZOOM,
+            camera_zoom_step: CAMERA_ZOOM_STEP,
+            camera_smoothing: CAMERA_SMOOTHING,
+            edge_scroll: false,
+            edge_scroll_margin: EDGE_SCROLL_MARGIN,
+            villager_speed: VILLAGER_SPEED,
+            default_idle_point: [DEFAULT_IDLE_POINT.x, DEFAULT_IDLE_POINT.y],
+        }
+    }
+}
+
+impl GameConfig {
+    /// Reads the config at `path` through `assets`. A missing file is only an error when
+    /// `required` is set, otherwise the defaults are used.
+    pub fn load(assets: &mut Assets, path: &str, required: bool) -> Result<GameConfig, EngineError> {
+        if !required && !Path::new(path).exists() {
+            info!("No config file at {}, using defaults", path);
+            return Ok(GameConfig::default());
+        }
+
+        let handle = assets.load_data(path)?;
+        let config = GameConfig::parse(path, &assets.get(handle)?.text)?;
+        info!("Loaded config from {}", path);
+
+        Ok(config)
+    }
+
+    pub fn parse(path: &str, text: &str) -> Result<GameConfig, EngineError> {
+        let config: GameConfig = serde_json::from_str(text).map_err(|error| config_error(path, error.to_string()))?;
+        config.validate().map_err(|reason| config_error(path, reason))?;
+
+        Ok(config)
+    }
+
+    /// Checks every value is usable, reporting all the bad ones at once
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems: Vec<String> = vec![];
+        if self.screen_width < 320 || self.screen_height < 240 {
+            problems.push(format!(
+                "screen size {}x{} is below the 320x240 minimum",
+                self.screen_width, self.screen_height
+            ));
+        }
+        if self.target_fps == 0 {
+            problems.push("target_fps must be above 0".to_string());
+        }
+        if !self.ui_scale.is_finite() || self.ui_scale <= 0.0 {
+            problems.push(format!("ui_scale must be above 0, got {}", self.ui_scale));
+        }
+        for (name, value) in [
+            ("camera_speed", self.camera_speed),
+            ("camera_zoom_step", self.camera_zoom_step),
+            ("camera_smoothing", self.camera_smoothing),
+            ("edge_scroll_margin", self.edge_scroll_margin),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                problems.push(format!("{} must be 0 or more, got {}", name, value));
+            }
+        }
+        if !self.camera_min_zoom.is_finite() || self.camera_min_zoom <= 0.0 {
+            problems.push(format!("camera_min_zoom must be above 0, got {}", self.camera_min_zoom));
+        }
+        if !(self.camera_min_zoom..=self.camera_max_zoom).contains(&self.camera_zoom) {
+            problems.push(format!(
+                "camera_zoom {} must be between camera_min_zoom {} and camera_max_zoom {}",
+                self.camera_zoom, self.camera_min_zoom, self.camera_max_zoom
+            ));
+        }
+        if !self.villager_speed.is_finite() || self.villager_speed <= 0.0 {
+            problems.push(format!("villager_speed must be above 0, got {}", self.villager_speed));
+        }
+        if !self.default_idle_point.iter().all(|value| value.is_finite()) {
+            problems.push("default_idle_point must be two finite numbers".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join(", "))
+        }
+    }
+
+    /// The effective config as pretty JSON, in the same format the file is read in
+    pub fn dump(&self) -> Result<String, EngineError> {
+        serde_json::to_string_pretty(self).map_err(|error| config_error("<dump>", error.to_string()))
+    }
+
+    pub fn screen_width_f(&self) -> f32 {
+        self.screen_width as f32
+    }
+
+    pub fn screen_height_f(&self) -> f32 {
+        self.screen_height as f32
+    }
+
+    pub fn default_idle_point(&self) -> Vector2 {
+        Vector2 {
+            x: self.default_idle_point[0],
+            y: self.default_idle_point[1],
+        }
+    }
+}
+
+fn config_error(path: &str, reason: String) -> EngineError {
+    EngineError::data("Config", path, reason)
+}
@@ -1,26 +1,72 @@
 use raylib::prelude::{Vector2, Rectangle};
 
+use crate::engine::{collision::CollisionMask, enums::CollisionLayer};
+
 pub const SCREEN_WIDTH: i32 = 1280;
-pub const SCREEN_WIDTH_F: f32 = 1280.0;
 pub const SCREEN_HEIGHT: i32 = 720;
-pub const SCREEN_HEIGHT_F: f32 = 720.0;
+
+pub const TARGET_FPS: u32 = 75;
+/// Simulation ticks per second, independent of the render frame rate
+pub const SIMULATION_TICK_RATE: u32 = 60;
+
+/// Read at startup when no --config is given. The game runs on defaults if it's missing.
+pub const CONFIG_PATH: &str = "config.json";
+/// Input bindings, written with the defaults when missing
+pub const BINDINGS_PATH: &str = "bindings.json";
 
 pub const TILESET_PATH: &str = "assets/tileset.png";
 pub const UI_ATLAS_PATH: &str = "assets/ui.png";
 pub const FONT_PATH: &str = "assets/prstartk.ttf";
+/// Building types, see `BuildingDefinitions`
+pub const BUILDINGS_PATH: &str = "assets/buildings.json";
+/// Resource types, see `ResourceRegistry`
+pub const RESOURCES_PATH: &str = "assets/resources.json";
+/// Seconds between two checks for changed asset files (debug builds only)
+pub const ASSET_RELOAD_INTERVAL: f32 = 1.0;
+/// Quicksave slot (F5 saves, F9 loads)
+pub const SAVE_PATH: &str = "saves/colony.json";
+/// Names the config and input bindings are kept under in input recordings
+pub const RECORDED_CONFIG: &str = "config";
+pub const RECORDED_BINDINGS: &str = "bindings";
 
-pub const CAMERA_SPEED: f32 = 10.0;
+/// Vertical distance between two menu entries
+pub const MENU_ENTRY_SPACING: f32 = 50.0;
+
+/// Screen pixels per second the camera pans at
+pub const CAMERA_SPEED: f32 = 600.0;
+pub const CAMERA_ZOOM: f32 = 2.0;
+pub const CAMERA_MIN_ZOOM: f32 = 1.0;
+pub const CAMERA_MAX_ZOOM: f32 = 4.0;
+/// Zoom change per mouse wheel notch, relative to the current zoom
+pub const CAMERA_ZOOM_STEP: f32 = 0.1;
+/// How fast the camera catches up with where it's headed. 0 disables smoothing.
+pub const CAMERA_SMOOTHING: f32 = 12.0;
+/// Distance from the screen border at which edge scrolling kicks in
+pub const EDGE_SCROLL_MARGIN: f32 = 16.0;
+/// Size multiplier of UI buttons, independent of the camera zoom
+pub const UI_SCALE: f32 = 2.0;
 
 pub const TILE_SIZE: f32 = 16.0;
 pub const CHUNK_TILE_SIZE: i32 = 16;
 /// The size in pixels of any given chunk
 pub const CHUNK_RAW_SIZE: i32 = CHUNK_TILE_SIZE * TILE_SIZE as i32;
+/// Cell size of the collision broadphase grid
+pub const COLLISION_CELL_SIZE: f32 = 2.0 * TILE_SIZE;
+/// Layers each kind of collision box gets contacts with
+pub const VILLAGER_COLLISION_MASK: CollisionMask = CollisionMask::NONE.with(CollisionLayer::Building);
+pub const BUILDING_COLLISION_MASK: CollisionMask = CollisionMask::NONE.with(CollisionLayer::Villager);
+/// A building can't be placed over anything solid
+pub const PLACEMENT_COLLISION_MASK: CollisionMask = CollisionMask::NONE
+    .with(CollisionLayer::Villager)
+    .with(CollisionLayer::Building)
+    .with(CollisionLayer::Terrain);
 
 pub const DEFAULT_IDLE_POINT: Vector2 = Vector2 { x: 192.0, y: 192.0 };
 pub const DEFAULT_IDLE_POINT_ATLAS_TILE: Vector2 = Vector2 { x: 2.0, y: 6.0 };
-pub const PLAYER_SPEED: f32 = 2.0;
-
-pub const HAULER_CAPACITY: i32 = 10;
+/// Farthest from its destination a haul task looks for a storage holding its resource
+pub const STORAGE_SEARCH_RADIUS: f32 = 64.0 * TILE_SIZE;
+/// Pixels per second villagers walk at
+pub const VILLAGER_SPEED: f32 = 50.0;
 
 pub const CONSTRUCTION_RECT: Rectangle = Rectangle { 
     x: 0.0 * TILE_SIZE, 
@@ -28,3 +74,10 @@ pub const CONSTRUCTION_RECT: Rectangle = Rectangle {
     width: TILE_SIZE, 
     height: TILE_SIZE 
 };
+/// Alternates with `CONSTRUCTION_RECT` while builders work on a site
+pub const CONSTRUCTION_PROGRESS_RECT: Rectangle = Rectangle {
+    x: 0.0 * TILE_SIZE,
+    y: 4.0 * TILE_SIZE,
+    width: TILE_SIZE,
+    height: TILE_SIZE
+};
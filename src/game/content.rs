@@ -0,0 +1,125 @@
+use hecs::World;
+
+use crate::engine::{
+    assets::{Assets, DataFile, DataSource, Handle},
+    error::EngineError,
+    schedule::SystemContext,
+    ui::datatypes::UiScale,
+};
+
+use super::{
+    buildings::definitions::BuildingDefinitions,
+    config::GameConfig,
+    constants::{BUILDINGS_PATH, RESOURCES_PATH},
+    resource_registry::ResourceRegistry,
+};
+
+// STRUCTS ------
+/// Game content read from the data files. Every run inserts both as resources.
+#[derive(Debug, Clone)]
+pub struct GameContent {
+    pub resources: ResourceRegistry,
+    pub buildings: BuildingDefinitions,
+}
+
+impl GameContent {
+    /// Reads the resources first, building costs are checked against them
+    pub fn load(assets: &mut Assets) -> Result<GameContent, EngineError> {
+        let resources = ResourceRegistry::load(assets, RESOURCES_PATH)?;
+        let buildings = BuildingDefinitions::load(assets, BUILDINGS_PATH, &resources)?;
+
+        Ok(GameContent { resources, buildings })
+    }
+}
+
+/// Data files the game state was parsed from, stored as a resource in runs that hot reload
+/// assets. `reload_game_data` parses them again when they change on disk.
+pub struct DataSources {
+    /// Missing when the defaults are used
+    pub m_config: Option<DataSource>,
+    pub resources: DataSource,
+    pub buildings: DataSource,
+}
+
+impl DataSources {
+    /// Sources for the data files already loaded into `assets`
+    pub fn new(assets: &mut Assets, config_path: &str) -> Result<DataSources, EngineError> {
+        let m_config = if assets.is_loaded(Handle::<DataFile>::from_path(config_path)) {
+            Some(DataSource::load(assets, config_path)?)
+        } else {
+            None
+        };
+
+        Ok(DataSources {
+            m_config,
+            resources: DataSource::load(assets, RESOURCES_PATH)?,
+            buildings: DataSource::load(assets, BUILDINGS_PATH)?,
+        })
+    }
+}
+
+// FUNCTIONS ------
+/// Swaps in the config, resources and building definitions parsed from reloaded data files. A
+/// file that doesn't parse is reported and the current version kept. The window size and frame
+/// rate only apply on the next start, buildings already placed keep their footprint.
+///
+/// Resources and buildings are swapped together, the building costs are checked against the
+/// new resources. Resources can be added or changed while running, not removed: the colony may
+/// still hold some.
+pub fn reload_game_data(_world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    if !ctx.resources.contains::<DataSources>() || !ctx.resources.contains::<Assets>() {
+        return Ok(());
+    }
+
+    let assets = ctx.resources.get::<Assets>()?;
+    let mut sources = ctx.resources.get_mut::<DataSources>()?;
+
+    if let Some(source) = sources.m_config.as_mut() {
+        if let Some(text) = source.poll_changed(&assets) {
+            match GameConfig::parse(source.path(&assets), text) {
+                Ok(config) => {
+                    *ctx.resources.get_mut::<UiScale>()? = UiScale(config.ui_scale);
+                    *ctx.resources.get_mut::<GameConfig>()? = config;
+                    info!("Applied the reloaded config");
+                }
+                Err(error) => warn!("Kept the current config: {}", error),
+            }
+        }
+    }
+
+    let resources_changed = sources.resources.poll_changed(&assets).is_some();
+    let buildings_changed = sources.buildings.poll_changed(&assets).is_some();
+    if resources_changed || buildings_changed {
+        let parsed = parse_content(&assets, &sources, &*ctx.resources.get::<ResourceRegistry>()?);
+        match parsed {
+            Ok(content) => {
+                *ctx.resources.get_mut::<ResourceRegistry>()? = content.resources;
+                *ctx.resources.get_mut::<BuildingDefinitions>()? = content.buildings;
+                info!("Applied the reloaded resources and building definitions");
+            }
+            Err(error) => warn!("Kept the current resources and building definitions: {}", error),
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the resources and buildings files as they are now in `assets`
+fn parse_content(
+    assets: &Assets,
+    sources: &DataSources,
+    current: &ResourceRegistry,
+) -> Result<GameContent, EngineError> {
+    let path = sources.resources.path(assets);
+    let resources = ResourceRegistry::parse(path, &assets.get(sources.resources.handle)?.text)?;
+    if let Some(id) = current.sorted_ids().into_iter().find(|id| !resources.contains(id)) {
+        return Err(EngineError::data("Definitions", path, format!("resource {:?} was removed", id.0)));
+    }
+    let buildings = BuildingDefinitions::parse(
+        sources.buildings.path(assets),
+        &assets.get(sources.buildings.handle)?.text,
+        &resources,
+    )?;
+
+    Ok(GameContent { resources, buildings })
+}
@@ -2,118 +2,205 @@ use hecs::World;
 use raylib::prelude::*;
 
 use crate::engine::{
-    collision::{draw_collisions, TriggerCollision},
+    animation::{sprite_draw_position, Animator},
+    assets::Assets,
+    collision::TriggerCollision,
     datatypes::Sprite,
-    enums::VillagerState,
-    ui::draw::draw_mouse_selection,
-    TILESET,
+    enums::{RenderLayer, Stage, VillagerState},
+    error::EngineError,
+    render::{DrawCommand, RenderQueue},
+    schedule::{DrawContext, Schedule, System, SystemEntry},
+    ui::datatypes::{Label, UIElement},
 };
 
 use super::{
-    buildings::datatypes::{ConstructionPlacement, ConstructionStorage, OngoingConstruction},
-    constants::{CONSTRUCTION_RECT, SCREEN_WIDTH_F, TILE_SIZE},
-    ui::datatypes::SelectedHauler,
+    buildings::datatypes::{ConstructionPlacement, ConstructionStorage, Footprint, OngoingConstruction},
+    constants::TILE_SIZE,
+    scenes::Scene,
+    settings::Settings,
+    assets::{base_font, tileset},
+    ui::datatypes::{SelectedHauler, StorageIcons},
     villagers::datatypes::{
         Backpack, CarryingState, GameItem, IdleState, LoadingState, WorkingState,
     }, 
     tilemap::{draw_tilemap, check_visible_tilemap_chunks},
 };
 
-pub fn draw_game(
+pub fn register_draw_systems(schedule: &mut Schedule) {
+    // Only the test map has a tilemap resource
+    schedule.add_system(
+        SystemEntry::new(
+            "check_visible_tilemap_chunks",
+            Stage::Render,
+            System::DrawWorld(check_visible_tilemap_chunks),
+        )
+        .in_scene(Scene::TestMap.label()),
+    );
+    // World drawables only queue themselves, `draw_render_queue` sorts and draws them
+    schedule.add_system(
+        SystemEntry::new("draw_tilemap", Stage::Render, System::DrawWorld(draw_tilemap))
+            .after("check_visible_tilemap_chunks")
+            .before("draw_render_queue")
+            .in_scene(Scene::TestMap.label()),
+    );
+    schedule.add_system(
+        SystemEntry::new("draw_sprites", Stage::Render, System::DrawWorld(draw_sprites))
+            .before("draw_render_queue"),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "draw_construction_placement",
+            Stage::Render,
+            System::DrawWorld(draw_construction_placement),
+        )
+        .before("draw_render_queue"),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "draw_construction",
+            Stage::Render,
+            System::DrawWorld(draw_construction),
+        )
+        .before("draw_render_queue"),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "draw_storage_icons",
+            Stage::Render,
+            System::DrawScreen(draw_storage_icons),
+        )
+        .after("draw_ui")
+        .in_scene(Scene::TestMap.label()),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "draw_selected_hauler_state",
+            Stage::Render,
+            System::DrawScreen(draw_selected_hauler_state),
+        )
+        .after("draw_ui")
+        .in_scene(Scene::TestMap.label()),
+    );
+    schedule.add_system(
+        SystemEntry::new("draw_fps", Stage::Render, System::DrawScreen(draw_fps)).after("draw_ui"),
+    );
+}
+
+/// Draws the resource icons of the global storage label, sized like its text
+pub fn draw_storage_icons(
     world: &mut World,
-    mode2d: &mut RaylibMode2D<RaylibDrawHandle>,
-    camera: &Camera2D,
-) {
-    check_visible_tilemap_chunks(world, mode2d, camera);
-    draw_tilemap(world, mode2d);
-    draw_sprites(world, mode2d);
-    draw_construction_placement(world, mode2d);
-    draw_construction(world, mode2d);
-    draw_collisions(world, mode2d, TILE_SIZE);
-    draw_mouse_selection(world, mode2d, &camera, TILE_SIZE);
+    draw_handle: &mut RaylibDrawHandle,
+    ctx: &DrawContext,
+) -> Result<(), EngineError> {
+    let assets = ctx.resources.get::<Assets>()?;
+    let texture = assets.get(tileset())?;
+
+    let mut query = world.query::<(&StorageIcons, &Label, &UIElement)>();
+    for (_, (storage_icons, label, element)) in query.into_iter() {
+        if !element.visible {
+            continue;
+        }
+        let text_position = element.position + element.offset;
+        for (icon, offset) in storage_icons.icons.iter() {
+            let dest = Rectangle {
+                x: text_position.x + offset,
+                y: text_position.y,
+                width: label.font_size,
+                height: label.font_size,
+            };
+            draw_handle.draw_texture_pro(texture, icon, dest, Vector2::zero(), 0.0, Color::WHITE);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn draw_fps(
+    _world: &mut World,
+    draw_handle: &mut RaylibDrawHandle,
+    ctx: &DrawContext,
+) -> Result<(), EngineError> {
+    if ctx.resources.get::<Settings>()?.show_fps {
+        draw_handle.draw_fps(10, 10);
+    }
+
+    Ok(())
 }
 
+/// Shows the state and backpack of the hauler in `SelectedHauler`, below the storage label
 pub fn draw_selected_hauler_state(
     world: &mut World,
     draw_handle: &mut RaylibDrawHandle,
-    font: &Font,
-) {
-    let mut selected_hauler_query = world.query::<&SelectedHauler>();
-    selected_hauler_query
-        .into_iter()
-        .for_each(|(_, selected_hauler)| {
-            let mut m_state: Option<VillagerState> = None;
-            let mut backpack_item: Option<GameItem> = None;
-
-            let idle_query = world.get::<IdleState>(selected_hauler.hauler);
-            if let Ok(_) = idle_query {
-                m_state = Some(VillagerState::Idle);
-            }
-            let loading_query = world.get::<LoadingState>(selected_hauler.hauler);
-            if let Ok(_) = loading_query {
-                m_state = Some(VillagerState::Loading);
-            }
-            let carrying_query = world.get::<CarryingState>(selected_hauler.hauler);
-            if let Ok(_) = carrying_query {
-                m_state = Some(VillagerState::Carrying);
-            }
-            let working_query = world.get::<WorkingState>(selected_hauler.hauler);
-            if let Ok(_) = working_query {
-                m_state = Some(VillagerState::Working);
-            }
-            let backpack_query = world.get::<Backpack>(selected_hauler.hauler);
-            if let Ok(bp) = backpack_query {
-                backpack_item = bp.clone_item();
-            }
-
-            if let Some(state) = m_state {
-                let font_size: f32 = 11.4;
-
-                let text = String::from(format!("Hauler state: {:?}", state));
-                let half_text_size = measure_text_ex(font, &text, font_size, 1.0) / 2.0;
-                draw_handle.draw_text_ex(
-                    font,
-                    &text,
-                    Vector2 {
-                        x: ((SCREEN_WIDTH_F / 2.0) - half_text_size.x),
-                        y: 12.0,
-                    },
-                    font_size,
-                    1.0,
-                    Color::BLACK,
-                );
-                let text: String;
-                match backpack_item {
-                    Some(item) => {
-                        text = String::from(format!(
-                            "Backpack Item: ({:?}, {:?})",
-                            item.resource, item.amount
-                        ))
-                    }
-                    None => text = String::from("Backpack Item: None"),
-                }
-                let half_text_size = measure_text_ex(font, &text, font_size, 1.0) / 2.0;
-                draw_handle.draw_text_ex(
-                    font,
-                    &text,
-                    Vector2 {
-                        x: (SCREEN_WIDTH_F / 2.0) - half_text_size.x,
-                        y: 32.0,
-                    },
-                    font_size,
-                    1.0,
-                    Color::BLACK,
-                );
-            }
-        });
+    ctx: &DrawContext,
+) -> Result<(), EngineError> {
+    let hauler = match ctx.resources.get::<SelectedHauler>()?.m_hauler {
+        Some(hauler) => hauler,
+        None => return Ok(()),
+    };
+
+    let mut m_state: Option<VillagerState> = None;
+    if world.get::<IdleState>(hauler).is_ok() {
+        m_state = Some(VillagerState::Idle);
+    }
+    if world.get::<LoadingState>(hauler).is_ok() {
+        m_state = Some(VillagerState::Loading);
+    }
+    if world.get::<CarryingState>(hauler).is_ok() {
+        m_state = Some(VillagerState::Carrying);
+    }
+    if world.get::<WorkingState>(hauler).is_ok() {
+        m_state = Some(VillagerState::Working);
+    }
+    // A despawned hauler has no state left to show
+    let state = match m_state {
+        Some(state) => state,
+        None => return Ok(()),
+    };
+    let m_backpack_item: Option<GameItem> = world
+        .get::<Backpack>(hauler)
+        .ok()
+        .and_then(|backpack| backpack.clone_item());
+
+    let assets = ctx.resources.get::<Assets>()?;
+    let font = assets.get(base_font())?;
+    let font_size: f32 = 11.4;
+    let screen_width = draw_handle.get_screen_width() as f32;
+
+    let backpack_text = match m_backpack_item {
+        Some(item) => format!("Backpack Item: ({:?}, {:?})", item.resource, item.amount),
+        None => String::from("Backpack Item: None"),
+    };
+    let lines = [(format!("Hauler state: {:?}", state), 32.0), (backpack_text, 52.0)];
+    for (text, y) in lines.iter() {
+        let half_text_size = measure_text_ex(font, text, font_size, 1.0) / 2.0;
+        draw_handle.draw_text_ex(
+            font,
+            text,
+            Vector2 {
+                x: (screen_width / 2.0) - half_text_size.x,
+                y: *y,
+            },
+            font_size,
+            1.0,
+            Color::BLACK,
+        );
+    }
+
+    Ok(())
 }
 
-pub fn draw_construction_placement(world: &mut World, mode2d: &mut RaylibMode2D<RaylibDrawHandle>) {
+/// The hover ghost shows the finished building, turned as it will be placed, with its entrance
+/// tile marked. It goes on the overlay layer, so it's never hidden under other sprites.
+pub fn draw_construction_placement(
+    world: &mut World,
+    _mode2d: &mut RaylibMode2D<RaylibDrawHandle>,
+    ctx: &DrawContext,
+) -> Result<(), EngineError> {
+    let mut render_queue = ctx.resources.get_mut::<RenderQueue>()?;
     let mut query = world
-        .query::<(&TriggerCollision, &Sprite)>()
-        .with::<ConstructionStorage>()
-        .with::<ConstructionPlacement>();
-    query.into_iter().for_each(|(_, (trigger, sprite))| {
+        .query::<(&ConstructionPlacement, &TriggerCollision, &Sprite, &Footprint)>()
+        .with::<ConstructionStorage>();
+    for (_, (placement, trigger, sprite, footprint)) in query.into_iter() {
         let mut color = if trigger.colliding {
             Color::RED
         } else {
@@ -121,55 +208,112 @@ pub fn draw_construction_placement(world: &mut World, mode2d: &mut RaylibMode2D<
         };
         color.a = 170;
 
-        mode2d.draw_texture_rec(
-            TILESET.get().unwrap(),
-            CONSTRUCTION_RECT,
-            sprite.position,
-            color,
+        let ghost = Sprite {
+            rect: placement.building_rect,
+            ..sprite.clone()
+        };
+        push_building_sprite(&mut render_queue, RenderLayer::Overlay, &ghost, footprint, placement.position, color);
+
+        let entrance = footprint.entrance_position(placement.position);
+        let rect = Rectangle {
+            x: entrance.x,
+            y: entrance.y,
+            width: TILE_SIZE,
+            height: TILE_SIZE,
+        };
+        render_queue.push(
+            RenderLayer::Overlay,
+            placement.position.y + footprint.size().y,
+            DrawCommand::Rectangle { rect, color: Color { a: 110, ..Color::YELLOW } },
         );
-    });
+    }
+
+    Ok(())
 }
 
-pub fn draw_construction(world: &mut World, mode2d: &mut RaylibMode2D<RaylibDrawHandle>) {
+pub fn draw_construction(
+    world: &mut World,
+    _mode2d: &mut RaylibMode2D<RaylibDrawHandle>,
+    ctx: &DrawContext,
+) -> Result<(), EngineError> {
+    let mut render_queue = ctx.resources.get_mut::<RenderQueue>()?;
     let mut query = world
-        .query::<&Sprite>()
+        .query::<(&Sprite, &Footprint, Option<&Animator>)>()
         .with::<ConstructionStorage>()
         .without::<ConstructionPlacement>();
-    query.into_iter().for_each(|(_, sprite)| {
-        mode2d.draw_texture_rec(
-            TILESET.get().unwrap(),
-            CONSTRUCTION_RECT,
-            sprite.position,
-            Color::WHITE,
-        );
-    });
+    for (_, (sprite, footprint, m_animator)) in query.into_iter() {
+        let position = sprite_draw_position(sprite, m_animator);
+        push_building_sprite(&mut render_queue, RenderLayer::Objects, sprite, footprint, position, Color::WHITE);
+    }
 
     let mut query = world
-        .query::<&Sprite>()
+        .query::<(&Sprite, &Footprint, Option<&Animator>)>()
         .with::<OngoingConstruction>()
         .without::<ConstructionStorage>()
         .without::<ConstructionPlacement>();
-    query.into_iter().for_each(|(_, sprite)| {
-        mode2d.draw_texture_rec(
-            TILESET.get().unwrap(),
-            CONSTRUCTION_RECT,
-            sprite.position,
-            Color::WHITE,
-        );
-    });
+    for (_, (sprite, footprint, m_animator)) in query.into_iter() {
+        let position = sprite_draw_position(sprite, m_animator);
+        push_building_sprite(&mut render_queue, RenderLayer::Objects, sprite, footprint, position, Color::WHITE);
+    }
+
+    Ok(())
 }
 
-pub fn draw_sprites(world: &mut World, mode2d: &mut RaylibMode2D<RaylibDrawHandle>) {
+/// Queues every other sprite on the objects layer, or on the layer of its `RenderLayer`
+/// component when it has one
+pub fn draw_sprites(
+    world: &mut World,
+    _mode2d: &mut RaylibMode2D<RaylibDrawHandle>,
+    ctx: &DrawContext,
+) -> Result<(), EngineError> {
+    let mut render_queue = ctx.resources.get_mut::<RenderQueue>()?;
     let mut query = world
-        .query::<&Sprite>()
+        .query::<(&Sprite, Option<&Animator>, Option<&RenderLayer>, Option<&Footprint>)>()
         .without::<ConstructionStorage>()
         .without::<OngoingConstruction>();
-    query.into_iter().for_each(|(_, sprite)| {
-        mode2d.draw_texture_rec(
-            TILESET.get().unwrap(),
-            sprite.rect,
-            sprite.position,
-            Color::WHITE,
-        );
+    for (_, (sprite, m_animator, m_layer, m_footprint)) in query.into_iter() {
+        let layer = m_layer.copied().unwrap_or(RenderLayer::Objects);
+        let position = sprite_draw_position(sprite, m_animator);
+        match m_footprint {
+            Some(footprint) => push_building_sprite(&mut render_queue, layer, sprite, footprint, position, Color::WHITE),
+            None => render_queue.push_sprite(layer, sprite, position, Color::WHITE),
+        }
+    }
+
+    Ok(())
+}
+
+/// Queues a building sprite turned like its footprint. Art smaller than the footprint, like the
+/// construction tiles, is repeated over every tile of it, all sorted by the bottom edge of the
+/// footprint.
+fn push_building_sprite(
+    render_queue: &mut RenderQueue,
+    layer: RenderLayer,
+    sprite: &Sprite,
+    footprint: &Footprint,
+    position: Vector2,
+    tint: Color,
+) {
+    let size = footprint.size();
+    let sort_y = sprite.position.y + size.y;
+    let art_size = footprint.rotation.rotate_size(Vector2 {
+        x: sprite.rect.width,
+        y: sprite.rect.height,
     });
+    let texture = |position: Vector2| DrawCommand::Texture {
+        texture: sprite.texture,
+        source: sprite.rect,
+        position,
+        rotation: footprint.rotation,
+        tint,
+    };
+
+    if art_size.x >= size.x && art_size.y >= size.y {
+        render_queue.push(layer, sort_y, texture(position));
+        return;
+    }
+
+    for offset in footprint.tile_offsets() {
+        render_queue.push(layer, sort_y, texture(position + offset));
+    }
 }
@@ -0,0 +1,62 @@
+use hecs::{Entity, World};
+
+use crate::engine::{
+    enums::VillagerState,
+    error::EngineError,
+    events::Events,
+    schedule::SystemContext,
+};
+
+use super::{resource_registry::ResourceId, villagers::datatypes::GameItem};
+
+// ENUMS ------
+/// Gameplay changes other systems (UI notifications, statistics, sound) can react to
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    VillagerStateChanged {
+        villager: Entity,
+        from: VillagerState,
+        to: VillagerState,
+    },
+    ItemPickedUp {
+        villager: Entity,
+        building: Entity,
+        item: GameItem,
+    },
+    ItemDelivered {
+        villager: Entity,
+        building: Entity,
+        item: GameItem,
+    },
+    ConstructionResourcesComplete {
+        building: Entity,
+    },
+    ConstructionFinished {
+        building: Entity,
+    },
+    BuildingPlaced {
+        building: Entity,
+    },
+    TaskCreated {
+        task_id: usize,
+        resource: ResourceId,
+    },
+    TaskAssigned {
+        task_id: usize,
+        hauler: Entity,
+    },
+    TaskCompleted {
+        task_id: usize,
+        hauler: Entity,
+    },
+}
+
+// FUNCTIONS ------
+pub fn log_game_events(_world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let mut events = ctx.resources.get_mut::<Events<GameEvent>>()?;
+    for event in events.read("log_game_events") {
+        debug!("{:?}", event);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,347 @@
+use hecs::{Component, World};
+
+use crate::engine::{
+    datatypes::AppExit,
+    error::EngineError,
+    input::{InputRecording, RecordedFiles},
+    resources::Resources,
+    scene::SceneStack,
+    enums::Stage,
+    schedule::SystemContext,
+};
+
+use super::{
+    actions::GameActions,
+    buildings::datatypes::{Building, ConstructionStorage, OngoingConstruction},
+    config::GameConfig,
+    constants::{RECORDED_BINDINGS, RECORDED_CONFIG, SIMULATION_TICK_RATE},
+    content::GameContent,
+    save::{load_colony, save_colony},
+    scenes::Scene,
+    startup::{insert_base_resources, new_camera},
+    step::{build_schedule, current_tick, update_events, update_game},
+    tasks::{HaulTask, OpenTasks},
+    villagers::datatypes::{CarryingState, Hauler, IdleState, LoadingState, WorkingState},
+};
+
+/// Runs the test map simulation for `frames` fixed ticks without a window, font or raylib
+/// handle, then prints a short report of the colony state. The run starts from the save at
+/// `load_path` when given, and writes the colony to `save_path` when done.
+pub fn run_headless(
+    config: &GameConfig,
+    actions: &GameActions,
+    content: &GameContent,
+    frames: u32,
+    seed: u64,
+    load_path: Option<&str>,
+    save_path: Option<&str>,
+) -> Result<(), EngineError> {
+    let (world, resources) = simulate(config, actions, content, frames, seed, load_path)?;
+
+    println!("Seed: {}", seed);
+    print_headless_report(&world, &resources, current_tick(&resources)?, 1.0 / SIMULATION_TICK_RATE as f32)?;
+
+    if let Some(path) = save_path {
+        save_colony(&world, &resources, path)?;
+    }
+
+    Ok(())
+}
+
+/// Plays an input recording back without a window and prints the same report as headless runs.
+/// See `replay`.
+pub fn run_replay(
+    config: &GameConfig,
+    actions: &GameActions,
+    content: &GameContent,
+    path: &str,
+    save_path: Option<&str>,
+) -> Result<(), EngineError> {
+    let recording = InputRecording::load(path)?;
+    let (world, resources) = replay(config, actions, content, &recording)?;
+
+    println!("Replayed {} ({} input frames)", path, recording.frames.len());
+    println!("Seed: {}", recording.seed);
+    print_headless_report(&world, &resources, current_tick(&resources)?, 1.0 / SIMULATION_TICK_RATE as f32)?;
+
+    if let Some(save_path) = save_path {
+        save_colony(&world, &resources, save_path)?;
+    }
+
+    Ok(())
+}
+
+/// Runs the test map simulation for `frames` fixed ticks, starting from the save at `load_path`
+/// when given, and returns the world and resources it ended with.
+pub fn simulate(
+    config: &GameConfig,
+    actions: &GameActions,
+    content: &GameContent,
+    frames: u32,
+    seed: u64,
+    load_path: Option<&str>,
+) -> Result<(World, Resources), EngineError> {
+    let mut world = World::new();
+    let mut resources = Resources::new();
+    insert_base_resources(&mut resources, config, actions, content, Scene::TestMap, seed);
+
+    let mut schedule = build_schedule();
+
+    match load_path {
+        Some(path) => load_colony(&mut world, &mut resources, path)?,
+        None => {
+            schedule.run_stage(Stage::Startup, &mut world, &mut SystemContext::new(0.0, &mut resources))?;
+            schedule.apply_scene_transitions(&mut world, &mut resources)?;
+        }
+    }
+
+    let delta = 1.0 / SIMULATION_TICK_RATE as f32;
+    for _ in 0..frames {
+        update_game(&mut world, &mut resources, &mut schedule, delta)?;
+        schedule.run_stage(Stage::UI, &mut world, &mut SystemContext::new(delta, &mut resources))?;
+        update_events(&resources)?;
+
+        if resources.get::<AppExit>()?.requested {
+            break;
+        }
+    }
+
+    Ok((world, resources))
+}
+
+/// Plays an input recording back without a window. The run starts the way the windowed game
+/// does, then every recorded frame goes through the input stage followed by the ticks the
+/// session ran before the next frame, which reproduces the recorded simulation. Saves are
+/// loaded from the copies kept in the recording, not from disk.
+///
+/// The config and bindings kept in the recording replace `config` and `actions`, which are only
+/// used for recordings that didn't keep them.
+///
+/// The render stage never runs, so nothing is drawn and the `RenderQueue` stays empty. Only the
+/// simulation and the UI state are reproduced.
+pub fn replay(
+    config: &GameConfig,
+    actions: &GameActions,
+    content: &GameContent,
+    recording: &InputRecording,
+) -> Result<(World, Resources), EngineError> {
+    let config = recording.setting_or(RECORDED_CONFIG, config)?;
+    let actions = recording.setting_or(RECORDED_BINDINGS, actions)?;
+    let mut world = World::new();
+    let mut resources = Resources::new();
+    insert_base_resources(&mut resources, &config, &actions, content, Scene::MainMenu, recording.seed);
+
+    let mut schedule = build_schedule();
+    schedule.run_stage(Stage::Startup, &mut world, &mut SystemContext::new(0.0, &mut resources))?;
+
+    resources.insert(RecordedFiles::replaying(recording.files.clone()));
+    if let Some(load_path) = &recording.load_path {
+        load_colony(&mut world, &mut resources, load_path)?;
+    }
+
+    let mut camera = new_camera(&config);
+    let delta = 1.0 / SIMULATION_TICK_RATE as f32;
+    for (index, frame) in recording.frames.iter().enumerate() {
+        schedule.apply_scene_transitions(&mut world, &mut resources)?;
+        resources.insert(frame.input.clone());
+
+        let mut input_ctx = SystemContext {
+            delta: frame.delta,
+            resources: &mut resources,
+            m_raylib_handle: None,
+            m_camera: Some(&mut camera),
+        };
+        schedule.run_stage(Stage::Input, &mut world, &mut input_ctx)?;
+
+        while current_tick(&resources)? < recording.end_tick_of(index) {
+            update_game(&mut world, &mut resources, &mut schedule, delta)?;
+        }
+
+        schedule.run_stage(Stage::UI, &mut world, &mut SystemContext::new(frame.delta, &mut resources))?;
+        update_events(&resources)?;
+
+        if resources.get::<AppExit>()?.requested {
+            break;
+        }
+    }
+
+    Ok((world, resources))
+}
+
+fn print_headless_report(world: &World, resources: &Resources, frames: u64, delta: f32) -> Result<(), EngineError> {
+    println!("Headless run finished: {} frames ({:.2}s simulated)", frames, frames as f32 * delta);
+    if !resources.contains::<OpenTasks>() {
+        let scenes = resources.get::<SceneStack>()?;
+        println!("No colony running in scenes {:?} ({} entities)", scenes.scenes(), world.len());
+        return Ok(());
+    }
+
+    let open_tasks = resources.get::<OpenTasks>()?.haul_list.len();
+    let running_tasks = world.query::<&HaulTask>().iter().count();
+    let awaiting_resources = world
+        .query::<&Building>()
+        .with::<ConstructionStorage>()
+        .iter()
+        .count();
+    let ongoing_constructions = world
+        .query::<&Building>()
+        .with::<OngoingConstruction>()
+        .iter()
+        .count();
+
+    println!(
+        "Haulers - idle: {}, loading: {}, carrying: {}, working: {}",
+        count_haulers_in::<IdleState>(world),
+        count_haulers_in::<LoadingState>(world),
+        count_haulers_in::<CarryingState>(world),
+        count_haulers_in::<WorkingState>(world),
+    );
+    println!("Haul tasks - open: {}, running: {}", open_tasks, running_tasks);
+    println!(
+        "Buildings - awaiting resources: {}, under construction: {}",
+        awaiting_resources, ongoing_constructions
+    );
+
+    Ok(())
+}
+
+fn count_haulers_in<S: Component>(world: &World) -> usize {
+    world.query::<&Hauler>().with::<S>().iter().count()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::{
+        engine::{assets::Assets, input::RecordedFile},
+        game::{
+            actions::default_actions,
+            buildings::datatypes::StorageSpace,
+            constants::{CONFIG_PATH, SAVE_PATH},
+            save::SAVE_VERSION,
+        },
+    };
+
+    use super::*;
+
+    /// Config, bindings and content the game starts with when no file overrides them
+    pub(crate) fn game_data() -> (GameConfig, GameActions, GameContent) {
+        let mut assets = Assets::new();
+        let config = GameConfig::load(&mut assets, CONFIG_PATH, false).unwrap();
+        let content = GameContent::load(&mut assets).unwrap();
+
+        (config, default_actions(), content)
+    }
+
+    pub(crate) fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}_{}.json", name, std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn frame(tick: u64, input: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({ "tick": tick, "delta": 1.0 / 60.0, "input": input })
+    }
+
+    /// Starts a new game from the main menu, then places a house next to the starting one
+    fn place_house_recording(config: &GameConfig, actions: &GameActions) -> InputRecording {
+        let frames = vec![
+            frame(0, serde_json::json!({ "mouse_position": [0.0, 0.0] })),
+            frame(1, serde_json::json!({ "mouse_position": [0.0, 0.0], "keys_released": [257] })),
+            frame(4, serde_json::json!({ "mouse_position": [100.0, 700.0], "buttons_down": [0] })),
+            frame(5, serde_json::json!({ "mouse_position": [100.0, 700.0], "buttons_released": [0] })),
+            frame(8, serde_json::json!({ "mouse_position": [40.0, 390.0], "buttons_released": [0] })),
+            frame(9, serde_json::json!({ "mouse_position": [0.0, 0.0] })),
+        ];
+
+        let mut recording: InputRecording = serde_json::from_value(serde_json::json!({
+            "version": 1,
+            "seed": 42,
+            "load_path": null,
+            "ticks": 610,
+            "frames": frames,
+        }))
+        .unwrap();
+        recording.record_setting(RECORDED_CONFIG, config).unwrap();
+        recording.record_setting(RECORDED_BINDINGS, actions).unwrap();
+
+        recording
+    }
+
+    fn colony_summary(world: &World, resources: &Resources) -> (usize, usize, usize) {
+        (
+            resources.get::<OpenTasks>().unwrap().haul_list.len(),
+            world.query::<&Building>().with::<ConstructionStorage>().iter().count(),
+            world.query::<&Hauler>().with::<IdleState>().iter().count(),
+        )
+    }
+
+    #[test]
+    fn replay_reproduces_the_recorded_session() {
+        let (config, actions, content) = game_data();
+        let recording = place_house_recording(&config, &actions);
+
+        let (world, resources) = replay(&config, &actions, &content, &recording).unwrap();
+        assert_eq!(current_tick(&resources).unwrap(), 610);
+        // The starting house and the placed one both wait for their 5 loads of wood
+        assert_eq!(colony_summary(&world, &resources), (10, 2, 1));
+
+        let first_save = temp_path("replay_first");
+        let second_save = temp_path("replay_second");
+        save_colony(&world, &resources, &first_save).unwrap();
+        let (world, resources) = replay(&config, &actions, &content, &recording).unwrap();
+        save_colony(&world, &resources, &second_save).unwrap();
+
+        let first = std::fs::read_to_string(&first_save).unwrap();
+        let second = std::fs::read_to_string(&second_save).unwrap();
+        std::fs::remove_file(&first_save).unwrap();
+        std::fs::remove_file(&second_save).unwrap();
+        assert!(first == second, "replaying twice gave different colonies");
+    }
+
+    #[test]
+    fn replays_use_the_recorded_bindings_and_config() {
+        let (config, actions, content) = game_data();
+        let recording = place_house_recording(&config, &actions);
+
+        // Nothing is bound locally, and the menu buttons sit elsewhere on a taller screen
+        let mut local_config = config.clone();
+        local_config.screen_height += 360;
+        let (world, resources) = replay(&local_config, &GameActions::new(), &content, &recording).unwrap();
+
+        assert_eq!(colony_summary(&world, &resources), (10, 2, 1));
+        assert_eq!(*resources.get::<GameConfig>().unwrap(), config);
+    }
+
+    #[test]
+    fn replayed_quickloads_use_the_recorded_save() {
+        let (config, actions, content) = game_data();
+
+        // A colony whose warehouse is stocked, unlike the one a new game starts with
+        let save_path = temp_path("replay_quickload");
+        let (world, resources) = simulate(&config, &actions, &content, 10, 7, None).unwrap();
+        save_colony(&world, &resources, &save_path).unwrap();
+        let mut save: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&save_path).unwrap()).unwrap();
+        std::fs::remove_file(&save_path).unwrap();
+        assert_eq!(save["version"], SAVE_VERSION);
+        for entity in save["entities"].as_array_mut().unwrap() {
+            if entity["warehouse"] == true {
+                entity["storage_space"]["item_list"] = serde_json::json!({ "wood": 100 });
+            }
+        }
+
+        let mut recording = place_house_recording(&config, &actions);
+        recording.frames.truncate(2);
+        // F9 at tick 100
+        let quickload = serde_json::json!({ "mouse_position": [0.0, 0.0], "keys_released": [298] });
+        recording.record(100, 1.0 / 60.0, &serde_json::from_value(quickload).unwrap());
+        recording.ticks = 200;
+        // Whatever the quicksave slot holds on disk is ignored
+        recording.files = vec![RecordedFile {
+            path: SAVE_PATH.to_string(),
+            text: save.to_string(),
+        }];
+
+        let (world, _) = replay(&config, &actions, &content, &recording).unwrap();
+        let stored: i32 = world.query::<&StorageSpace>().iter().map(|(_, storage)| storage.stored()).sum();
+        assert!(stored > 0, "the recorded save wasn't loaded");
+    }
+}
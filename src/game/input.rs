@@ -1,69 +1,216 @@
 use hecs::{Entity, World};
-use raylib::consts::KeyboardKey::*;
-use raylib::consts::MouseButton::*;
 use raylib::prelude::*;
 
 use crate::engine::{
-    collision::{is_point_inside_box, DrawCollisions},
-    enums::ButtonState,
+    collision::{is_point_inside_box, CollisionMask, DrawCollisions},
+    enums::{ButtonState, CollisionLayer, Stage},
+    error::EngineError,
+    input::InputState,
+    scene::{SceneOwned, SceneStack},
+    schedule::{Schedule, System, SystemContext, SystemEntry},
+    spatial_hash::CollisionGrid,
+    spatial_query::entities_at_point,
     ui::{
-        datatypes::{Button, CameraZoom, DebugUI, ToggleButton, UIElement},
+        datatypes::{Button, ButtonAction, DebugUI, HandleAction, ToggleButton, UIElement, UiScale},
         toggle_mouse_selection,
     },
 };
 
-use super::buildings::step::place_hovering_building;
+use super::actions::{Action, GameActions};
+use super::assets::tileset;
+use super::buildings::step::{cancel_hovering_building, place_hovering_building, rotate_hovering_building};
+use super::camera::update_camera;
+use super::buildings::datatypes::ConstructionPlacement;
 use super::buildings::step::update_construction_hover;
-use super::constants::{CAMERA_SPEED, TILE_SIZE};
+use super::constants::{SAVE_PATH, TILE_SIZE};
+use super::save::{queue_colony_load, save_colony};
+use super::scenes::Scene;
+use super::ui::datatypes::SelectedHauler;
+use super::villagers::datatypes::Hauler;
 
 // FUNCTIONS ------
-pub fn handle_input(world: &mut World, raylib_handle: &mut RaylibHandle, camera: &mut Camera2D) -> Result<(), String> {
-    camera.target = read_camera_input(raylib_handle, camera.target);
-    place_hovering_building(world, raylib_handle)?;
-    check_debug_button_click(world, raylib_handle)?;
-    update_construction_hover(world, raylib_handle, camera);
-
-    if raylib_handle.is_key_released(KEY_F10) {
-        toggle_mouse_selection(world)?;
+pub fn register_input_systems(schedule: &mut Schedule) {
+    // Camera and building placement only make sense over the map
+    let test_map = Scene::TestMap.label();
+
+    schedule.add_system(
+        SystemEntry::new("update_camera", Stage::Input, System::Update(update_camera))
+            .in_scene(test_map),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "place_hovering_building",
+            Stage::Input,
+            System::Update(place_hovering_building),
+        )
+        .before("check_debug_button_click")
+        .in_scene(test_map),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "cancel_hovering_building",
+            Stage::Input,
+            System::Update(cancel_hovering_building),
+        )
+        .in_scene(test_map),
+    );
+    schedule.add_system(SystemEntry::new(
+        "check_debug_button_click",
+        Stage::Input,
+        System::Update(check_debug_button_click),
+    ));
+    schedule.add_system(
+        SystemEntry::new(
+            "rotate_hovering_building",
+            Stage::Input,
+            System::Update(rotate_hovering_building),
+        )
+        .before("update_construction_hover")
+        .in_scene(test_map),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "update_construction_hover",
+            Stage::Input,
+            System::Update(update_construction_hover),
+        )
+        .after("update_camera")
+        .after("check_debug_button_click")
+        .in_scene(test_map),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "select_villager_under_mouse",
+            Stage::Input,
+            System::Update(select_villager_under_mouse),
+        )
+        .before("place_hovering_building")
+        .in_scene(test_map),
+    );
+    schedule.add_system(
+        SystemEntry::new(
+            "check_mouse_selection_toggle",
+            Stage::Input,
+            System::Update(check_mouse_selection_toggle),
+        )
+        .in_scene(test_map),
+    );
+    schedule.add_system(SystemEntry::new(
+        "check_quicksave_keys",
+        Stage::Input,
+        System::Update(check_quicksave_keys),
+    ));
+}
+
+pub fn check_mouse_selection_toggle(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let released = {
+        let input = ctx.resources.get::<InputState>()?;
+        ctx.resources.get::<GameActions>()?.is_released(&input, Action::ToggleSelection)
+    };
+    if released {
+        toggle_mouse_selection(world, tileset())?;
     }
 
     Ok(())
 }
 
-pub fn check_debug_button_click(world: &mut World, raylib_handle: &mut RaylibHandle) -> Result<(), String> {
-    let zoom: f32;
-    {
-        let mut zoom_query = world.query::<&CameraZoom>();
-        let (_, CameraZoom(z)) = zoom_query.into_iter().nth(0).ok_or("Camera zoom missing")?;
-        zoom = z.clone();
+/// Clicking a hauler on the map shows its state through `SelectedHauler`. Clicks on buttons or
+/// placing a building don't change the selection.
+pub fn select_villager_under_mouse(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let (mouse_pos, mouse_world_pos) = {
+        let input = ctx.resources.get::<InputState>()?;
+        if !ctx.resources.get::<GameActions>()?.is_released(&input, Action::Click) {
+            return Ok(());
+        }
+        let camera = ctx.m_camera.as_deref().ok_or(EngineError::MissingContext("Camera"))?;
+        (input.mouse_position(), input.mouse_world_position(camera))
+    };
+    let UiScale(scale) = *ctx.resources.get::<UiScale>()?;
+    let over_button = world
+        .query::<&UIElement>()
+        .iter()
+        .filter(|(entity, _)| world.get::<Button>(*entity).is_ok() || world.get::<ToggleButton>(*entity).is_ok())
+        .any(|(_, element)| is_point_inside_box(&mouse_pos, &button_box(element, scale)));
+    let placing = world.query::<&ConstructionPlacement>().iter().next().is_some();
+    if over_button || placing {
+        return Ok(());
+    }
+
+    let m_hauler = {
+        let grid = ctx.resources.get::<CollisionGrid>()?;
+        entities_at_point(world, &grid, mouse_world_pos, CollisionMask::NONE.with(CollisionLayer::Villager))
+            .into_iter()
+            .find(|entity| world.get::<Hauler>(*entity).is_ok())
+    };
+    if let Some(hauler) = m_hauler {
+        ctx.resources.get_mut::<SelectedHauler>()?.m_hauler = Some(hauler);
+    }
+
+    Ok(())
+}
+
+/// Quicksave (F5 by default) saves the colony to the quicksave slot, quickload (F9) loads it
+/// back. A failed save or load is logged and the game keeps running.
+pub fn check_quicksave_keys(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let (save_pressed, load_pressed) = {
+        let input = ctx.resources.get::<InputState>()?;
+        let actions = ctx.resources.get::<GameActions>()?;
+        (
+            actions.is_released(&input, Action::QuickSave),
+            actions.is_released(&input, Action::QuickLoad),
+        )
+    };
+
+    if save_pressed {
+        if let Err(error) = save_colony(world, ctx.resources, SAVE_PATH) {
+            warn!("Quicksave failed: {}", error);
+        }
+    }
+    if load_pressed {
+        if let Err(error) = queue_colony_load(ctx.resources, SAVE_PATH) {
+            warn!("Quickload failed: {}", error);
+        }
     }
 
-    let mut functions: Vec<fn(&mut World) -> Result<(), String>> = vec![];
-    let mut handle_functions: Vec<fn(&mut World, &mut RaylibHandle) -> Result<(), String>> = vec![];
+    Ok(())
+}
+
+/// Updates the button states from the mouse and runs the actions of clicked buttons. Actions
+/// that need the raylib handle are skipped with a warning when running without a window.
+pub fn check_debug_button_click(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let input = ctx.resources.get::<InputState>()?.clone();
+    let (click_down, click_released) = {
+        let actions = ctx.resources.get::<GameActions>()?;
+        (actions.is_down(&input, Action::Click), actions.is_released(&input, Action::Click))
+    };
+    let UiScale(scale) = *ctx.resources.get::<UiScale>()?;
+    // Buttons of scenes below the top one (the map under the pause menu...) don't react
+    let m_scene = ctx.resources.get::<SceneStack>()?.current();
+    let in_current_scene = |owner: Option<&SceneOwned>| owner.is_none_or(|owner| Some(owner.0) == m_scene);
+
+    let mut functions: Vec<ButtonAction> = vec![];
+    let mut handle_functions: Vec<HandleAction> = vec![];
 
     let button_query = world
-        .query_mut::<(&mut Button, &UIElement)>()
+        .query_mut::<(&mut Button, &UIElement, Option<&SceneOwned>)>()
         .without::<ToggleButton>();
-    for (_, (button, element)) in button_query.into_iter() {
-        let mouse_pos = raylib_handle.get_mouse_position();
-        let button_box = Rectangle {
-            x: element.position.x,
-            y: element.position.y - (TILE_SIZE * zoom),
-            width: TILE_SIZE * zoom,
-            height: TILE_SIZE * zoom,
-        };
-        if is_point_inside_box(&mouse_pos, &button_box) {
+    for (_, (button, element, owner)) in button_query.into_iter() {
+        if !in_current_scene(owner) {
+            continue;
+        }
+        let mouse_pos = input.mouse_position();
+        if is_point_inside_box(&mouse_pos, &button_box(element, scale)) {
             button.state = ButtonState::Hovered;
-            if raylib_handle.is_mouse_button_down(MOUSE_LEFT_BUTTON) {
+            if click_down {
                 button.state = ButtonState::Pressed;
             }
 
-            if raylib_handle.is_mouse_button_released(MOUSE_LEFT_BUTTON) {
-                if button.action.is_some() {
-                    functions.push(button.action.ok_or("No button action")?);
+            if click_released {
+                if let Some(action) = button.action {
+                    functions.push(action);
                 }
-                if button.handle_action.is_some() {
-                    handle_functions.push(button.handle_action.ok_or("No button handle action")?);
+                if let Some(handle_action) = button.handle_action {
+                    handle_functions.push(handle_action);
                 }
             }
         } else {
@@ -72,29 +219,26 @@ pub fn check_debug_button_click(world: &mut World, raylib_handle: &mut RaylibHan
     }
 
     let toggle_button_query = world
-        .query_mut::<(&mut ToggleButton, &UIElement)>()
+        .query_mut::<(&mut ToggleButton, &UIElement, Option<&SceneOwned>)>()
         .without::<Button>();
 
-    for (_, (button, element)) in toggle_button_query.into_iter() {
-        let mouse_pos = raylib_handle.get_mouse_position();
-        let button_box = Rectangle {
-            x: element.position.x,
-            y: element.position.y - (TILE_SIZE * zoom),
-            width: TILE_SIZE * zoom,
-            height: TILE_SIZE * zoom,
-        };
-        if is_point_inside_box(&mouse_pos, &button_box) {
-            if raylib_handle.is_mouse_button_released(MOUSE_LEFT_BUTTON) {
+    for (_, (button, element, owner)) in toggle_button_query.into_iter() {
+        if !in_current_scene(owner) {
+            continue;
+        }
+        let mouse_pos = input.mouse_position();
+        if is_point_inside_box(&mouse_pos, &button_box(element, scale)) {
+            if click_released {
                 if button.state == ButtonState::Toggled {
                     button.state = ButtonState::Hovered;
                 } else {
                     button.state = ButtonState::Toggled;
                 }
-                if button.action.is_some() {
-                    functions.push(button.action.ok_or("No button action")?);
+                if let Some(action) = button.action {
+                    functions.push(action);
                 }
-                if button.handle_action.is_some() {
-                    handle_functions.push(button.handle_action.ok_or("No button handle action")?);
+                if let Some(handle_action) = button.handle_action {
+                    handle_functions.push(handle_action);
                 }
             } else if button.state != ButtonState::Toggled {
                 button.state = ButtonState::Hovered;
@@ -107,35 +251,26 @@ pub fn check_debug_button_click(world: &mut World, raylib_handle: &mut RaylibHan
     }
 
     for function in functions.iter() {
-        function(world)?;
+        function(world, ctx)?;
     }
 
-    for handle_function in handle_functions.iter() {
-        handle_function(world, raylib_handle)?;
+    // Actions on the window itself have nothing to act on in headless runs and replays
+    match ctx.m_raylib_handle.as_deref_mut() {
+        Some(raylib_handle) => {
+            for handle_function in handle_functions.iter() {
+                handle_function(world, raylib_handle)?;
+            }
+        }
+        None if !handle_functions.is_empty() => {
+            warn!("Skipped {} button action(s) that need a window", handle_functions.len());
+        }
+        None => {}
     }
 
     Ok(())
 }
 
-pub fn read_camera_input(raylib_handle: &mut RaylibHandle, target: Vector2) -> Vector2 {
-    let mut new_target = target;
-    if raylib_handle.is_key_down(KEY_D) {
-        new_target.x = target.x + CAMERA_SPEED;
-    }
-    if raylib_handle.is_key_down(KEY_A) {
-        new_target.x = target.x - CAMERA_SPEED;
-    }
-    if raylib_handle.is_key_down(KEY_W) {
-        new_target.y = target.y - CAMERA_SPEED;
-    }
-    if raylib_handle.is_key_down(KEY_S) {
-        new_target.y = target.y + CAMERA_SPEED;
-    }
-
-    return new_target;
-}
-
-pub fn toggle_debug_text(world: &mut World) -> Result<(), String> {
+pub fn toggle_debug_text(world: &mut World, _ctx: &mut SystemContext) -> Result<(), EngineError> {
     let mut entity_list: Vec<Entity> = vec![];
     {
         let mut selection_query = world.query::<&DebugUI>();
@@ -147,14 +282,14 @@ pub fn toggle_debug_text(world: &mut World) -> Result<(), String> {
         world.spawn((DebugUI,));
     } else {
         for entity in entity_list {
-            world.despawn(entity).map_err(|_| "No such entity")?;
+            world.despawn(entity).map_err(|_| EngineError::NoSuchEntity(entity))?;
         }
     }
 
     Ok(())
 }
 
-pub fn toggle_draw_collisions(world: &mut World) -> Result<(), String> {
+pub fn toggle_draw_collisions(world: &mut World, _ctx: &mut SystemContext) -> Result<(), EngineError> {
     let mut entity_list: Vec<Entity> = vec![];
     {
         let mut selection_query = world.query::<&DrawCollisions>();
@@ -166,9 +301,19 @@ pub fn toggle_draw_collisions(world: &mut World) -> Result<(), String> {
         world.spawn((DrawCollisions,));
     } else {
         for entity in entity_list {
-            world.despawn(entity).map_err(|_| "No such entity")?;
+            world.despawn(entity).map_err(|_| EngineError::NoSuchEntity(entity))?;
         }
     }
 
     Ok(())
 }
+
+/// Screen area a button is clicked in, `position` being its bottom left corner
+fn button_box(element: &UIElement, scale: f32) -> Rectangle {
+    Rectangle {
+        x: element.position.x,
+        y: element.position.y - (TILE_SIZE * scale),
+        width: TILE_SIZE * scale,
+        height: TILE_SIZE * scale,
+    }
+}
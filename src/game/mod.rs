@@ -1,9 +1,22 @@
+pub mod actions;
+pub mod animations;
+pub mod assets;
+pub mod bench;
 pub mod buildings;
+pub mod camera;
+pub mod cli;
+pub mod config;
 pub mod constants;
+pub mod content;
 pub mod draw;
 pub mod enums;
+pub mod events;
+pub mod headless;
 pub mod input;
+pub mod resource_registry;
+pub mod save;
 pub mod scenes;
+pub mod settings;
 pub mod startup;
 pub mod step;
 pub mod tasks;
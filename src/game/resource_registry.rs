@@ -0,0 +1,148 @@
+use std::{collections::BTreeMap, fmt};
+
+use raylib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{assets::Assets, error::EngineError};
+
+use super::constants::TILE_SIZE;
+
+// STRUCTS ------
+/// Id of a resource type, the key it has in the resources file
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ResourceId(pub String);
+
+impl ResourceId {
+    pub fn new(id: &str) -> ResourceId {
+        ResourceId(id.to_string())
+    }
+}
+
+impl fmt::Display for ResourceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum ResourceCategory {
+    Raw,
+    Processed,
+    Food,
+}
+
+/// One resource type as written in the resources file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceDefinition {
+    /// Shown to the player
+    pub name: String,
+    /// Icon tile in the tileset
+    pub icon_atlas_tile: [f32; 2],
+    /// Amount a hauler carries in one trip
+    pub stack_size: i32,
+    pub category: ResourceCategory,
+    /// Weight of a single unit
+    pub weight: f32,
+}
+
+impl ResourceDefinition {
+    pub fn icon_rect(&self) -> Rectangle {
+        Rectangle {
+            x: self.icon_atlas_tile[0] * TILE_SIZE,
+            y: self.icon_atlas_tile[1] * TILE_SIZE,
+            width: TILE_SIZE,
+            height: TILE_SIZE,
+        }
+    }
+
+    fn problems(&self, id: &ResourceId) -> Vec<String> {
+        let mut problems: Vec<String> = vec![];
+        if self.name.trim().is_empty() {
+            problems.push(format!("{}: name must not be empty", id));
+        }
+        if !self.icon_atlas_tile.iter().all(|value| value.is_finite() && *value >= 0.0) {
+            problems.push(format!("{}: icon_atlas_tile must be two numbers of 0 or more", id));
+        }
+        if self.stack_size <= 0 {
+            problems.push(format!("{}: stack_size must be above 0, got {}", id, self.stack_size));
+        }
+        if !self.weight.is_finite() || self.weight < 0.0 {
+            problems.push(format!("{}: weight must be 0 or more, got {}", id, self.weight));
+        }
+
+        problems
+    }
+}
+
+/// Every resource type, keyed by id. Read from the resources file at startup and stored as a
+/// resource.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct ResourceRegistry {
+    definitions: BTreeMap<ResourceId, ResourceDefinition>,
+}
+
+impl ResourceRegistry {
+    /// Reads the registry at `path` through `assets`
+    pub fn load(assets: &mut Assets, path: &str) -> Result<ResourceRegistry, EngineError> {
+        let handle = assets.load_data(path)?;
+        let registry = ResourceRegistry::parse(path, &assets.get(handle)?.text)?;
+        info!("Loaded {} resource definitions from {}", registry.definitions.len(), path);
+
+        Ok(registry)
+    }
+
+    pub fn parse(path: &str, text: &str) -> Result<ResourceRegistry, EngineError> {
+        let registry: ResourceRegistry =
+            serde_json::from_str(text).map_err(|error| registry_error(path, error.to_string()))?;
+        registry.validate().map_err(|reason| registry_error(path, reason))?;
+
+        Ok(registry)
+    }
+
+    /// Checks every definition is usable, reporting all the bad values at once
+    pub fn validate(&self) -> Result<(), String> {
+        let problems: Vec<String> = self
+            .definitions
+            .iter()
+            .flat_map(|(id, definition)| definition.problems(id))
+            .collect();
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join(", "))
+        }
+    }
+
+    pub fn get(&self, id: &ResourceId) -> Result<&ResourceDefinition, EngineError> {
+        self.definitions
+            .get(id)
+            .ok_or_else(|| EngineError::NotFound {
+                kind: "resource",
+                name: id.to_string(),
+            })
+    }
+
+    pub fn contains(&self, id: &ResourceId) -> bool {
+        self.definitions.contains_key(id)
+    }
+
+    pub fn stack_size(&self, id: &ResourceId) -> Result<i32, EngineError> {
+        Ok(self.get(id)?.stack_size)
+    }
+
+    /// Resources by category, then by id
+    pub fn sorted_ids(&self) -> Vec<&ResourceId> {
+        let mut ids: Vec<&ResourceId> = self.definitions.keys().collect();
+        ids.sort_by_key(|id| (self.definitions[*id].category, *id));
+        ids
+    }
+}
+
+// FUNCTIONS ------
+fn registry_error(path: &str, reason: String) -> EngineError {
+    EngineError::data("Definitions", path, reason)
+}
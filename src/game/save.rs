@@ -0,0 +1,753 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use hecs::{Entity, World};
+use rand_chacha::ChaCha12Rng;
+use raylib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{
+    assets::Handle,
+    collision::{BodyCollision, CollisionBox, CollisionMask, TriggerCollision},
+    datatypes::{GameRng, IdCounter, Sprite},
+    enums::{CollisionLayer, Rotation, VillagerState},
+    error::EngineError,
+    input::RecordedFiles,
+    resources::Resources,
+    scene::SceneStack,
+    schedule::SystemContext,
+};
+
+use super::{
+    animations::animator_for,
+    buildings::{
+        datatypes::{
+            Building, BuildingKind, ConstructionPlacement, ConstructionStorage, Footprint, House,
+            OngoingConstruction, Storage, StorageSpace, Warehouse,
+        },
+        definitions::BuildingDefinitions,
+    },
+    config::GameConfig,
+    constants::{CHUNK_TILE_SIZE, TILESET_PATH, TILE_SIZE, UI_ATLAS_PATH},
+    resource_registry::{ResourceId, ResourceRegistry},
+    scenes::Scene,
+    startup::spawn_ui,
+    tasks::{HaulTask, OpenTasks},
+    tilemap::{Chunk, Tile, Tilemap},
+    ui::datatypes::{GlobalStorage, SelectedHauler},
+    villagers::{
+        datatypes::{Backpack, CarryingState, GameItem, Hauler, IdleInfo, IdleState, LoadingState, WorkingState},
+        step::villager_state,
+    },
+};
+
+/// Bumped whenever the layout of `SaveFile` changes. Files with another version are refused.
+pub const SAVE_VERSION: u32 = 1;
+
+/// Texture paths sprites may reference. Handles are path hashes, so saving a sprite needs the
+/// path its handle was built from.
+const SPRITE_TEXTURE_PATHS: [&str; 2] = [TILESET_PATH, UI_ATLAS_PATH];
+
+// STRUCTS ------
+/// On-disk layout of a saved colony. It only uses plain types, so components can change shape
+/// without breaking the format as long as the conversions below are kept up to date.
+#[derive(Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    /// Next id handed out to haul tasks
+    pub next_id: usize,
+    pub rng: ChaCha12Rng,
+    pub tilemap: SavedTilemap,
+    pub open_tasks: Vec<SavedHaulTask>,
+    pub entities: Vec<SavedEntity>,
+    /// Saved id of the hauler in the `SelectedHauler` resource
+    pub selected_hauler: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedTilemap {
+    pub tileset: String,
+    pub chunks: Vec<SavedChunk>,
+}
+
+/// Tiles are stored row by row as atlas tiles (`row * 256 + column`), their world coords
+/// are rebuilt from the chunk rect on load.
+#[derive(Serialize, Deserialize)]
+pub struct SavedChunk {
+    pub rect: [f32; 4],
+    pub tiles: Vec<u16>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedHaulTask {
+    pub id: usize,
+    pub origin_position: Option<[f32; 2]>,
+    pub destination_position: Option<[f32; 2]>,
+    pub resource: ResourceId,
+    pub amount: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedSprite {
+    pub texture: String,
+    pub rect: [f32; 4],
+    pub position: [f32; 2],
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedCollisionBox {
+    pub rect: [f32; 4],
+    pub layer: CollisionLayer,
+    pub mask: Vec<CollisionLayer>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedFootprint {
+    pub width: i32,
+    pub height: i32,
+    pub entrance: (i32, i32),
+    pub rotation: Rotation,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedIdleInfo {
+    pub idle_point: [f32; 2],
+    pub idle_timer: f32,
+    pub timer_range: (f32, f32),
+    pub radius: f32,
+    pub target_position: [f32; 2],
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedItem {
+    pub resource: ResourceId,
+    pub amount: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedBackpack {
+    pub item: Option<SavedItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedStorageSpace {
+    pub item_list: BTreeMap<ResourceId, i32>,
+    pub reserved_item_list: BTreeMap<ResourceId, i32>,
+    pub capacity: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedConstructionStorage {
+    pub tasks_generated: bool,
+    pub required_item_list: BTreeMap<ResourceId, i32>,
+}
+
+/// Every saved component of one entity. Collision state is left out, the collision systems
+/// recompute it on the first tick.
+#[derive(Default, Serialize, Deserialize)]
+pub struct SavedEntity {
+    /// Entity bits at save time, only used to resolve references between entities
+    pub id: u64,
+    pub sprite: Option<SavedSprite>,
+    pub collision_box: Option<SavedCollisionBox>,
+    pub footprint: Option<SavedFootprint>,
+    pub body_collision: bool,
+    pub trigger_collision: bool,
+    pub building: bool,
+    /// Type id of the building definition
+    pub building_kind: Option<String>,
+    pub warehouse: bool,
+    pub house: bool,
+    pub storage: bool,
+    pub storage_space: Option<SavedStorageSpace>,
+    pub construction_storage: Option<SavedConstructionStorage>,
+    pub work_required: Option<f32>,
+    pub hauler: bool,
+    pub villager_state: Option<VillagerState>,
+    pub idle_info: Option<SavedIdleInfo>,
+    pub backpack: Option<SavedBackpack>,
+    pub haul_task: Option<SavedHaulTask>,
+}
+
+/// A save read and checked by `queue_colony_load`, stored as a resource until it replaces the
+/// world between frames.
+pub struct PendingColony {
+    path: String,
+    save: SaveFile,
+    tilemap: Tilemap,
+}
+
+// FUNCTIONS ------
+/// Writes the colony (tilemap, entities, open tasks and rng state) to `path`.
+pub fn save_colony(world: &World, resources: &Resources, path: &str) -> Result<(), EngineError> {
+    let save = build_save(world, resources)?;
+    let save_error = |reason: String| EngineError::data("Save file", path, reason);
+
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).map_err(|error| save_error(error.to_string()))?;
+    }
+    let text = serde_json::to_string(&save).map_err(|error| save_error(error.to_string()))?;
+    fs::write(path, text).map_err(|error| save_error(error.to_string()))?;
+    info!("Saved colony to {}", path);
+
+    Ok(())
+}
+
+/// Replaces the world and the colony resources with the ones saved in `path`. On error the
+/// current world is left untouched. Meant for startup, systems should use `queue_colony_load`.
+pub fn load_colony(world: &mut World, resources: &mut Resources, path: &str) -> Result<(), EngineError> {
+    let colony = read_save(resources, path)?;
+    restore_save(world, resources, colony)?;
+    info!("Loaded colony from {}", path);
+
+    Ok(())
+}
+
+/// Reads and checks the save in `path` now, and replaces the world with it between frames,
+/// after the current scenes were left. On error nothing is queued.
+pub fn queue_colony_load(resources: &mut Resources, path: &str) -> Result<(), EngineError> {
+    let colony = read_save(resources, path)?;
+    resources.insert(colony);
+    resources
+        .get_mut::<SceneStack>()?
+        .replace_with(restore_pending_colony);
+
+    Ok(())
+}
+
+fn restore_pending_colony(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let colony = ctx
+        .resources
+        .remove::<PendingColony>()
+        .ok_or(EngineError::MissingResource("PendingColony"))?;
+    let path = colony.path.clone();
+    restore_save(world, ctx.resources, colony)?;
+    info!("Loaded colony from {}", path);
+
+    Ok(())
+}
+
+/// Reads the save in `path` and checks everything restoring it needs, so the restore itself
+/// can't fail halfway through. Reads go through `RecordedFiles` when the session is recorded or
+/// replayed.
+fn read_save(resources: &Resources, path: &str) -> Result<PendingColony, EngineError> {
+    let save_error = |reason: String| EngineError::data("Save file", path, reason);
+
+    let text = if resources.contains::<RecordedFiles>() {
+        resources.get_mut::<RecordedFiles>()?.read(path).map_err(save_error)?
+    } else {
+        fs::read_to_string(path).map_err(|error| save_error(error.to_string()))?
+    };
+    let value: serde_json::Value = serde_json::from_str(&text).map_err(|error| save_error(error.to_string()))?;
+    let version = value
+        .get("version")
+        .and_then(|version| version.as_u64())
+        .ok_or_else(|| save_error("missing version".to_string()))?;
+    if version != SAVE_VERSION as u64 {
+        return Err(save_error(format!(
+            "unsupported version {} (expected {})",
+            version, SAVE_VERSION
+        )));
+    }
+    let save: SaveFile = serde_json::from_value(value).map_err(|error| save_error(error.to_string()))?;
+
+    let tilemap = load_tilemap(&save.tilemap, path)?;
+
+    let mut entity_ids: HashSet<u64> = HashSet::new();
+    for saved in save.entities.iter() {
+        entity_ids.insert(saved.id);
+    }
+    if let Some(id) = save.selected_hauler {
+        if !entity_ids.contains(&id) {
+            return Err(save_error(format!("selected hauler {} is not in the save", id)));
+        }
+    }
+    let buildings = resources.get::<BuildingDefinitions>()?;
+    for kind in save.entities.iter().filter_map(|saved| saved.building_kind.as_ref()) {
+        if buildings.get(kind).is_err() {
+            return Err(save_error(format!("building type {:?} has no definition", kind)));
+        }
+    }
+
+    let registry = resources.get::<ResourceRegistry>()?;
+    if let Some(resource) = saved_resources(&save).into_iter().find(|resource| !registry.contains(resource)) {
+        return Err(save_error(format!("resource {:?} has no definition", resource.0)));
+    }
+
+    Ok(PendingColony {
+        path: path.to_string(),
+        save,
+        tilemap,
+    })
+}
+
+fn build_save(world: &World, resources: &Resources) -> Result<SaveFile, EngineError> {
+    let tilemap = resources.get::<Tilemap>()?;
+    let GameRng(rng) = &*resources.get::<GameRng>()?;
+    let open_tasks = resources.get::<OpenTasks>()?;
+
+    let mut entities: Vec<SavedEntity> = vec![];
+    let mut query = world
+        .query::<&Sprite>()
+        .without::<ConstructionPlacement>();
+    for (entity, sprite) in query.iter() {
+        entities.push(save_entity(world, entity, sprite)?);
+    }
+
+    let m_selected_hauler = resources
+        .get::<SelectedHauler>()?
+        .m_hauler
+        .map(|hauler| hauler.to_bits().get());
+
+    Ok(SaveFile {
+        version: SAVE_VERSION,
+        next_id: resources.get::<IdCounter>()?.peek(),
+        rng: rng.clone(),
+        tilemap: save_tilemap(&tilemap),
+        open_tasks: open_tasks.haul_list.iter().map(save_haul_task).collect(),
+        entities,
+        selected_hauler: m_selected_hauler,
+    })
+}
+
+fn restore_save(world: &mut World, resources: &mut Resources, colony: PendingColony) -> Result<(), EngineError> {
+    let PendingColony { save, tilemap, .. } = colony;
+    let buildings = resources.get::<BuildingDefinitions>()?.clone();
+
+    world.clear();
+
+    let mut entity_map: HashMap<u64, Entity> = HashMap::new();
+    for saved in save.entities.iter() {
+        let entity = spawn_saved_entity(world, &buildings, saved)?;
+        entity_map.insert(saved.id, entity);
+    }
+
+    resources.insert(SelectedHauler {
+        m_hauler: save.selected_hauler.map(|id| entity_map[&id]),
+    });
+    spawn_ui(world, &*resources.get::<GameConfig>()?)?;
+
+    resources.insert(tilemap);
+    resources.insert(OpenTasks {
+        haul_list: save.open_tasks.iter().map(load_haul_task).collect(),
+    });
+    resources.insert(GlobalStorage::default());
+    resources.insert(GameRng(save.rng));
+    resources.insert(IdCounter::starting_at(save.next_id));
+    resources
+        .get_mut::<SceneStack>()?
+        .reset_to(Scene::TestMap.label());
+
+    Ok(())
+}
+
+fn save_entity(world: &World, entity: Entity, sprite: &Sprite) -> Result<SavedEntity, EngineError> {
+    let texture = SPRITE_TEXTURE_PATHS
+        .iter()
+        .find(|path| Handle::from_path(path) == sprite.texture)
+        .ok_or_else(|| EngineError::AssetNotLoaded(format!("{:?}", sprite.texture)))?;
+
+    let mut saved = SavedEntity {
+        id: entity.to_bits().get(),
+        sprite: Some(SavedSprite {
+            texture: texture.to_string(),
+            rect: rect_to_array(&sprite.rect),
+            position: vector_to_array(&sprite.position),
+        }),
+        ..SavedEntity::default()
+    };
+
+    let entity_ref = world.entity(entity).map_err(|_| EngineError::NoSuchEntity(entity))?;
+    saved.body_collision = entity_ref.has::<BodyCollision>();
+    saved.trigger_collision = entity_ref.has::<TriggerCollision>();
+    saved.building = entity_ref.has::<Building>();
+    saved.warehouse = entity_ref.has::<Warehouse>();
+    saved.house = entity_ref.has::<House>();
+    saved.building_kind = entity_ref.get::<BuildingKind>().map(|kind| kind.0.clone());
+    saved.storage = entity_ref.has::<Storage>();
+    saved.hauler = entity_ref.has::<Hauler>();
+
+    if let Some(col_box) = entity_ref.get::<CollisionBox>() {
+        saved.collision_box = Some(SavedCollisionBox {
+            rect: rect_to_array(&col_box.rect),
+            layer: col_box.layer,
+            mask: col_box.mask.layers().collect(),
+        });
+    }
+    if let Some(footprint) = entity_ref.get::<Footprint>() {
+        saved.footprint = Some(SavedFootprint {
+            width: footprint.width,
+            height: footprint.height,
+            entrance: footprint.entrance,
+            rotation: footprint.rotation,
+        });
+    }
+    if let Some(storage) = entity_ref.get::<StorageSpace>() {
+        saved.storage_space = Some(SavedStorageSpace {
+            item_list: storage.item_list.clone().into_iter().collect(),
+            reserved_item_list: storage.reserved_item_list.clone().into_iter().collect(),
+            capacity: storage.capacity,
+        });
+    }
+    if let Some(construction) = entity_ref.get::<ConstructionStorage>() {
+        saved.construction_storage = Some(SavedConstructionStorage {
+            tasks_generated: construction.tasks_generated,
+            required_item_list: construction.required_item_list.clone().into_iter().collect(),
+        });
+    }
+    if let Some(construction) = entity_ref.get::<OngoingConstruction>() {
+        saved.work_required = Some(construction.work_required);
+    }
+    if let Some(idle_info) = entity_ref.get::<IdleInfo>() {
+        saved.idle_info = Some(SavedIdleInfo {
+            idle_point: vector_to_array(&idle_info.idle_point),
+            idle_timer: idle_info.idle_timer,
+            timer_range: idle_info.timer_range,
+            radius: idle_info.radius,
+            target_position: vector_to_array(&idle_info.target_position),
+        });
+    }
+    if let Some(backpack) = entity_ref.get::<Backpack>() {
+        saved.backpack = Some(SavedBackpack {
+            item: backpack.item.as_ref().map(|item| SavedItem {
+                resource: item.resource.clone(),
+                amount: item.amount,
+            }),
+        });
+    }
+    if let Some(haul_task) = entity_ref.get::<HaulTask>() {
+        saved.haul_task = Some(save_haul_task(&haul_task));
+    }
+
+    saved.villager_state = villager_state(world, entity);
+
+    Ok(saved)
+}
+
+fn spawn_saved_entity(
+    world: &mut World,
+    buildings: &BuildingDefinitions,
+    saved: &SavedEntity,
+) -> Result<Entity, EngineError> {
+    let entity = world.spawn(());
+    let insert_error = |_| EngineError::NoSuchEntity(entity);
+
+    if let Some(sprite) = &saved.sprite {
+        world
+            .insert_one(
+                entity,
+                Sprite {
+                    texture: Handle::from_path(&sprite.texture),
+                    rect: array_to_rect(sprite.rect),
+                    position: array_to_vector(sprite.position),
+                },
+            )
+            .map_err(insert_error)?;
+    }
+    if let Some(col_box) = &saved.collision_box {
+        let mask: CollisionMask = col_box.mask.iter().copied().collect();
+        world
+            .insert_one(entity, CollisionBox::new(array_to_rect(col_box.rect), col_box.layer, mask))
+            .map_err(insert_error)?;
+    }
+    if let Some(footprint) = &saved.footprint {
+        let footprint = Footprint {
+            width: footprint.width,
+            height: footprint.height,
+            entrance: footprint.entrance,
+            rotation: footprint.rotation,
+        };
+        world.insert_one(entity, footprint).map_err(insert_error)?;
+    }
+    if saved.body_collision {
+        world.insert_one(entity, BodyCollision::default()).map_err(insert_error)?;
+    }
+    if saved.trigger_collision {
+        world.insert_one(entity, TriggerCollision::new()).map_err(insert_error)?;
+    }
+    if saved.building {
+        world.insert_one(entity, Building).map_err(insert_error)?;
+    }
+    if let Some(kind) = &saved.building_kind {
+        world.insert_one(entity, BuildingKind(kind.clone())).map_err(insert_error)?;
+    }
+    if saved.warehouse {
+        world.insert_one(entity, Warehouse).map_err(insert_error)?;
+    }
+    if saved.house {
+        world.insert_one(entity, House).map_err(insert_error)?;
+    }
+    if saved.storage {
+        world.insert_one(entity, Storage).map_err(insert_error)?;
+    }
+    if let Some(storage) = &saved.storage_space {
+        world
+            .insert_one(
+                entity,
+                StorageSpace {
+                    item_list: storage.item_list.clone().into_iter().collect(),
+                    reserved_item_list: storage.reserved_item_list.clone().into_iter().collect(),
+                    capacity: storage.capacity,
+                },
+            )
+            .map_err(insert_error)?;
+    }
+    if let Some(construction) = &saved.construction_storage {
+        world
+            .insert_one(
+                entity,
+                ConstructionStorage {
+                    tasks_generated: construction.tasks_generated,
+                    required_item_list: construction.required_item_list.clone().into_iter().collect(),
+                },
+            )
+            .map_err(insert_error)?;
+    }
+    if let Some(work_required) = saved.work_required {
+        world
+            .insert_one(entity, OngoingConstruction { work_required })
+            .map_err(insert_error)?;
+    }
+    if saved.hauler {
+        world.insert_one(entity, Hauler).map_err(insert_error)?;
+    }
+    if let Some(idle_info) = &saved.idle_info {
+        world
+            .insert_one(
+                entity,
+                IdleInfo {
+                    idle_point: array_to_vector(idle_info.idle_point),
+                    idle_timer: idle_info.idle_timer,
+                    timer_range: idle_info.timer_range,
+                    radius: idle_info.radius,
+                    target_position: array_to_vector(idle_info.target_position),
+                },
+            )
+            .map_err(insert_error)?;
+    }
+    if let Some(backpack) = &saved.backpack {
+        let backpack = Backpack {
+            item: backpack.item.as_ref().map(|item| GameItem::new(item.resource.clone(), item.amount)),
+        };
+        world.insert_one(entity, backpack).map_err(insert_error)?;
+    }
+    if let Some(haul_task) = &saved.haul_task {
+        world.insert_one(entity, load_haul_task(haul_task)).map_err(insert_error)?;
+    }
+    match saved.villager_state {
+        Some(VillagerState::Idle) => world.insert_one(entity, IdleState),
+        Some(VillagerState::Loading) => world.insert_one(entity, LoadingState),
+        Some(VillagerState::Carrying) => world.insert_one(entity, CarryingState),
+        Some(VillagerState::Working) => world.insert_one(entity, WorkingState),
+        None => Ok(()),
+    }
+    .map_err(insert_error)?;
+    if let Some(animator) = animator_for(world, buildings, entity)? {
+        world.insert_one(entity, animator).map_err(insert_error)?;
+    }
+
+    Ok(entity)
+}
+
+fn save_tilemap(tilemap: &Tilemap) -> SavedTilemap {
+    let chunks = tilemap
+        .chunks
+        .iter()
+        .map(|chunk| SavedChunk {
+            rect: rect_to_array(&chunk.rect),
+            tiles: chunk
+                .tiles
+                .iter()
+                .map(|tile| {
+                    let atlas = tile.get_sprite_coord();
+                    let column = (atlas.x / TILE_SIZE) as u16;
+                    let row = (atlas.y / TILE_SIZE) as u16;
+                    row * 256 + column
+                })
+                .collect(),
+        })
+        .collect();
+
+    SavedTilemap {
+        tileset: TILESET_PATH.to_string(),
+        chunks,
+    }
+}
+
+fn load_tilemap(saved: &SavedTilemap, path: &str) -> Result<Tilemap, EngineError> {
+    let mut chunks: Vec<Chunk> = vec![];
+
+    for saved_chunk in saved.chunks.iter() {
+        let tile_count = (CHUNK_TILE_SIZE * CHUNK_TILE_SIZE) as usize;
+        if saved_chunk.tiles.len() != tile_count {
+            return Err(EngineError::data(
+                "Save file",
+                path,
+                format!("chunk has {} tiles, expected {}", saved_chunk.tiles.len(), tile_count),
+            ));
+        }
+
+        let rect = array_to_rect(saved_chunk.rect);
+        let tiles = saved_chunk
+            .tiles
+            .iter()
+            .enumerate()
+            .map(|(index, atlas_tile)| {
+                let index = index as i32;
+                let coord = Vector2 {
+                    x: rect.x + (index % CHUNK_TILE_SIZE) as f32,
+                    y: rect.y + (index / CHUNK_TILE_SIZE) as f32,
+                };
+                let atlas = Rectangle {
+                    x: (atlas_tile % 256) as f32 * TILE_SIZE,
+                    y: (atlas_tile / 256) as f32 * TILE_SIZE,
+                    width: TILE_SIZE,
+                    height: TILE_SIZE,
+                };
+                Tile::new(coord, atlas)
+            })
+            .collect();
+
+        chunks.push(Chunk {
+            rect,
+            tiles,
+            visible: false,
+        });
+    }
+
+    Ok(Tilemap::new(Handle::from_path(&saved.tileset), chunks))
+}
+
+fn save_haul_task(task: &HaulTask) -> SavedHaulTask {
+    SavedHaulTask {
+        id: task.id,
+        origin_position: task.origin_position.as_ref().map(vector_to_array),
+        destination_position: task.destination_position.as_ref().map(vector_to_array),
+        resource: task.resource.clone(),
+        amount: task.amount,
+    }
+}
+
+fn load_haul_task(saved: &SavedHaulTask) -> HaulTask {
+    HaulTask {
+        id: saved.id,
+        origin_position: saved.origin_position.map(array_to_vector),
+        destination_position: saved.destination_position.map(array_to_vector),
+        resource: saved.resource.clone(),
+        amount: saved.amount,
+    }
+}
+
+/// Every resource id the save refers to, in tasks, storages and backpacks
+fn saved_resources(save: &SaveFile) -> Vec<&ResourceId> {
+    let mut resources: Vec<&ResourceId> = save.open_tasks.iter().map(|task| &task.resource).collect();
+    for saved in save.entities.iter() {
+        if let Some(storage) = &saved.storage_space {
+            resources.extend(storage.item_list.keys());
+            resources.extend(storage.reserved_item_list.keys());
+        }
+        if let Some(construction) = &saved.construction_storage {
+            resources.extend(construction.required_item_list.keys());
+        }
+        if let Some(item) = saved.backpack.as_ref().and_then(|backpack| backpack.item.as_ref()) {
+            resources.push(&item.resource);
+        }
+        if let Some(task) = &saved.haul_task {
+            resources.push(&task.resource);
+        }
+    }
+
+    resources
+}
+
+fn vector_to_array(vector: &Vector2) -> [f32; 2] {
+    [vector.x, vector.y]
+}
+
+fn array_to_vector(array: [f32; 2]) -> Vector2 {
+    Vector2 { x: array[0], y: array[1] }
+}
+
+fn rect_to_array(rect: &Rectangle) -> [f32; 4] {
+    [rect.x, rect.y, rect.width, rect.height]
+}
+
+fn array_to_rect(array: [f32; 4]) -> Rectangle {
+    Rectangle {
+        x: array[0],
+        y: array[1],
+        width: array[2],
+        height: array[3],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        headless::{
+            simulate,
+            tests::{game_data, temp_path},
+        },
+        startup::insert_base_resources,
+    };
+
+    use super::*;
+
+    #[test]
+    fn saves_load_back_to_the_same_colony() {
+        let (config, actions, content) = game_data();
+        let (world, resources) = simulate(&config, &actions, &content, 600, 42, None).unwrap();
+        let first_path = temp_path("save_round_trip_first");
+        save_colony(&world, &resources, &first_path).unwrap();
+
+        let mut loaded_world = World::new();
+        let mut loaded_resources = Resources::new();
+        insert_base_resources(&mut loaded_resources, &config, &actions, &content, Scene::MainMenu, 0);
+        load_colony(&mut loaded_world, &mut loaded_resources, &first_path).unwrap();
+        let second_path = temp_path("save_round_trip_second");
+        save_colony(&loaded_world, &loaded_resources, &second_path).unwrap();
+
+        let first: serde_json::Value = serde_json::from_str(&fs::read_to_string(&first_path).unwrap()).unwrap();
+        let second: serde_json::Value = serde_json::from_str(&fs::read_to_string(&second_path).unwrap()).unwrap();
+        fs::remove_file(&first_path).unwrap();
+        fs::remove_file(&second_path).unwrap();
+
+        // Entities get new ids on load, everything else must survive as is
+        let strip_ids = |mut save: serde_json::Value| {
+            save["selected_hauler"] = serde_json::Value::Null;
+            for entity in save["entities"].as_array_mut().unwrap() {
+                entity["id"] = serde_json::Value::Null;
+            }
+            save
+        };
+        assert_eq!(first["entities"].as_array().unwrap().len(), second["entities"].as_array().unwrap().len());
+        assert!(strip_ids(first) == strip_ids(second), "the reloaded colony differs from the saved one");
+        assert_eq!(loaded_resources.get::<SceneStack>().unwrap().current(), Some(Scene::TestMap.label()));
+    }
+
+    #[test]
+    fn saves_from_other_versions_are_refused() {
+        let (config, actions, content) = game_data();
+        let (world, resources) = simulate(&config, &actions, &content, 1, 42, None).unwrap();
+        let path = temp_path("save_version");
+        save_colony(&world, &resources, &path).unwrap();
+        let mut save: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        save["version"] = serde_json::json!(SAVE_VERSION + 1);
+        fs::write(&path, save.to_string()).unwrap();
+
+        let mut loaded_world = World::new();
+        let mut loaded_resources = Resources::new();
+        insert_base_resources(&mut loaded_resources, &config, &actions, &content, Scene::MainMenu, 0);
+        let result = load_colony(&mut loaded_world, &mut loaded_resources, &path);
+        fs::remove_file(&path).unwrap();
+
+        match result {
+            Err(EngineError::Data { reason, .. }) => assert!(reason.contains("unsupported version"), "{}", reason),
+            other => panic!("expected a version error, got {:?}", other.err()),
+        }
+        assert!(loaded_world.is_empty());
+    }
+}
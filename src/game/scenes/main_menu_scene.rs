@@ -1,6 +1,129 @@
 use hecs::World;
-use raylib::text::Font;
+use raylib::prelude::*;
+
+use crate::{
+    engine::{
+        datatypes::AppExit,
+        enums::Stage,
+        error::EngineError,
+        scene::{SceneDefinition, SceneStack},
+        schedule::{Schedule, System, SystemContext, SystemEntry},
+        ui::{datatypes::Label, spawn_label},
+    },
+    game::{
+        assets::base_font,
+        config::GameConfig,
+        constants::SAVE_PATH,
+        save::queue_colony_load,
+        ui::menu::{navigate_menu, spawn_menu_entry, MenuSelection},
+    },
+};
+
+use super::Scene;
+
+// TAGS ------
+pub struct MainMenuStatusLabel;
+
+pub fn register_main_menu_systems(schedule: &mut Schedule) {
+    let scene = Scene::MainMenu.label();
+    schedule.add_scene(SceneDefinition::new(scene).on_enter(enter_main_menu));
+    schedule.add_system(
+        SystemEntry::new("navigate_menu", Stage::Input, System::Update(navigate_menu))
+            .after("check_debug_button_click")
+            .in_scene(scene)
+            .in_scene(Scene::Settings.label()),
+    );
+}
+
+pub fn enter_main_menu(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    ctx.resources.insert(MenuSelection::default());
+    let screen_width = ctx.resources.get::<GameConfig>()?.screen_width_f();
+
+    spawn_menu_title(world, screen_width, "HECS RAYLIB TEST");
+    let status = spawn_label(
+        world,
+        Vector2 {
+            x: screen_width / 2.0 - 100.0,
+            y: 520.0,
+        },
+        Vector2::zero(),
+        0,
+        base_font(),
+        10.0,
+        1.0,
+        Color::MAROON,
+    );
+    world
+        .insert_one(status, MainMenuStatusLabel)
+        .map_err(|_| EngineError::NoSuchEntity(status))?;
+
+    let origin = menu_origin(screen_width);
+    spawn_menu_entry(world, origin, 0, "New Game", Vector2 { x: 6.0, y: 0.0 }, new_game)?;
+    spawn_menu_entry(world, origin, 1, "Load Game", Vector2 { x: 0.0, y: 1.0 }, load_game)?;
+    spawn_menu_entry(world, origin, 2, "Settings", Vector2 { x: 3.0, y: 0.0 }, open_settings)?;
+    spawn_menu_entry(world, origin, 3, "Quit", Vector2 { x: 0.0, y: 0.0 }, quit_game)?;
+
+    Ok(())
+}
+
+/// Where the first entry of a full screen menu goes
+pub fn menu_origin(screen_width: f32) -> Vector2 {
+    Vector2 {
+        x: screen_width / 2.0 - 100.0,
+        y: 300.0,
+    }
+}
+
+pub fn spawn_menu_title(world: &mut World, screen_width: f32, text: &str) {
+    let title = spawn_label(
+        world,
+        Vector2 {
+            x: screen_width / 2.0 - 160.0,
+            y: 180.0,
+        },
+        Vector2::zero(),
+        0,
+        base_font(),
+        30.0,
+        1.0,
+        Color::BLACK,
+    );
+    if let Ok(mut label) = world.get_mut::<Label>(title) {
+        label.text = text.to_string();
+    }
+}
+
+pub fn new_game(_world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    ctx.resources
+        .get_mut::<SceneStack>()?
+        .switch_to(Scene::TestMap.label());
+
+    Ok(())
+}
+
+/// Loads the quicksave slot once the menu was left. When there's nothing to load the menu stays
+/// up with a message.
+pub fn load_game(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    if let Err(error) = queue_colony_load(ctx.resources, SAVE_PATH) {
+        warn!("Loading {} failed: {}", SAVE_PATH, error);
+        for (_, label) in world.query_mut::<&mut Label>().with::<MainMenuStatusLabel>() {
+            label.text = "No saved game to load".to_string();
+        }
+    }
+
+    Ok(())
+}
+
+pub fn open_settings(_world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    ctx.resources
+        .get_mut::<SceneStack>()?
+        .switch_to(Scene::Settings.label());
+
+    Ok(())
+}
+
+pub fn quit_game(_world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    ctx.resources.get_mut::<AppExit>()?.requested = true;
 
-pub fn update_main_menu_scene(_world: &mut World, _font: &Font, _delta: f32) -> Result<(), String> {
     Ok(())
 }
@@ -1,14 +1,25 @@
 pub mod main_menu_scene;
+pub mod pause_scene;
+pub mod settings_scene;
 pub mod test_map_scene;
 
-#[derive(Debug, Clone)]
-pub struct ActiveScene {
-    pub scene: Scene,
-}
-
 #[derive(Debug, Clone)]
 pub enum Scene {
     MainMenu,
+    Settings,
     TestMap,
-    TestMap2,
+    /// Pushed over the test map
+    Pause,
+}
+
+impl Scene {
+    /// Name used by the schedule to enable systems per scene
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Scene::MainMenu => "main_menu",
+            Scene::Settings => "settings",
+            Scene::TestMap => "test_map",
+            Scene::Pause => "pause",
+        }
+    }
 }
@@ -0,0 +1,66 @@
+use hecs::World;
+use raylib::prelude::*;
+
+use crate::{
+    engine::{
+        assets::Assets,
+        enums::Stage,
+        error::EngineError,
+        input::InputState,
+        scene::{SceneDefinition, SceneStack},
+        schedule::{DrawContext, Schedule, System, SystemContext, SystemEntry},
+    },
+    game::{
+        actions::{Action, GameActions},
+        assets::base_font,
+    },
+};
+
+use super::Scene;
+
+pub fn register_pause_systems(schedule: &mut Schedule) {
+    let scene = Scene::Pause.label();
+    schedule.add_scene(SceneDefinition::new(scene).on_draw(draw_pause_overlay));
+    schedule.add_system(
+        SystemEntry::new(
+            "check_pause_menu_keys",
+            Stage::Input,
+            System::Update(check_pause_menu_keys),
+        )
+        .in_scene(scene),
+    );
+}
+
+/// Pause (P by default) resumes the game, quit to menu (M) leaves it for the main menu.
+pub fn check_pause_menu_keys(_world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let input = ctx.resources.get::<InputState>()?;
+    let actions = ctx.resources.get::<GameActions>()?;
+    let mut scenes = ctx.resources.get_mut::<SceneStack>()?;
+    if actions.is_released(&input, Action::Pause) {
+        scenes.pop();
+    } else if actions.is_released(&input, Action::QuitToMenu) {
+        scenes.switch_to(Scene::MainMenu.label());
+    }
+
+    Ok(())
+}
+
+pub fn draw_pause_overlay(_world: &mut World, draw_handle: &mut RaylibDrawHandle, ctx: &DrawContext) -> Result<(), EngineError> {
+    let assets = ctx.resources.get::<Assets>()?;
+    let (screen_width, screen_height) = (draw_handle.get_screen_width(), draw_handle.get_screen_height());
+
+    draw_handle.draw_rectangle(0, 0, screen_width, screen_height, Color::BLACK.fade(0.4));
+    draw_handle.draw_text_ex(
+        assets.get(base_font())?,
+        "PAUSED - P to resume, M for main menu",
+        Vector2 {
+            x: screen_width as f32 / 2.0 - 180.0,
+            y: screen_height as f32 / 2.0,
+        },
+        10.0,
+        1.0,
+        Color::WHITE,
+    );
+
+    Ok(())
+}
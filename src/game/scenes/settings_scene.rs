@@ -0,0 +1,60 @@
+use hecs::World;
+use raylib::prelude::*;
+
+use crate::{
+    engine::{
+        error::EngineError,
+        scene::{SceneDefinition, SceneStack},
+        schedule::{Schedule, SystemContext},
+    },
+    game::{
+        config::GameConfig,
+        settings::Settings,
+        ui::menu::{set_menu_entry_text, spawn_menu_entry, MenuSelection},
+    },
+};
+
+use super::{
+    main_menu_scene::{menu_origin, spawn_menu_title},
+    Scene,
+};
+
+pub fn register_settings_systems(schedule: &mut Schedule) {
+    schedule.add_scene(SceneDefinition::new(Scene::Settings.label()).on_enter(enter_settings));
+}
+
+pub fn enter_settings(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    ctx.resources.insert(MenuSelection::default());
+    let show_fps = ctx.resources.get::<Settings>()?.show_fps;
+    let screen_width = ctx.resources.get::<GameConfig>()?.screen_width_f();
+
+    spawn_menu_title(world, screen_width, "SETTINGS");
+
+    let origin = menu_origin(screen_width);
+    spawn_menu_entry(world, origin, 0, &show_fps_text(show_fps), Vector2 { x: 3.0, y: 0.0 }, toggle_show_fps)?;
+    spawn_menu_entry(world, origin, 1, "Back", Vector2 { x: 0.0, y: 0.0 }, back_to_main_menu)?;
+
+    Ok(())
+}
+
+pub fn toggle_show_fps(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let show_fps = {
+        let mut settings = ctx.resources.get_mut::<Settings>()?;
+        settings.show_fps = !settings.show_fps;
+        settings.show_fps
+    };
+
+    set_menu_entry_text(world, 0, &show_fps_text(show_fps))
+}
+
+pub fn back_to_main_menu(_world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    ctx.resources
+        .get_mut::<SceneStack>()?
+        .switch_to(Scene::MainMenu.label());
+
+    Ok(())
+}
+
+fn show_fps_text(show_fps: bool) -> String {
+    format!("Show FPS: {}", if show_fps { "On" } else { "Off" })
+}
@@ -1,42 +1,190 @@
 use hecs::World;
-use raylib::{text::Font, RaylibHandle, RaylibThread};
 
 use crate::{
-    engine::collision::{detect_body_collisions, detect_trigger_collisions},
+    engine::{
+        enums::Stage,
+        error::EngineError,
+        input::InputState,
+        resources::Resources,
+        scene::{SceneDefinition, SceneStack},
+        schedule::{Schedule, System, SystemContext, SystemEntry},
+    },
     game::{
-        buildings::step::update_buildings,
+        actions::{Action, GameActions},
+        animations::{update_building_animations, update_villager_animations},
+        assets::tileset,
+        buildings::{
+            definitions::BuildingDefinitions,
+            step::{
+                advance_construction, check_construction_collided_with_entity, check_construction_resources,
+                check_storage_collided_with_entity, generate_construction_haul_tasks,
+            },
+        },
+        config::GameConfig,
         startup::{spawn_buildings, spawn_ui, spawn_villagers},
-        tasks::{update_tasks, OpenTasks},
-        tilemap::generate_tilemap,
-        ui::step::update_ui,
-        villagers::step::update_villagers,
+        tasks::{find_idle_hauler_for_task, find_storage_source_for_haul_task, OpenTasks},
+        tilemap::{generate_tilemap, Tilemap},
+        ui::{
+            datatypes::{GlobalStorage, SelectedHauler},
+            step::{update_active_haul_task_count, update_global_storage, update_idle_haul_task_count},
+        },
+        villagers::{
+            hauler::{update_carrying_state, update_loading_state},
+            step::{update_collision_box_position, update_idle_state},
+        },
     },
 };
 
-pub fn update_test_map_scene(world: &mut World, font: &Font, delta: f32) -> Result<(), String> {
-    update_tasks(world)?;
-    update_villagers(world, delta);
-    update_buildings(world)?;
-    detect_body_collisions(world);
-    detect_trigger_collisions(world);
+use super::Scene;
+
+pub fn register_test_map_systems(schedule: &mut Schedule) {
+    let scene = Scene::TestMap.label();
+    schedule.add_scene(
+        SceneDefinition::new(scene)
+            .on_enter(enter_test_map)
+            .on_exit(exit_test_map),
+    );
+
+    let systems = [
+        SystemEntry::new(
+            "check_pause_key",
+            Stage::Input,
+            System::Update(check_pause_key),
+        ),
+        SystemEntry::new(
+            "find_idle_hauler_for_task",
+            Stage::PreUpdate,
+            System::Update(find_idle_hauler_for_task),
+        ),
+        SystemEntry::new(
+            "find_storage_source_for_haul_task",
+            Stage::PreUpdate,
+            System::Update(find_storage_source_for_haul_task),
+        )
+        .after("find_idle_hauler_for_task"),
+        SystemEntry::new(
+            "update_idle_state",
+            Stage::Update,
+            System::Update(update_idle_state),
+        ),
+        SystemEntry::new(
+            "update_loading_state",
+            Stage::Update,
+            System::Update(update_loading_state),
+        ),
+        SystemEntry::new(
+            "update_carrying_state",
+            Stage::Update,
+            System::Update(update_carrying_state),
+        ),
+        SystemEntry::new(
+            "generate_construction_haul_tasks",
+            Stage::Update,
+            System::Update(generate_construction_haul_tasks),
+        )
+        .after("update_carrying_state"),
+        SystemEntry::new(
+            "check_storage_collided_with_entity",
+            Stage::Update,
+            System::Update(check_storage_collided_with_entity),
+        )
+        .after("generate_construction_haul_tasks"),
+        SystemEntry::new(
+            "check_construction_collided_with_entity",
+            Stage::Update,
+            System::Update(check_construction_collided_with_entity),
+        )
+        .after("check_storage_collided_with_entity"),
+        SystemEntry::new(
+            "check_construction_resources",
+            Stage::Update,
+            System::Update(check_construction_resources),
+        )
+        .after("check_construction_collided_with_entity"),
+        SystemEntry::new(
+            "advance_construction",
+            Stage::Update,
+            System::Update(advance_construction),
+        )
+        .after("check_construction_resources"),
+        SystemEntry::new(
+            "update_collision_box_position",
+            Stage::PostUpdate,
+            System::Update(update_collision_box_position),
+        )
+        .before("update_collision_grid"),
+        SystemEntry::new(
+            "update_villager_animations",
+            Stage::PostUpdate,
+            System::Update(update_villager_animations),
+        )
+        .before("advance_animations"),
+        SystemEntry::new(
+            "update_building_animations",
+            Stage::PostUpdate,
+            System::Update(update_building_animations),
+        )
+        .before("advance_animations"),
+        SystemEntry::new(
+            "update_active_haul_task_count",
+            Stage::UI,
+            System::Update(update_active_haul_task_count),
+        ),
+        SystemEntry::new(
+            "update_idle_haul_task_count",
+            Stage::UI,
+            System::Update(update_idle_haul_task_count),
+        ),
+        SystemEntry::new(
+            "update_global_storage",
+            Stage::UI,
+            System::Update(update_global_storage),
+        ),
+    ];
+
+    for entry in systems {
+        schedule.add_system(entry.in_scene(scene));
+    }
+}
+
+pub fn enter_test_map(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    setup_test_map(world, ctx.resources)
+}
+
+/// Drops the map resources. The scene's entities are despawned by the schedule.
+pub fn exit_test_map(_world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    ctx.resources.remove::<Tilemap>();
+    ctx.resources.remove::<OpenTasks>();
+    ctx.resources.remove::<GlobalStorage>();
+    ctx.resources.remove::<SelectedHauler>();
+
+    Ok(())
+}
 
-    update_ui(world, font)?;
+/// Pause (P by default) pauses the game.
+pub fn check_pause_key(_world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let released = {
+        let input = ctx.resources.get::<InputState>()?;
+        ctx.resources.get::<GameActions>()?.is_released(&input, Action::Pause)
+    };
+    if released {
+        ctx.resources.get_mut::<SceneStack>()?.push(Scene::Pause.label());
+    }
 
     Ok(())
 }
 
-pub fn setup_test_map(
-    world: &mut World,
-    _raylib_handle: &mut RaylibHandle,
-    _thread: &RaylibThread,
-) -> Result<(), String> {
-    generate_tilemap(world, 1024, 1024);
+pub fn setup_test_map(world: &mut World, resources: &mut Resources) -> Result<(), EngineError> {
+    generate_tilemap(resources, tileset(), 1024, 1024)?;
 
-    world.spawn((OpenTasks::default(),));
+    resources.insert(OpenTasks::default());
+    resources.insert(GlobalStorage::default());
 
-    spawn_buildings(world);
-    spawn_villagers(world);
-    spawn_ui(world)?;
+    let config = resources.get::<GameConfig>()?.clone();
+    spawn_buildings(world, &config, &*resources.get::<BuildingDefinitions>()?)?;
+    let hauler = spawn_villagers(world, &config);
+    resources.insert(SelectedHauler { m_hauler: Some(hauler) });
+    spawn_ui(world, &config)?;
 
     Ok(())
 }
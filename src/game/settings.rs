@@ -0,0 +1,6 @@
+// STRUCTS ------
+/// Player settings, stored as a resource
+#[derive(Default, Debug, Clone)]
+pub struct Settings {
+    pub show_fps: bool,
+}
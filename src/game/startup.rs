@@ -1,76 +1,119 @@
 use std::collections::HashMap;
 
-use hecs::World;
-use raylib::{prelude::*, RaylibHandle, RaylibThread};
+use hecs::{Entity, World};
+use raylib::prelude::*;
 
 use crate::{
     engine::{
-        datatypes::Sprite,
-        enums::{CollisionType, GameResource},
-        ui::{spawn_button, spawn_label, spawn_toggle_button},
+        datatypes::{AppExit, GameRng, IdCounter, Sprite},
+        enums::CollisionType,
+        error::EngineError,
+        events::Events,
+        input::InputState,
+        resources::Resources,
+        scene::SceneStack,
+        spatial_hash::CollisionGrid,
+        timestep::SimulationTick,
+        ui::{datatypes::UiScale, spawn_button, spawn_label, spawn_toggle_button},
     },
     game::{
-        buildings::{house::spawn_finished_house, warehouse::spawn_finished_warehouse},
+        actions::GameActions,
+        buildings::{
+            datatypes::StorageSpace,
+            definitions::BuildingDefinitions,
+            spawn::{spawn_building, spawn_building_placement},
+        },
+        camera::CameraController,
+        config::GameConfig,
         constants::*,
+        content::GameContent,
+        events::GameEvent,
         input::{toggle_debug_text, toggle_draw_collisions},
-        scenes::{ActiveScene, Scene},
+        resource_registry::ResourceId,
+        scenes::Scene,
+        settings::Settings,
         villagers::hauler::spawn_hauler,
     },
 };
 
 use super::{
-    buildings::house::spawn_house_placement,
+    assets::{base_font, tileset, ui_atlas},
     constants::TILE_SIZE,
-    scenes::test_map_scene::setup_test_map,
-    ui::datatypes::{ActiveTaskCountLabel, GlobalStorageLabel, IdleTaskCountLabel, SelectedHauler},
+    ui::datatypes::{ActiveTaskCountLabel, GlobalStorageLabel, IdleTaskCountLabel, StorageIcons},
 };
 
-pub fn game_setup(
-    world: &mut World,
-    raylib_handle: &mut RaylibHandle,
-    thread: &RaylibThread,
-) -> Result<(), String> {
-    let m_global_storage = world.query_mut::<&ActiveScene>().into_iter().nth(0);
-    Ok(if let Some((_, active_scene)) = m_global_storage {
-        return match active_scene.scene {
-            Scene::TestMap => setup_test_map(world, raylib_handle, thread),
-            Scene::MainMenu => Ok(()),
-            Scene::TestMap2 => Ok(()),
-        };
-    })
+/// Inserts the resources every run starts with, windowed, headless or replayed. `scene` is
+/// entered on the first scene transition.
+pub fn insert_base_resources(
+    resources: &mut Resources,
+    config: &GameConfig,
+    actions: &GameActions,
+    content: &GameContent,
+    scene: Scene,
+    seed: u64,
+) {
+    resources.insert(SceneStack::starting_with(scene.label()));
+    resources.insert(GameRng::from_seed(seed));
+    resources.insert(IdCounter::default());
+    resources.insert(Events::<GameEvent>::new());
+    resources.insert(SimulationTick::default());
+    resources.insert(InputState::default());
+    resources.insert(CollisionGrid::new(COLLISION_CELL_SIZE));
+    resources.insert(UiScale(config.ui_scale));
+    resources.insert(CameraController::new(config));
+    resources.insert(Settings::default());
+    resources.insert(AppExit::default());
+    resources.insert(config.clone());
+    resources.insert(actions.clone());
+    resources.insert(content.resources.clone());
+    resources.insert(content.buildings.clone());
 }
 
-pub fn spawn_villagers(world: &mut World) {
-    let selected_hauler = spawn_hauler(
+pub fn new_camera(config: &GameConfig) -> Camera2D {
+    Camera2D {
+        offset: Vector2::zero(),
+        target: Vector2::zero(),
+        rotation: 0.0,
+        zoom: config.camera_zoom,
+    }
+}
+
+/// Spawns the starting haulers and returns the one selected at first
+pub fn spawn_villagers(world: &mut World, config: &GameConfig) -> Entity {
+    spawn_hauler(
         world,
         Vector2 { x: 48.0, y: 48.0 },
         Vector2 { x: 6.0, y: 12.0 },
         CollisionType::Trigger,
-        None,
-    );
-    world.spawn((SelectedHauler {
-        hauler: selected_hauler,
-    },));
+        Some(config.default_idle_point()),
+    )
 }
 
-pub fn spawn_buildings(world: &mut World) {
-    let sprite = Sprite::new(DEFAULT_IDLE_POINT, DEFAULT_IDLE_POINT_ATLAS_TILE, TILE_SIZE);
+pub fn spawn_buildings(
+    world: &mut World,
+    config: &GameConfig,
+    buildings: &BuildingDefinitions,
+) -> Result<(), EngineError> {
+    let sprite = Sprite::new(tileset(), config.default_idle_point(), DEFAULT_IDLE_POINT_ATLAS_TILE, TILE_SIZE);
     world.spawn((sprite,));
 
-    spawn_finished_warehouse(world, Vector2 { x: 304.0, y: 256.0 }, HashMap::new());
+    spawn_building(world, buildings, "warehouse", Vector2 { x: 304.0, y: 256.0 }, true)?;
 
-    spawn_finished_house(world, Vector2 { x: 16.0, y: 192.0 });
+    spawn_building(world, buildings, "house", Vector2 { x: 16.0, y: 192.0 }, false)?;
+
+    Ok(())
 }
 
-pub fn spawn_ui(world: &mut World) -> Result<(), String> {
+pub fn spawn_ui(world: &mut World, config: &GameConfig) -> Result<(), EngineError> {
     spawn_toggle_button(
         world,
         Vector2 {
             x: 10.0,
-            y: (SCREEN_HEIGHT as f32) - 10.0,
+            y: config.screen_height_f() - 10.0,
         },
         Vector2::zero(),
         0,
+        ui_atlas(),
         Vector2 { x: 0.0, y: 0.0 },
         TILE_SIZE,
         Some(toggle_draw_collisions),
@@ -81,10 +124,11 @@ pub fn spawn_ui(world: &mut World) -> Result<(), String> {
         world,
         Vector2 {
             x: 50.0,
-            y: (SCREEN_HEIGHT as f32) - 10.0,
+            y: config.screen_height_f() - 10.0,
         },
         Vector2::zero(),
         0,
+        ui_atlas(),
         Vector2 { x: 3.0, y: 0.0 },
         TILE_SIZE,
         Some(toggle_debug_text),
@@ -95,14 +139,16 @@ pub fn spawn_ui(world: &mut World) -> Result<(), String> {
         world,
         Vector2 {
             x: 90.0,
-            y: (SCREEN_HEIGHT as f32) - 10.0,
+            y: config.screen_height_f() - 10.0,
         },
         Vector2::zero(),
         0,
+        ui_atlas(),
         Vector2 { x: 3.0, y: 1.0 },
         TILE_SIZE,
-        Some(|world| -> Result<(), String> {
-            spawn_house_placement(world, Vector2 { x: 0.0, y: 0.0 });
+        Some(|world, ctx| -> Result<(), EngineError> {
+            let buildings = ctx.resources.get::<BuildingDefinitions>()?;
+            spawn_building_placement(world, &buildings, "house", Vector2 { x: 0.0, y: 0.0 })?;
             Ok(())
         }),
         None,
@@ -112,15 +158,20 @@ pub fn spawn_ui(world: &mut World) -> Result<(), String> {
         world,
         Vector2 {
             x: 130.0,
-            y: (SCREEN_HEIGHT as f32) - 10.0,
+            y: config.screen_height_f() - 10.0,
         },
         Vector2::zero(),
         0,
+        ui_atlas(),
         Vector2 { x: 0.0, y: 1.0 },
         TILE_SIZE,
-        Some(|world| -> Result<(), String> {
-            let storage: HashMap<GameResource, i32> = HashMap::from([(GameResource::Wood, 40)]);
-            spawn_finished_warehouse(world, Vector2 { x: 304.0, y: 48.0 }, storage);
+        Some(|world, ctx| -> Result<(), EngineError> {
+            let buildings = ctx.resources.get::<BuildingDefinitions>()?;
+            let warehouse = spawn_building(world, &buildings, "warehouse", Vector2 { x: 304.0, y: 48.0 }, true)?;
+            world
+                .get_mut::<StorageSpace>(warehouse)
+                .map_err(EngineError::component::<StorageSpace>(warehouse))?
+                .item_list = HashMap::from([(ResourceId::new("wood"), 40)]);
             Ok(())
         }),
         None,
@@ -129,47 +180,50 @@ pub fn spawn_ui(world: &mut World) -> Result<(), String> {
     let label = spawn_label(
         world,
         Vector2 {
-            x: SCREEN_WIDTH_F - 15.0,
+            x: config.screen_width_f() - 15.0,
             y: 12.0,
         },
         Vector2::zero(),
         0,
+        base_font(),
         10.0,
         1.0,
         Color::BLACK,
     );
     world
         .insert(label, (IdleTaskCountLabel,))
-        .map_err(|_| "No such entity")?;
+        .map_err(|_| EngineError::NoSuchEntity(label))?;
     let label = spawn_label(
         world,
         Vector2 {
-            x: SCREEN_WIDTH_F - 15.0,
+            x: config.screen_width_f() - 15.0,
             y: 32.0,
         },
         Vector2::zero(),
         0,
+        base_font(),
         10.0,
         1.0,
         Color::BLACK,
     );
     world
         .insert(label, (ActiveTaskCountLabel,))
-        .map_err(|_| "No such entity")?;
+        .map_err(|_| EngineError::NoSuchEntity(label))?;
     let label = spawn_label(
         world,
         Vector2 {
-            x: SCREEN_WIDTH_F / 2.0,
+            x: config.screen_width_f() / 2.0,
             y: 12.0,
         },
         Vector2::zero(),
         0,
+        base_font(),
         10.0,
         1.0,
         Color::BLACK,
     );
     world
-        .insert(label, (GlobalStorageLabel,))
-        .map_err(|_| "No such entity")?;
+        .insert(label, (GlobalStorageLabel, StorageIcons::default()))
+        .map_err(|_| EngineError::NoSuchEntity(label))?;
     Ok(())
 }
@@ -1,24 +1,80 @@
 use hecs::World;
-use raylib::{text::Font, RaylibHandle};
 
-use super::scenes::{
-    main_menu_scene::update_main_menu_scene, test_map_scene::update_test_map_scene, ActiveScene,
-    Scene,
+use crate::engine::{
+    enums::Stage,
+    error::EngineError,
+    events::Events,
+    resources::Resources,
+    schedule::{Schedule, System, SystemContext, SystemEntry},
+    startup::register_engine_systems,
+    timestep::SimulationTick,
 };
 
+use super::{
+    content::reload_game_data,
+    draw::register_draw_systems,
+    events::{log_game_events, GameEvent},
+    input::register_input_systems,
+    scenes::{
+        main_menu_scene::register_main_menu_systems, pause_scene::register_pause_systems,
+        settings_scene::register_settings_systems,
+        test_map_scene::register_test_map_systems,
+    },
+};
+
+/// Stages that advance the simulation. They run once per fixed tick.
+pub const SIMULATION_STAGES: [Stage; 3] = [Stage::PreUpdate, Stage::Update, Stage::PostUpdate];
+
+/// Schedule with every game and engine system. Windowed, headless and replayed runs all build
+/// it here, so systems without ordering constraints between them run in the same order.
+pub fn build_schedule() -> Schedule {
+    let mut schedule = Schedule::new();
+    register_game_systems(&mut schedule);
+    register_engine_systems(&mut schedule);
+
+    schedule
+}
+
+pub fn register_game_systems(schedule: &mut Schedule) {
+    schedule.add_system(SystemEntry::new(
+        "reload_game_data",
+        Stage::Input,
+        System::Update(reload_game_data),
+    ));
+    schedule.add_system(SystemEntry::new(
+        "log_game_events",
+        Stage::UI,
+        System::Update(log_game_events),
+    ));
+
+    register_input_systems(schedule);
+    register_draw_systems(schedule);
+    register_main_menu_systems(schedule);
+    register_pause_systems(schedule);
+    register_settings_systems(schedule);
+    register_test_map_systems(schedule);
+}
+
+/// Ages the event channels. Runs once per frame, after every stage.
+pub fn update_events(resources: &Resources) -> Result<(), EngineError> {
+    resources.get_mut::<Events<GameEvent>>()?.update();
+
+    Ok(())
+}
+
 pub fn update_game(
     world: &mut World,
-    raylib_handle: &mut RaylibHandle,
-    font: &Font,
-) -> Result<(), String> {
-    let delta = raylib_handle.get_frame_time();
-
-    let m_global_storage = world.query_mut::<&ActiveScene>().into_iter().nth(0);
-    Ok(if let Some((_, active_scene)) = m_global_storage {
-        return match active_scene.scene {
-            Scene::TestMap => update_test_map_scene(world, font, delta),
-            Scene::MainMenu => update_main_menu_scene(world, font, delta),
-            Scene::TestMap2 => todo!(),
-        };
-    })
+    resources: &mut Resources,
+    schedule: &mut Schedule,
+    delta: f32,
+) -> Result<(), EngineError> {
+    schedule.apply_scene_transitions(world, resources)?;
+    schedule.run_stages(&SIMULATION_STAGES, world, &mut SystemContext::new(delta, resources))?;
+    resources.get_mut::<SimulationTick>()?.0 += 1;
+
+    Ok(())
+}
+
+pub fn current_tick(resources: &Resources) -> Result<u64, EngineError> {
+    Ok(resources.get::<SimulationTick>()?.0)
 }
@@ -5,19 +5,28 @@ use raylib::prelude::*;
 
 use crate::{
     engine::{
-        datatypes::Sprite,
-        enums::{GameResource, VillagerState},
-        utils::get_id,
+        collision::CollisionMask,
+        datatypes::{IdCounter, Sprite},
+        enums::{CollisionLayer, VillagerState},
+        error::EngineError,
+        events::Events,
+        schedule::SystemContext,
+        spatial_hash::CollisionGrid,
+        spatial_query::entities_in_radius,
+    },
+    game::{
+        constants::STORAGE_SEARCH_RADIUS,
+        events::GameEvent,
+        resource_registry::{ResourceId, ResourceRegistry},
+        villagers::datatypes::{Hauler, IdleState},
     },
-    game::villagers::datatypes::{Hauler, IdleState},
 };
 
 use super::{
     buildings::{
-        datatypes::{ConstructionStorage, StorageSpace},
+        datatypes::{ConstructionStorage, Footprint, StorageSpace},
         step::storage_has_required_resource,
     },
-    constants::HAULER_CAPACITY,
 };
 
 // STRUCTS ------
@@ -31,49 +40,57 @@ pub struct HaulTask {
     pub id: usize,
     pub origin_position: Option<Vector2>,
     pub destination_position: Option<Vector2>,
-    pub resource: GameResource,
+    pub resource: ResourceId,
+    /// Items moved, at most one stack of the resource
+    pub amount: i32,
 }
 
 // FUNCTION ------
-pub fn update_tasks(world: &mut World) -> Result<(), String> {
-    find_idle_hauler_for_task(world)?;
-    find_storage_source_for_haul_task(world);
+pub fn give_haul_task_to_idle(
+    open_tasks: &mut OpenTasks,
+    ids: &mut IdCounter,
+    events: &mut Events<GameEvent>,
+    resources: &ResourceRegistry,
+) -> Result<(), EngineError> {
+    let resource = ResourceId::new("wood");
+    let amount = resources.stack_size(&resource)?;
+    generate_haul_task(
+        open_tasks,
+        ids,
+        events,
+        Some(Vector2 { x: 304.0, y: 48.0 }),
+        Vector2 { x: 304.0, y: 248.0 },
+        resource,
+        amount,
+    );
 
     Ok(())
 }
 
-pub fn give_haul_task_to_idle(world: &mut World) {
-    let query = world.query_mut::<&mut OpenTasks>();
-    query.into_iter().for_each(|(_, open_tasks)| {
-        let haul_task = HaulTask {
-            id: get_id(),
-            origin_position: Some(Vector2 { x: 304.0, y: 48.0 }),
-            destination_position: Some(Vector2 { x: 304.0, y: 248.0 }),
-            resource: GameResource::Wood,
-        };
-        open_tasks.haul_list.push(haul_task);
-    });
-}
-
 pub fn generate_haul_task(
-    world: &mut World,
+    open_tasks: &mut OpenTasks,
+    ids: &mut IdCounter,
+    events: &mut Events<GameEvent>,
     origin_position: Option<Vector2>,
     destination_position: Vector2,
-    resource: GameResource,
+    resource: ResourceId,
+    amount: i32,
 ) {
-    let query = world.query_mut::<&mut OpenTasks>();
-    query.into_iter().for_each(|(_, open_tasks)| {
-        let haul_task = HaulTask {
-            id: get_id(),
-            origin_position,
-            destination_position: Some(destination_position),
-            resource,
-        };
-        open_tasks.haul_list.push(haul_task);
+    let haul_task = HaulTask {
+        id: ids.next_id(),
+        origin_position,
+        destination_position: Some(destination_position),
+        resource,
+        amount,
+    };
+    events.send(GameEvent::TaskCreated {
+        task_id: haul_task.id,
+        resource: haul_task.resource.clone(),
     });
+    open_tasks.haul_list.push(haul_task);
 }
 
-pub fn find_idle_hauler_for_task(world: &mut World) -> Result<(), String> {
+pub fn find_idle_hauler_for_task(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
     let mut m_selected_hauler: Option<Entity> = None;
     let mut m_haul_task: Option<HaulTask> = None;
 
@@ -91,21 +108,22 @@ pub fn find_idle_hauler_for_task(world: &mut World) -> Result<(), String> {
 
     if let Some(hauler) = m_selected_hauler {
         {
-            let query = world.query_mut::<&mut OpenTasks>();
-            query.into_iter().for_each(|(_, open_tasks)| {
-                if m_haul_task.is_none() {
-                    let last = open_tasks.haul_list.last();
-                    if last.is_some() && last.unwrap().origin_position.is_some() {
-                        m_haul_task = open_tasks.haul_list.pop();
-                    }
-                };
-            });
+            let mut open_tasks = ctx.resources.get_mut::<OpenTasks>()?;
+            let last = open_tasks.haul_list.last();
+            if last.is_some() && last.unwrap().origin_position.is_some() {
+                m_haul_task = open_tasks.haul_list.pop();
+            }
         }
 
         if let Some(haul_task) = m_haul_task {
             if haul_task.origin_position.is_some() && haul_task.destination_position.is_some() {
-                world.insert(hauler, (haul_task,)).map_err(|_| "No such entity")?;
-                IdleState::change_state_to(world, hauler, VillagerState::Loading);
+                let mut events = ctx.resources.get_mut::<Events<GameEvent>>()?;
+                events.send(GameEvent::TaskAssigned {
+                    task_id: haul_task.id,
+                    hauler,
+                });
+                world.insert(hauler, (haul_task,)).map_err(|_| EngineError::NoSuchEntity(hauler))?;
+                IdleState::change_state_to(world, &mut events, hauler, VillagerState::Loading);
             }
         }
     }
@@ -113,44 +131,101 @@ pub fn find_idle_hauler_for_task(world: &mut World) -> Result<(), String> {
     Ok(())
 }
 
-pub fn find_storage_source_for_haul_task(world: &mut World) {
+/// Entrance of the storage nearest to `position` that holds `amount` of `resource`. Only
+/// storages within `STORAGE_SEARCH_RADIUS` and already in the `CollisionGrid` are found.
+pub fn nearest_storage_with_resource(
+    world: &World,
+    grid: &CollisionGrid,
+    position: Vector2,
+    resource: &ResourceId,
+    amount: i32,
+) -> Option<Vector2> {
+    let buildings = CollisionMask::NONE.with(CollisionLayer::Building);
+    entities_in_radius(world, grid, position, STORAGE_SEARCH_RADIUS, buildings)
+        .into_iter()
+        .filter(|building| world.get::<ConstructionStorage>(*building).is_err())
+        .find_map(|building| {
+            let storage = world.get::<StorageSpace>(building).ok()?;
+            if !storage_has_required_resource(&storage, resource, amount) {
+                return None;
+            }
+            let sprite = world.get::<Sprite>(building).ok()?;
+            let footprint = world.get::<Footprint>(building).ok()?;
+            Some(footprint.entrance_position(sprite.position))
+        })
+}
+
+pub fn find_storage_source_for_haul_task(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
     let mut sourceless_tasks: HashMap<usize, HaulTask> = HashMap::new();
 
     {
-        let mut query = world.query::<&OpenTasks>();
-        query.into_iter().for_each(|(_, open_tasks)| {
-            open_tasks.haul_list.clone().into_iter().for_each(|task| {
-                if task.origin_position.is_none() {
-                    sourceless_tasks.insert(task.id.clone(), task.clone());
-                }
-            });
+        let open_tasks = ctx.resources.get::<OpenTasks>()?;
+        open_tasks.haul_list.clone().into_iter().for_each(|task| {
+            if task.origin_position.is_none() {
+                sourceless_tasks.insert(task.id, task.clone());
+            }
         });
     }
 
     {
-        sourceless_tasks.iter_mut().for_each(|(_, task)| {
-            let mut origin_query = world
-                .query::<(&StorageSpace, &Sprite)>()
-                .without::<ConstructionStorage>();
-            origin_query.into_iter().for_each(|(_, (storage, sprite))| {
-                if storage_has_required_resource(&storage, task.resource, HAULER_CAPACITY) {
-                    task.origin_position = Some(sprite.position);
-                }
-            });
-        })
+        let grid = ctx.resources.get::<CollisionGrid>()?;
+        for (_, task) in sourceless_tasks.iter_mut() {
+            if let Some(destination) = task.destination_position {
+                task.origin_position =
+                    nearest_storage_with_resource(world, &grid, destination, &task.resource, task.amount);
+            }
+        }
     }
 
     {
-        let query = world.query_mut::<&mut OpenTasks>();
-        query.into_iter().for_each(|(_, open_tasks)| {
-            open_tasks.haul_list.iter_mut().for_each(|mut task| {
-                if sourceless_tasks.contains_key(&task.id)
-                    && sourceless_tasks[&task.id].origin_position.is_some()
-                    && task.origin_position.is_none()
-                {
-                    task.origin_position = sourceless_tasks[&task.id].origin_position;
-                }
-            });
+        let mut open_tasks = ctx.resources.get_mut::<OpenTasks>()?;
+        open_tasks.haul_list.iter_mut().for_each(|task| {
+            if sourceless_tasks.contains_key(&task.id)
+                && sourceless_tasks[&task.id].origin_position.is_some()
+                && task.origin_position.is_none()
+            {
+                task.origin_position = sourceless_tasks[&task.id].origin_position;
+            }
         });
     }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        engine::{resources::Resources, spatial_hash::update_collision_grid},
+        game::{buildings::spawn::spawn_building, headless::tests::game_data},
+    };
+
+    use super::*;
+
+    #[test]
+    fn haul_tasks_take_from_the_nearest_storage_with_the_resource() {
+        let (_, _, content) = game_data();
+        let mut world = World::new();
+        let mut storages: Vec<Entity> = vec![];
+        for x in [0.0, 160.0, 320.0] {
+            let position = Vector2 { x, y: 0.0 };
+            storages.push(spawn_building(&mut world, &content.buildings, "warehouse", position, true).unwrap());
+        }
+        for (storage, wood) in storages.iter().zip([10, 10, 0]) {
+            world.get_mut::<StorageSpace>(*storage).unwrap().item_list =
+                HashMap::from([(ResourceId::new("wood"), wood)]);
+        }
+        let mut resources = Resources::new();
+        resources.insert(CollisionGrid::new(32.0));
+        update_collision_grid(&mut world, &mut SystemContext::new(0.0, &mut resources)).unwrap();
+        let grid = resources.get::<CollisionGrid>().unwrap();
+
+        // The empty warehouse is the closest one but can't fill the task
+        let destination = Vector2 { x: 320.0, y: 64.0 };
+        let origin = nearest_storage_with_resource(&world, &grid, destination, &ResourceId::new("wood"), 10);
+        let sprite = world.get::<Sprite>(storages[1]).unwrap();
+        let footprint = world.get::<Footprint>(storages[1]).unwrap();
+        assert_eq!(origin, Some(footprint.entrance_position(sprite.position)));
+
+        assert_eq!(nearest_storage_with_resource(&world, &grid, destination, &ResourceId::new("wood"), 20), None);
+    }
 }
@@ -3,7 +3,10 @@ use rand::Rng;
 use raylib::prelude::*;
 use raylib::texture::Texture2D;
 
-use crate::engine::{TILESET, collision::are_boxes_colliding};
+use crate::engine::{
+    assets::Handle, collision::are_boxes_colliding, datatypes::GameRng, enums::{RenderLayer, Rotation},
+    error::EngineError, render::{DrawCommand, RenderQueue}, resources::Resources, schedule::DrawContext,
+};
 
 use super::constants::{TILE_SIZE, CHUNK_TILE_SIZE, CHUNK_RAW_SIZE};
 
@@ -42,18 +45,41 @@ pub struct Chunk {
 }
 
 pub struct Tilemap {
+    pub tileset: Handle<Texture2D>,
     pub chunks: Vec<Chunk>,
 }
 
 impl Tilemap {
-    pub fn new(chunks: Vec<Chunk>) -> Tilemap {
-        Tilemap { chunks }
+    pub fn new(tileset: Handle<Texture2D>, chunks: Vec<Chunk>) -> Tilemap {
+        Tilemap { tileset, chunks }
+    }
+
+    /// Area covered by the chunks, in world pixels
+    pub fn bounds(&self) -> Rectangle {
+        if self.chunks.is_empty() {
+            return Rectangle::default();
+        }
+
+        let (mut min_x, mut min_y) = (f32::MAX, f32::MAX);
+        let (mut max_x, mut max_y) = (f32::MIN, f32::MIN);
+        self.chunks.iter().for_each(|chunk| {
+            min_x = min_x.min(chunk.rect.x * TILE_SIZE);
+            min_y = min_y.min(chunk.rect.y * TILE_SIZE);
+            max_x = max_x.max(chunk.rect.x * TILE_SIZE + CHUNK_RAW_SIZE as f32);
+            max_y = max_y.max(chunk.rect.y * TILE_SIZE + CHUNK_RAW_SIZE as f32);
+        });
+
+        Rectangle {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x,
+            height: max_y - min_y,
+        }
     }
 }
 
 // FUNCTIONS ------
-pub fn generate_chunk(origin_x: i32, origin_y: i32) -> Chunk {
-    let mut rng = rand::thread_rng();
+pub fn generate_chunk(origin_x: i32, origin_y: i32, rng: &mut impl Rng) -> Chunk {
     let mut chunk = Chunk::default();
 
     let world_start_x: i32 = origin_x * CHUNK_TILE_SIZE;
@@ -86,69 +112,81 @@ pub fn generate_chunk(origin_x: i32, origin_y: i32) -> Chunk {
     return chunk;
 }
 
-pub fn generate_tilemap(world: &mut World, width: i32, height: i32) {
-    let mut tilemap = Tilemap::new(vec![]);
+pub fn generate_tilemap(
+    resources: &mut Resources,
+    tileset: Handle<Texture2D>,
+    width: i32,
+    height: i32,
+) -> Result<(), EngineError> {
+    let mut tilemap = Tilemap::new(tileset, vec![]);
 
     let chunk_x: i32 = width / CHUNK_TILE_SIZE;
     let chunk_y: i32 = height / CHUNK_TILE_SIZE;
 
-    for y in 0..chunk_y {
-        for x in 0..chunk_x {
-            let chunk = generate_chunk(x, y);
-            tilemap.chunks.push(chunk);
+    {
+        let mut game_rng = resources.get_mut::<GameRng>()?;
+        let GameRng(rng) = &mut *game_rng;
+
+        for y in 0..chunk_y {
+            for x in 0..chunk_x {
+                let chunk = generate_chunk(x, y, rng);
+                tilemap.chunks.push(chunk);
+            }
         }
     }
 
-    world.spawn((tilemap,));
+    resources.insert(tilemap);
+
+    Ok(())
 }
 
-pub fn draw_tilemap(world: &mut World, draw_handle: &mut RaylibMode2D<RaylibDrawHandle>) {
-    let mut query = world.query::<&Tilemap>();
-    let result = query.into_iter().nth(0);
+pub fn draw_tilemap(_world: &mut World, _draw_handle: &mut RaylibMode2D<RaylibDrawHandle>, ctx: &DrawContext) -> Result<(), EngineError> {
+    let tilemap = ctx.resources.get::<Tilemap>()?;
+    let mut render_queue = ctx.resources.get_mut::<RenderQueue>()?;
 
-    if let Some((_, tilemap)) = result {
-        tilemap.chunks.iter().for_each(|chunk| {
-            if chunk.visible {
-                chunk.tiles.iter().for_each(|tile| {
-                    draw_tile(draw_handle, &TILESET.get().unwrap(), &tile);
-                });
-            }
-        });
-    }
+    tilemap.chunks.iter().for_each(|chunk| {
+        if chunk.visible {
+            chunk.tiles.iter().for_each(|tile| {
+                queue_tile(&mut render_queue, tilemap.tileset, tile);
+            });
+        }
+    });
+
+    Ok(())
 }
 
-pub fn check_visible_tilemap_chunks(world: &mut World, draw_handle: &mut RaylibMode2D<RaylibDrawHandle>, camera: &Camera2D) {
-    let query = world.query_mut::<&mut Tilemap>();
-    let result = query.into_iter().nth(0);
-
-    if let Some((_, tilemap)) = result {
-        let cw = (draw_handle.get_screen_width() as f32) / camera.zoom;
-        let ch = (draw_handle.get_screen_height() as f32) / camera.zoom;
-        let camera_rect = Rectangle {
-            x: camera.target.x,
-            y: camera.target.y,
-            width: cw,
-            height: ch
+pub fn check_visible_tilemap_chunks(_world: &mut World, draw_handle: &mut RaylibMode2D<RaylibDrawHandle>, ctx: &DrawContext) -> Result<(), EngineError> {
+    let camera = ctx.camera;
+    let mut tilemap = ctx.resources.get_mut::<Tilemap>()?;
+
+    let cw = (draw_handle.get_screen_width() as f32) / camera.zoom;
+    let ch = (draw_handle.get_screen_height() as f32) / camera.zoom;
+    let camera_rect = Rectangle {
+        x: camera.target.x,
+        y: camera.target.y,
+        width: cw,
+        height: ch
+    };
+
+    tilemap.chunks.iter_mut().for_each(|chunk| {
+        let chunk_rect = Rectangle {
+            x: chunk.rect.x * TILE_SIZE,
+            y: chunk.rect.y * TILE_SIZE,
+            width: CHUNK_RAW_SIZE as f32,
+            height: CHUNK_RAW_SIZE as f32,
         };
 
-        tilemap.chunks.iter_mut().for_each(|mut chunk| {
-            let chunk_rect = Rectangle {
-                x: chunk.rect.x * TILE_SIZE,
-                y: chunk.rect.y * TILE_SIZE,
-                width: CHUNK_RAW_SIZE as f32,
-                height: CHUNK_RAW_SIZE as f32,
-            };
+        if are_boxes_colliding(&camera_rect, &chunk_rect) {
+            chunk.visible = true;
+        }
+    });
 
-            if are_boxes_colliding(&camera_rect, &chunk_rect) {
-                chunk.visible = true;
-            }
-        });
-    }
+    Ok(())
 }
 
-pub fn draw_tile(
-    draw_handle: &mut RaylibMode2D<RaylibDrawHandle>,
-    tileset: &Texture2D,
+pub fn queue_tile(
+    render_queue: &mut RenderQueue,
+    tileset: Handle<Texture2D>,
     tile: &Tile,
 ) {
     // TODO: Draw singleitem tile
@@ -158,10 +196,16 @@ pub fn draw_tile(
         height: TILE_SIZE,
         width: TILE_SIZE,
     };
-    draw_handle.draw_texture_rec(
-        tileset,
-        rect,
-        tile.coord * TILE_SIZE,
-        Color::WHITE
+    // Ground tiles never overlap, no need to sort them
+    render_queue.push(
+        RenderLayer::Ground,
+        0.0,
+        DrawCommand::Texture {
+            texture: tileset,
+            source: rect,
+            position: tile.coord * TILE_SIZE,
+            rotation: Rotation::Deg0,
+            tint: Color::WHITE
+        }
     );
 }
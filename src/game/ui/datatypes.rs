@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 
 use hecs::Entity;
+use raylib::prelude::Rectangle;
 
-use crate::engine::enums::GameResource;
+use crate::game::resource_registry::ResourceId;
 
 // TRAITS
 pub trait ComponentTag {}
@@ -15,10 +16,20 @@ pub struct GlobalStorageLabel;
 impl ComponentTag for ActiveTaskCountLabel {}
 
 // STRUCTS
+/// Hauler clicked last on the map, stored as a resource while the test map is up
+#[derive(Default, Clone, Copy)]
 pub struct SelectedHauler {
-    pub hauler: Entity
+    pub m_hauler: Option<Entity>,
 }
 
+/// Icons drawn in front of each entry of the global storage label: the icon rectangle in the
+/// tileset and its x offset from the start of the label text
+#[derive(Default)]
+pub struct StorageIcons {
+    pub icons: Vec<(Rectangle, f32)>,
+}
+
+#[derive(Default)]
 pub struct GlobalStorage {
-    pub resource_list: HashMap<GameResource, i32>
+    pub resource_list: HashMap<ResourceId, i32>
 }
@@ -0,0 +1,145 @@
+use hecs::World;
+use raylib::prelude::*;
+
+use crate::{
+    engine::{
+        enums::ButtonState,
+        error::EngineError,
+        input::InputState,
+        schedule::SystemContext,
+        ui::{
+            datatypes::{Button, ButtonAction, Label},
+            spawn_button, spawn_label,
+        },
+    },
+    game::{
+        actions::{Action, GameActions},
+        assets::{base_font, ui_atlas},
+        constants::{MENU_ENTRY_SPACING, TILE_SIZE},
+    },
+};
+
+const MENU_FONT_SIZE: f32 = 20.0;
+
+// STRUCTS ------
+/// Position of a button and its label in the menu of the current scene
+pub struct MenuEntry {
+    pub index: usize,
+}
+
+/// Index of the highlighted menu entry, stored as a resource and reset when a menu scene is
+/// entered
+#[derive(Default, Debug, Clone, Copy)]
+pub struct MenuSelection(pub usize);
+
+// FUNCTIONS ------
+/// Spawns a menu entry: a button from the UI atlas with `text` on its right. Entries are laid
+/// out top to bottom from `origin` in index order.
+pub fn spawn_menu_entry(
+    world: &mut World,
+    origin: Vector2,
+    index: usize,
+    text: &str,
+    atlas_tile: Vector2,
+    action: ButtonAction,
+) -> Result<(), EngineError> {
+    let position = Vector2 {
+        x: origin.x,
+        y: origin.y + index as f32 * MENU_ENTRY_SPACING,
+    };
+
+    let button = spawn_button(
+        world,
+        position,
+        Vector2::zero(),
+        0,
+        ui_atlas(),
+        atlas_tile,
+        TILE_SIZE,
+        Some(action),
+        None,
+    );
+    world
+        .insert_one(button, MenuEntry { index })
+        .map_err(|_| EngineError::NoSuchEntity(button))?;
+
+    let label = spawn_label(
+        world,
+        Vector2 {
+            x: position.x + 44.0,
+            y: position.y - 26.0,
+        },
+        Vector2::zero(),
+        0,
+        base_font(),
+        MENU_FONT_SIZE,
+        1.0,
+        Color::BLACK,
+    );
+    world
+        .insert_one(label, MenuEntry { index })
+        .map_err(|_| EngineError::NoSuchEntity(label))?;
+    set_menu_entry_text(world, index, text)?;
+
+    Ok(())
+}
+
+pub fn set_menu_entry_text(world: &mut World, index: usize, text: &str) -> Result<(), EngineError> {
+    for (_, (entry, label)) in world.query_mut::<(&MenuEntry, &mut Label)>() {
+        if entry.index == index {
+            label.text = text.to_string();
+        }
+    }
+
+    Ok(())
+}
+
+/// The menu up and down actions move the selection, menu confirm runs the selected entry. Hovering an entry with
+/// the mouse selects it, clicks go through the regular button handling.
+pub fn navigate_menu(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let input = ctx.resources.get::<InputState>()?.clone();
+    let actions = ctx.resources.get::<GameActions>()?.clone();
+    let entry_count = world
+        .query::<&MenuEntry>()
+        .with::<Button>()
+        .iter()
+        .count();
+    if entry_count == 0 {
+        return Ok(());
+    }
+
+    let mut selected = ctx.resources.get::<MenuSelection>()?.0;
+    for (_, (entry, button)) in world.query::<(&MenuEntry, &Button)>().iter() {
+        if button.state != ButtonState::Normal {
+            selected = entry.index;
+        }
+    }
+    if actions.is_pressed(&input, Action::MenuDown) {
+        selected = (selected + 1) % entry_count;
+    }
+    if actions.is_pressed(&input, Action::MenuUp) {
+        selected = (selected + entry_count - 1) % entry_count;
+    }
+    ctx.resources.get_mut::<MenuSelection>()?.0 = selected;
+
+    let mut m_action: Option<ButtonAction> = None;
+    for (_, (entry, button)) in world.query_mut::<(&MenuEntry, &mut Button)>() {
+        if entry.index == selected {
+            if button.state == ButtonState::Normal {
+                button.state = ButtonState::Hovered;
+            }
+            m_action = button.action;
+        }
+    }
+    for (_, (entry, label)) in world.query_mut::<(&MenuEntry, &mut Label)>() {
+        label.color = if entry.index == selected { Color::DARKBLUE } else { Color::BLACK };
+    }
+
+    if actions.is_released(&input, Action::MenuConfirm) {
+        if let Some(action) = m_action {
+            action(world, ctx)?;
+        }
+    }
+
+    Ok(())
+}
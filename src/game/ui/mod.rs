@@ -1,2 +1,3 @@
-pub mod step;
 pub mod datatypes;
+pub mod menu;
+pub mod step;
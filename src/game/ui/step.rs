@@ -2,36 +2,44 @@ use std::collections::HashMap;
 
 use hecs::{Entity, World};
 use raylib::{
-    prelude::Vector2,
-    text::{measure_text_ex, Font},
+    prelude::{Rectangle, Vector2},
+    text::measure_text_ex,
 };
 
 use crate::{
     engine::{
-        enums::GameResource,
+        assets::Assets,
+        error::EngineError,
+        schedule::SystemContext,
         ui::datatypes::{Label, UIElement},
     },
     game::{
         buildings::datatypes::StorageSpace,
+        resource_registry::{ResourceId, ResourceRegistry},
         tasks::{HaulTask, OpenTasks},
     },
 };
 
 use super::datatypes::{
-    ActiveTaskCountLabel, GlobalStorage, GlobalStorageLabel, IdleTaskCountLabel,
+    ActiveTaskCountLabel, GlobalStorage, GlobalStorageLabel, IdleTaskCountLabel, StorageIcons,
 };
 
-pub fn update_ui(world: &mut World, font: &Font) -> Result<(), String> {
-    update_active_haul_task_count(world, font)?;
-    update_idle_haul_task_count(world, font)?;
-    update_global_storage(world, font)?;
+// FUNCTIONS
+/// Measures `text` with the label's font settings. Headless runs have no assets, so nothing is
+/// measured and the label keeps a zero offset.
+fn measure_label_text(ctx: &SystemContext, text: &str, label: &Label) -> Result<Vector2, EngineError> {
+    if !ctx.resources.contains::<Assets>() {
+        return Ok(Vector2::zero());
+    }
 
-    Ok(())
+    let assets = ctx.resources.get::<Assets>()?;
+    let font = assets.get(label.font)?;
+
+    Ok(measure_text_ex(font, text, label.font_size, label.spacing))
 }
 
-// FUNCTIONS
-pub fn update_global_storage(world: &mut World, font: &Font) -> Result<(), String>{
-    let mut current_storage: HashMap<GameResource, i32> = HashMap::new();
+pub fn update_global_storage(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let mut current_storage: HashMap<ResourceId, i32> = HashMap::new();
     {
         let mut query = world.query::<&StorageSpace>();
         query.into_iter().for_each(|(_, storage)| {
@@ -49,12 +57,8 @@ pub fn update_global_storage(world: &mut World, font: &Font) -> Result<(), Strin
         });
     }
 
-    {
-        let m_global_storage = world.query_mut::<&mut GlobalStorage>().into_iter().nth(0);
-        if let Some((_, mut global_storage)) = m_global_storage {
-            global_storage.resource_list = current_storage.clone();
-        }
-    }
+    ctx.resources.get_mut::<GlobalStorage>()?.resource_list = current_storage.clone();
+    let (entries, weight) = global_storage_entries(&*ctx.resources.get::<ResourceRegistry>()?, &current_storage)?;
 
     let mut labels_to_update: Vec<Entity> = vec![];
 
@@ -71,13 +75,29 @@ pub fn update_global_storage(world: &mut World, font: &Font) -> Result<(), Strin
 
     for entity in labels_to_update.into_iter() {
         let mut _half_text_size: Vector2 = Vector2::zero();
-        let text = format!("{:?}", current_storage);
+        let mut icons: Vec<(Rectangle, f32)> = vec![];
         {
-            let mut label = world.get_mut::<Label>(entity).map_err(|_| "Component error")?;
-            _half_text_size =
-                measure_text_ex(font, &text, label.font_size.clone(), label.spacing.clone()) / 2.0;
+            let mut label = world.get_mut::<Label>(entity).map_err(EngineError::component::<Label>(entity))?;
+            // Two spaces in front of every entry leave room for its icon
+            let mut text = String::new();
+            for (icon, entry) in entries.iter() {
+                if !text.is_empty() {
+                    text.push_str(", ");
+                }
+                icons.push((*icon, measure_label_text(ctx, &text, &label)?.x));
+                text.push_str("  ");
+                text.push_str(entry);
+            }
+            if !entries.is_empty() {
+                text.push_str(&format!(" - Weight: {}", weight));
+            }
+
+            _half_text_size = measure_label_text(ctx, &text, &label)? / 2.0;
             label.text = text;
         }
+        if let Ok(mut storage_icons) = world.get_mut::<StorageIcons>(entity) {
+            storage_icons.icons = icons;
+        }
         {
             if let Ok(mut element) = world.get_mut::<UIElement>(entity) {
                 element.offset.x = -_half_text_size.x;
@@ -88,7 +108,26 @@ pub fn update_global_storage(world: &mut World, font: &Font) -> Result<(), Strin
     Ok(())
 }
 
-pub fn update_idle_haul_task_count(world: &mut World, font: &Font) -> Result<(), String>{
+/// Icon and stored amount by display name of every stored resource in registry order, along
+/// with the total weight stored
+fn global_storage_entries(
+    resources: &ResourceRegistry,
+    storage: &HashMap<ResourceId, i32>,
+) -> Result<(Vec<(Rectangle, String)>, f32), EngineError> {
+    let mut entries: Vec<(Rectangle, String)> = vec![];
+    let mut weight: f32 = 0.0;
+    for resource in resources.sorted_ids() {
+        if let Some(amount) = storage.get(resource) {
+            let definition = resources.get(resource)?;
+            entries.push((definition.icon_rect(), format!("{}: {}", definition.name, amount)));
+            weight += definition.weight * *amount as f32;
+        }
+    }
+
+    Ok((entries, weight))
+}
+
+pub fn update_idle_haul_task_count(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
     let mut labels_to_update: Vec<Entity> = vec![];
 
     {
@@ -103,23 +142,16 @@ pub fn update_idle_haul_task_count(world: &mut World, font: &Font) -> Result<(),
     }
 
     for entity in labels_to_update.into_iter() {
-        let mut _haul_count: usize = 0;
-        {
-            let mut query = world.query::<&OpenTasks>();
-            query.into_iter().for_each(|(_, open_tasks)| {
-                _haul_count = open_tasks.haul_list.len();
-            });
-        }
+        let _haul_count = ctx.resources.get::<OpenTasks>()?.haul_list.len();
         let mut _half_text_size: Vector2 = Vector2::zero();
         let text = format!("Haul tasks awaiting hauler: {:?}", _haul_count);
         {
-            let mut label = world.get_mut::<Label>(entity).map_err(|_| "Component error")?;
-            _half_text_size =
-                measure_text_ex(font, &text, label.font_size.clone(), label.spacing.clone());
+            let mut label = world.get_mut::<Label>(entity).map_err(EngineError::component::<Label>(entity))?;
+            _half_text_size = measure_label_text(ctx, &text, &label)?;
             label.text = text;
         }
         {
-            let mut element = world.get_mut::<UIElement>(entity).map_err(|_| "Component error")?;
+            let mut element = world.get_mut::<UIElement>(entity).map_err(EngineError::component::<UIElement>(entity))?;
             element.offset.x = -_half_text_size.x;
         }
     };
@@ -127,7 +159,7 @@ pub fn update_idle_haul_task_count(world: &mut World, font: &Font) -> Result<(),
     Ok(())
 }
 
-pub fn update_active_haul_task_count(world: &mut World, font: &Font) -> Result<(), String> {
+pub fn update_active_haul_task_count(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
     let mut labels_to_update: Vec<Entity> = vec![];
 
     {
@@ -150,16 +182,41 @@ pub fn update_active_haul_task_count(world: &mut World, font: &Font) -> Result<(
         let mut _half_text_size: Vector2 = Vector2::zero();
         let text = format!("Haul tasks running: {:?}", _haul_count);
         {
-            let mut label = world.get_mut::<Label>(entity).map_err(|_| "Component error")?;
-            _half_text_size =
-                measure_text_ex(font, &text, label.font_size.clone(), label.spacing.clone());
+            let mut label = world.get_mut::<Label>(entity).map_err(EngineError::component::<Label>(entity))?;
+            _half_text_size = measure_label_text(ctx, &text, &label)?;
             label.text = text;
         }
         {
-            let mut element = world.get_mut::<UIElement>(entity).map_err(|_| "Component error")?;
+            let mut element = world.get_mut::<UIElement>(entity).map_err(EngineError::component::<UIElement>(entity))?;
             element.offset.x = -_half_text_size.x;
         }
     };
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn global_storage_entries_follow_registry_order_and_add_up_weights() {
+        let resources = ResourceRegistry::parse(
+            "test",
+            r#"{
+                "stone": { "name": "Stone", "icon_atlas_tile": [7, 5], "stack_size": 10, "category": "Raw", "weight": 4.0 },
+                "bread": { "name": "Bread", "icon_atlas_tile": [7, 6], "stack_size": 15, "category": "Food", "weight": 0.5 }
+            }"#,
+        )
+        .unwrap();
+        let storage: HashMap<ResourceId, i32> =
+            [(ResourceId::new("bread"), 4), (ResourceId::new("stone"), 10)].into_iter().collect();
+
+        let (entries, weight) = global_storage_entries(&resources, &storage).unwrap();
+
+        let texts: Vec<&str> = entries.iter().map(|(_, text)| text.as_str()).collect();
+        assert_eq!(texts, vec!["Stone: 10", "Bread: 4"]);
+        assert_eq!(entries[0].0, resources.get(&ResourceId::new("stone")).unwrap().icon_rect());
+        assert_eq!(weight, 42.0);
+    }
+}
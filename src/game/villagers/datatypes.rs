@@ -1,7 +1,7 @@
 use hecs::{Entity, World};
 use raylib::prelude::*;
 
-use crate::engine::enums::*;
+use crate::{engine::{enums::*, events::Events}, game::{events::GameEvent, resource_registry::ResourceId}};
 
 // TAGS ------
 pub struct Hauler;
@@ -31,8 +31,17 @@ impl IdleInfo {
 pub struct IdleState;
 
 impl IdleState {
-    pub fn change_state_to(world: &mut World, entity: Entity, new_state: VillagerState) {
-        println!("Changed state to {:?}", new_state);
+    pub fn change_state_to(
+        world: &mut World,
+        events: &mut Events<GameEvent>,
+        entity: Entity,
+        new_state: VillagerState,
+    ) {
+        events.send(GameEvent::VillagerStateChanged {
+            villager: entity,
+            from: VillagerState::Idle,
+            to: new_state.clone(),
+        });
         match new_state {
             VillagerState::Loading => world.insert_one(entity, LoadingState).unwrap(),
             VillagerState::Working => world.insert_one(entity, WorkingState).unwrap(),
@@ -45,8 +54,17 @@ impl IdleState {
 pub struct LoadingState;
 
 impl LoadingState {
-    pub fn change_state_to(world: &mut World, entity: Entity, new_state: VillagerState) {
-        println!("Changed state to {:?}", new_state);
+    pub fn change_state_to(
+        world: &mut World,
+        events: &mut Events<GameEvent>,
+        entity: Entity,
+        new_state: VillagerState,
+    ) {
+        events.send(GameEvent::VillagerStateChanged {
+            villager: entity,
+            from: VillagerState::Loading,
+            to: new_state.clone(),
+        });
         match new_state {
             VillagerState::Idle => world.insert_one(entity, IdleState).unwrap(),
             VillagerState::Carrying => world.insert_one(entity, CarryingState).unwrap(),
@@ -59,8 +77,17 @@ impl LoadingState {
 pub struct CarryingState;
 
 impl CarryingState {
-    pub fn change_state_to(world: &mut World, entity: Entity, new_state: VillagerState) {
-        println!("Changed state to {:?}", new_state);
+    pub fn change_state_to(
+        world: &mut World,
+        events: &mut Events<GameEvent>,
+        entity: Entity,
+        new_state: VillagerState,
+    ) {
+        events.send(GameEvent::VillagerStateChanged {
+            villager: entity,
+            from: VillagerState::Carrying,
+            to: new_state.clone(),
+        });
         match new_state {
             VillagerState::Idle => world.insert_one(entity, IdleState).unwrap(),
             _ => {}
@@ -71,12 +98,12 @@ impl CarryingState {
 
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct GameItem {
-    pub resource: GameResource,
+    pub resource: ResourceId,
     pub amount: i32
 }
 
 impl GameItem {
-    pub fn new(resource: GameResource, amount: i32) -> GameItem {
+    pub fn new(resource: ResourceId, amount: i32) -> GameItem {
         GameItem { resource , amount }
     }
 }
@@ -3,13 +3,20 @@ use raylib::prelude::*;
 
 use crate::{
     game::{
+        assets::tileset,
+        animations::villager_animator,
+        events::GameEvent,
         tasks::HaulTask, 
-        constants::{HAULER_CAPACITY, TILE_SIZE, DEFAULT_IDLE_POINT}
+        config::GameConfig,
+        constants::{TILE_SIZE, DEFAULT_IDLE_POINT, VILLAGER_COLLISION_MASK}
     }, 
     engine::{
-        enums::{VillagerState, CollisionType}, 
+        enums::{VillagerState, CollisionType, CollisionLayer}, 
         datatypes::Sprite, 
         collision::{CollisionBox, BodyCollision, TriggerCollision},
+        error::EngineError,
+        events::Events,
+        schedule::SystemContext,
     }
 };
 
@@ -27,6 +34,7 @@ pub fn spawn_hauler(
     opt_idle_point: Option<Vector2>
 ) -> Entity {
     let sprite = Sprite::new(
+        tileset(),
         position,
         atlas_tile,
         TILE_SIZE
@@ -49,10 +57,10 @@ pub fn spawn_hauler(
         IdleInfo::default(idle_point),
         IdleState,
         Backpack::default(),
+        villager_animator(sprite.rect),
         sprite, 
-        CollisionBox {
-            rect
-    }));
+        CollisionBox::new(rect, CollisionLayer::Villager, VILLAGER_COLLISION_MASK)
+    ));
 
     match collision_type {
         CollisionType::Body => {
@@ -76,69 +84,71 @@ pub fn spawn_hauler(
 }
 
 
-pub fn update_loading_state(world: &mut World, delta: f32) {
+pub fn update_loading_state(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let delta = ctx.delta;
+    let speed = ctx.resources.get::<GameConfig>()?.villager_speed;
     let origin_missing_haulers: Vec<Entity> = vec![];
 
     let query = world.query_mut::<(&HaulTask, &mut Sprite)>().with::<LoadingState>();
     query.into_iter().for_each(|(_, (task, sprite))| {
         if let Some(target_position) = task.origin_position {
             if (target_position - sprite.position).length() > 1.0 {
-                move_villager(target_position, sprite, delta);
+                move_villager(target_position, sprite, speed, delta);
             }
         }
     });
 
+    let mut events = ctx.resources.get_mut::<Events<GameEvent>>()?;
     origin_missing_haulers.into_iter().for_each(|hauler| {
-        LoadingState::change_state_to(world, hauler, VillagerState::Idle);
+        LoadingState::change_state_to(world, &mut events, hauler, VillagerState::Idle);
     });
+
+    Ok(())
 }
 
-pub fn update_carrying_state(world: &mut World, delta: f32) {
+pub fn update_carrying_state(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let delta = ctx.delta;
+    let speed = ctx.resources.get::<GameConfig>()?.villager_speed;
     {
         let query = world.query_mut::<(&mut HaulTask, &mut Sprite)>().with::<CarryingState>();
         query.into_iter().for_each(|(_, (task, sprite))| {
             if let Some(target_position) = task.destination_position {
                 if (target_position - sprite.position).length() > 1.0 {
-                    move_villager(target_position, sprite, delta);
+                    move_villager(target_position, sprite, speed, delta);
                 }
             }
         });
     }
+
+    Ok(())
 }
 
-pub fn receive_resource(world: &mut World, hauler: Entity) -> Option<GameItem> {
-    let mut m_item: Option<GameItem> = None;
+/// Item a hauler picks up for its task
+fn haul_task_item(world: &World, hauler: Entity) -> Option<GameItem> {
+    world
+        .get::<HaulTask>(hauler)
+        .ok()
+        .map(|task| GameItem::new(task.resource.clone(), task.amount))
+}
 
-    {
-        let result = world.get::<HaulTask>(hauler);
-        if let Ok(task) = result {
-            m_item = Some(GameItem::new(task.resource, HAULER_CAPACITY));
-        }
-    }
+pub fn receive_resource(world: &mut World, events: &mut Events<GameEvent>, hauler: Entity) -> Option<GameItem> {
+    let m_item = haul_task_item(world, hauler);
     {
         let backpack = Backpack{ item: m_item.clone() };
         world.exchange_one::<Backpack, Backpack>(hauler, backpack).unwrap();
-        LoadingState::change_state_to(world, hauler, VillagerState::Carrying);
+        LoadingState::change_state_to(world, events, hauler, VillagerState::Carrying);
     }
 
-    return m_item;
+    m_item
 }
 
-pub fn deliver_resource(world: &mut World, hauler: Entity) -> Option<GameItem> {
-    let mut m_item: Option<GameItem> = None;
-
-    {
-        let result = world.get::<HaulTask>(hauler);
-        if let Ok(task) = result {
-            m_item = Some(GameItem::new(task.resource, HAULER_CAPACITY));
-        }
-    }
-    {
-        let backpack = Backpack{ item: None };
-        world.exchange_one::<Backpack, Backpack>(hauler, backpack).unwrap();
-        world.remove_one::<HaulTask>(hauler).unwrap();
-        CarryingState::change_state_to(world, hauler, VillagerState::Idle);
-    }
+/// Empties the hauler backpack and completes its task, returning what it carried
+pub fn deliver_resource(world: &mut World, events: &mut Events<GameEvent>, hauler: Entity) -> Option<GameItem> {
+    let backpack = Backpack{ item: None };
+    let carried = world.exchange_one::<Backpack, Backpack>(hauler, backpack).unwrap();
+    let task = world.remove_one::<HaulTask>(hauler).unwrap();
+    events.send(GameEvent::TaskCompleted { task_id: task.id, hauler });
+    CarryingState::change_state_to(world, events, hauler, VillagerState::Idle);
 
-    return m_item;
+    carried.item
 }
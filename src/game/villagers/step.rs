@@ -1,30 +1,63 @@
-use hecs::World;
+use hecs::{Entity, World};
 use rand::Rng;
 use raylib::prelude::*;
 
-use crate::engine::{collision::CollisionBox, datatypes::Sprite};
+use crate::{
+    engine::{
+        collision::CollisionBox,
+        datatypes::{GameRng, Sprite},
+        enums::{VillagerState, VillagerType},
+        error::EngineError,
+        schedule::SystemContext,
+    },
+    game::config::GameConfig,
+};
 
-use super::{hauler::{update_loading_state, update_carrying_state}, datatypes::{IdleInfo, IdleState}};
+use super::datatypes::{CarryingState, Hauler, IdleInfo, IdleState, LoadingState, WorkingState};
 
-pub fn update_villagers(world: &mut World, delta: f32) {
-    update_collision_box_position(world);
-    update_idle_state(world, delta);
-    update_loading_state(world, delta);
-    update_carrying_state(world, delta);
+/// What kind of villager `entity` is, if it's one
+pub fn villager_type(world: &World, entity: Entity) -> Option<VillagerType> {
+    if world.get::<Hauler>(entity).is_ok() {
+        Some(VillagerType::Hauler)
+    } else {
+        None
+    }
+}
+
+/// The state component `entity` carries, if any
+pub fn villager_state(world: &World, entity: Entity) -> Option<VillagerState> {
+    let entity_ref = world.entity(entity).ok()?;
+    if entity_ref.has::<IdleState>() {
+        Some(VillagerState::Idle)
+    } else if entity_ref.has::<LoadingState>() {
+        Some(VillagerState::Loading)
+    } else if entity_ref.has::<CarryingState>() {
+        Some(VillagerState::Carrying)
+    } else if entity_ref.has::<WorkingState>() {
+        Some(VillagerState::Working)
+    } else {
+        None
+    }
 }
 
-pub fn update_idle_state(world: &mut World, delta: f32) {
+pub fn update_idle_state(world: &mut World, ctx: &mut SystemContext) -> Result<(), EngineError> {
+    let delta = ctx.delta;
+    let speed = ctx.resources.get::<GameConfig>()?.villager_speed;
+    let mut game_rng = ctx.resources.get_mut::<GameRng>()?;
+    let GameRng(rng) = &mut *game_rng;
 
-    let idle_query = world.query_mut::<(&mut IdleInfo, &mut Sprite)>().with::<IdleState>();
+    let mut idle_query = world.query::<(&mut IdleInfo, &mut Sprite)>().with::<IdleState>();
     idle_query.into_iter().for_each(|(_, (idle_state, sprite))| {
         idle_state_tick(idle_state, delta);
         if idle_state.idle_timer <= 0.0 {
-            get_new_target(idle_state);
+            get_new_target(idle_state, rng);
         }
         if (idle_state.target_position - sprite.position).length() > 1.0 {
-            move_villager(idle_state.target_position, sprite, delta);
+            move_villager(idle_state.target_position, sprite, speed, delta);
         }
     });
+
+    Ok(())
 }
 
 pub fn idle_state_tick(idle_state: &mut IdleInfo, delta: f32) {
@@ -33,8 +66,7 @@ pub fn idle_state_tick(idle_state: &mut IdleInfo, delta: f32) {
     }
 }
 
-pub fn get_new_target(idle_state: &mut IdleInfo) {
-        let mut rng = rand::thread_rng();
+pub fn get_new_target(idle_state: &mut IdleInfo, rng: &mut impl Rng) {
         let rand_x: f32 = rng.gen_range(
             (idle_state.idle_point.x - idle_state.radius)..(idle_state.idle_point.x + idle_state.radius)
         );
@@ -45,16 +77,18 @@ pub fn get_new_target(idle_state: &mut IdleInfo) {
         idle_state.idle_timer = rng.gen_range(idle_state.timer_range.0..idle_state.timer_range.1);
 }
 
-pub fn move_villager(target_position: Vector2, sprite: &mut Sprite, delta: f32) {
+pub fn move_villager(target_position: Vector2, sprite: &mut Sprite, speed: f32, delta: f32) {
     let vector = (target_position - sprite.position).normalized();
-    sprite.position += vector * 50.0 * delta;
+    sprite.position += vector * speed * delta;
 }
 
-pub fn update_collision_box_position(world: &mut World) {
+pub fn update_collision_box_position(world: &mut World, _ctx: &mut SystemContext) -> Result<(), EngineError> {
     let query = world.query_mut::<(&Sprite, &mut CollisionBox)>();
     query.into_iter().for_each(|(_, (sprite, collision_box))| {
         collision_box.rect.x = sprite.position.x;
         collision_box.rect.y = sprite.position.y;
     });
+
+    Ok(())
 }
 
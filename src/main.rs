@@ -7,67 +7,191 @@ extern crate log;
 
 // test
 
-use engine::{draw::engine_draw, startup::world_setup, ui::datatypes::CameraZoom};
+use std::path::Path;
+
+use engine::{
+    assets::{hot_reload_assets, Assets, AssetWatcher},
+    datatypes::AppExit,
+    draw::engine_draw,
+    enums::Stage,
+    input::{InputRecording, InputState, RecordedFiles},
+    resources::Resources,
+    schedule::{DrawContext, SystemContext},
+    startup::world_setup,
+    timestep::FixedTimestep,
+};
 use game::{
-    constants::{FONT_PATH, SCREEN_HEIGHT, SCREEN_WIDTH, TILESET_PATH, TILE_SIZE, UI_ATLAS_PATH},
-    draw::draw_game,
-    input::handle_input,
-    scenes::{ActiveScene, Scene},
-    startup::game_setup,
-    step::update_game,
+    actions::{default_actions, GameActions},
+    bench::run_collision_bench,
+    cli::parse_cli_args,
+    config::GameConfig,
+    constants::{
+        ASSET_RELOAD_INTERVAL, BINDINGS_PATH, CONFIG_PATH, FONT_PATH, RECORDED_BINDINGS, RECORDED_CONFIG,
+        SIMULATION_TICK_RATE, TILESET_PATH, TILE_SIZE, UI_ATLAS_PATH,
+    },
+    content::{DataSources, GameContent},
+    headless::{run_headless, run_replay},
+    save::load_colony,
+    scenes::Scene,
+    startup::{insert_base_resources, new_camera},
+    step::{build_schedule, current_tick, update_events, update_game},
 };
 use hecs::World;
-use raylib::{math::Vector2, prelude::*};
 
-fn main() -> Result<(), String> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     pretty_env_logger::init();
 
+    let args: Vec<String> = std::env::args().collect();
+    let cli_options = parse_cli_args(&args)?;
+
+    // Data files go through the asset manager so windowed runs can hot reload them
+    let mut assets = Assets::new();
+    let config_path = cli_options.config_path.as_deref().unwrap_or(CONFIG_PATH);
+    let config = GameConfig::load(&mut assets, config_path, cli_options.config_path.is_some())?;
+    if cli_options.dump_config {
+        println!("{}", config.dump()?);
+        return Ok(());
+    }
+
+    let bindings_path = cli_options.bindings_path.as_deref().unwrap_or(BINDINGS_PATH);
+    let actions = GameActions::load(bindings_path, default_actions())?;
+    let content = GameContent::load(&mut assets)?;
+
+    if let Some(path) = &cli_options.replay_path {
+        return Ok(run_replay(&config, &actions, &content, path, cli_options.save_path.as_deref())?);
+    }
+
+    let seed = cli_options.seed.unwrap_or_else(rand::random);
+    info!("Simulation seed: {}", seed);
+
+    if let Some(count) = cli_options.bench_collisions {
+        return Ok(run_collision_bench(count, seed)?);
+    }
+
+    if let Some(frames) = cli_options.headless_frames {
+        return Ok(run_headless(
+            &config,
+            &actions,
+            &content,
+            frames,
+            seed,
+            cli_options.load_path.as_deref(),
+            cli_options.save_path.as_deref(),
+        )?);
+    }
+
+    // Gives players a bindings file to edit
+    if !Path::new(bindings_path).exists() {
+        if let Err(error) = actions.save(bindings_path) {
+            warn!("Couldn't write the default input bindings: {}", error);
+        }
+    }
+
     let (mut raylib_handle, thread) = raylib::init()
-        .size(SCREEN_WIDTH, SCREEN_HEIGHT)
+        .size(config.screen_width, config.screen_height)
         .title("Hecs Test")
         .build();
 
     let mut world = World::new();
+    let mut resources = Resources::new();
 
     // Set staring scene
-    world.spawn((ActiveScene {
-        scene: Scene::MainMenu,
-    },));
+    insert_base_resources(&mut resources, &config, &actions, &content, Scene::MainMenu, seed);
+    if cfg!(debug_assertions) {
+        resources.insert(AssetWatcher::new(ASSET_RELOAD_INTERVAL));
+        resources.insert(DataSources::new(&mut assets, config_path)?);
+    }
+    resources.insert(assets);
+
+    let mut schedule = build_schedule();
 
-    let font: Font = world_setup(
+    world_setup(
         &mut world,
+        &mut resources,
+        &mut schedule,
         &mut raylib_handle,
         &thread,
-        &FONT_PATH,
-        &TILESET_PATH,
-        &UI_ATLAS_PATH,
-        Some(game_setup),
+        &[TILESET_PATH, UI_ATLAS_PATH],
+        &[FONT_PATH],
     )?;
 
-    let zoom = 2.0;
+    // Saves loaded while recording are kept in the recording, so replays load the same colony
+    if cli_options.record_path.is_some() {
+        resources.insert(RecordedFiles::recording());
+    }
+    if let Some(path) = &cli_options.load_path {
+        load_colony(&mut world, &mut resources, path)?;
+    }
+
+    let mut camera = new_camera(&config);
+    let mut m_recording = cli_options
+        .record_path
+        .as_ref()
+        .map(|_| InputRecording::new(seed, cli_options.load_path.clone()));
+    // Replays run with the same config and bindings, whatever the files say where they run
+    if let Some(recording) = &mut m_recording {
+        recording.record_setting(RECORDED_CONFIG, &config)?;
+        recording.record_setting(RECORDED_BINDINGS, &actions)?;
+    }
+
+    raylib_handle.set_target_fps(config.target_fps);
+
+    let mut timestep = FixedTimestep::new(1.0 / SIMULATION_TICK_RATE as f32);
 
-    let mut camera = Camera2D {
-        offset: Vector2::zero(),
-        target: Vector2::zero(),
-        rotation: 0.0,
-        zoom,
-    };
-    world.spawn((CameraZoom(zoom),));
+    while !raylib_handle.window_should_close() && !resources.get::<AppExit>()?.requested {
+        schedule.apply_scene_transitions(&mut world, &mut resources)?;
+        let frame_time = raylib_handle.get_frame_time();
 
-    raylib_handle.set_target_fps(75);
+        hot_reload_assets(&resources, &mut raylib_handle, &thread, frame_time)?;
 
-    while !raylib_handle.window_should_close() {
-        handle_input(&mut world, &mut raylib_handle, &mut camera)?;
-        update_game(&mut world, &mut raylib_handle, &font)?;
+        let bindings = resources.get::<GameActions>()?.all_bindings();
+        let input = InputState::capture(&raylib_handle, &bindings);
+        if let Some(recording) = &mut m_recording {
+            recording.record(current_tick(&resources)?, frame_time, &input);
+        }
+        resources.insert(input);
+
+        let mut input_ctx = SystemContext {
+            delta: frame_time,
+            resources: &mut resources,
+            m_raylib_handle: Some(&mut raylib_handle),
+            m_camera: Some(&mut camera),
+        };
+        schedule.run_stage(Stage::Input, &mut world, &mut input_ctx)?;
+
+        for _ in 0..timestep.advance(frame_time) {
+            update_game(&mut world, &mut resources, &mut schedule, timestep.step)?;
+        }
+
+        let mut ui_ctx = SystemContext {
+            delta: frame_time,
+            resources: &mut resources,
+            m_raylib_handle: None,
+            m_camera: None,
+        };
+        schedule.run_stage(Stage::UI, &mut world, &mut ui_ctx)?;
+
+        let draw_ctx = DrawContext {
+            camera: &camera,
+            tile_size: TILE_SIZE,
+            resources: &resources,
+        };
         engine_draw(
             &mut world,
             &mut raylib_handle.begin_drawing(&thread),
-            &camera,
-            &font,
-            TILE_SIZE,
-            Some(draw_game),
-            None,
-        );
+            &mut schedule,
+            &draw_ctx,
+        )?;
+
+        update_events(&resources)?;
+    }
+
+    if let (Some(recording), Some(path)) = (&mut m_recording, &cli_options.record_path) {
+        recording.ticks = current_tick(&resources)?;
+        if let Some(files) = resources.remove::<RecordedFiles>() {
+            recording.files = files.into_files();
+        }
+        recording.save(path)?;
     }
 
     Ok(())